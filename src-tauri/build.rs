@@ -1,3 +1,54 @@
+#[path = "src/validate.rs"]
+mod validate;
+
+use std::collections::HashSet;
+
+const CREATURES_JSON: &str = "../src/data/creatures.json";
+const EVENTS_JSON: &str = "../src/data/events.json";
+
+/// Reject inconsistent bundled creature data at compile time instead of
+/// shipping creatures that can never be rolled.
+fn validate_creature_data() {
+    println!("cargo:rerun-if-changed={}", CREATURES_JSON);
+    println!("cargo:rerun-if-changed={}", EVENTS_JSON);
+
+    let read = |path: &str| -> serde_json::Value {
+        let text = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e));
+        serde_json::from_str(&text).unwrap_or_else(|e| panic!("Failed to parse {}: {}", path, e))
+    };
+
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+
+    let creatures = read(CREATURES_JSON);
+    let creatures = creatures
+        .as_array()
+        .expect("creatures.json must be an array");
+    for def in creatures {
+        validate::check_creature(def, "creatures.json", &mut seen, &mut errors);
+    }
+    validate::check_buckets(creatures, "creatures.json", &mut errors);
+
+    // Event-only creatures follow the same rules, except they need not fill every bucket
+    let events = read(EVENTS_JSON);
+    for event in events.as_array().expect("events.json must be an array") {
+        let source = format!("events.json[{}]", event["id"].as_str().unwrap_or("?"));
+        for def in event["creatures"].as_array().into_iter().flatten() {
+            validate::check_creature(def, &source, &mut seen, &mut errors);
+        }
+    }
+
+    if !errors.is_empty() {
+        panic!(
+            "\nCreature data failed validation ({} problem(s)):\n  {}\n",
+            errors.len(),
+            errors.join("\n  ")
+        );
+    }
+}
+
 fn main() {
+    validate_creature_data();
     tauri_build::build()
 }
@@ -0,0 +1,551 @@
+//! Optional status API for bar widgets and dashboards: a small HTTP server on
+//! 127.0.0.1 (settings `apiEnabled`, `apiPort`) with read-only JSON
+//! endpoints and a server-sent-events stream mirroring `energy-update` and
+//! `discovery`. Every request needs the token stored in
+//! `<save dir>/api-token`, as `Authorization: Bearer <token>` or, for
+//! `EventSource` clients that cannot set headers, `?token=<token>`.
+//!
+//! - `GET /energy`: pool energy and the discovery threshold
+//! - `GET /collection`: owned creatures, rarest first
+//! - `GET /history?limit=N`: recent discoveries, newest first, as `entries`.
+//!   JSON saves keep no journal, so they answer with `firstSightings`, each
+//!   owned creature's first discovery, instead.
+//! - `GET /odds`: chance of each rarity on the next discovery
+//! - `GET /events`: the event stream
+use crate::energy::ENERGY_THRESHOLD;
+use crate::events::{self, EventCalendar};
+use crate::registry::SharedRegistry;
+use crate::state::SharedState;
+use rand::Rng;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const TOKEN_FILE: &str = "api-token";
+/// App events mirrored on `/events`
+const STREAMED: [&str; 2] = ["energy-update", "discovery"];
+const DEFAULT_HISTORY: usize = 50;
+const MAX_HISTORY: usize = 1000;
+const MAX_HEAD_BYTES: usize = 8192;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Comment sent on an idle stream, so closed clients are noticed
+const KEEPALIVE: Duration = Duration::from_secs(15);
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+const SETTINGS_POLL: Duration = Duration::from_secs(1);
+
+/// Open `/events` streams
+static SUBSCRIBERS: Mutex<Vec<Sender<String>>> = Mutex::new(Vec::new());
+
+struct Context {
+    state: Arc<SharedState>,
+    registry: Arc<SharedRegistry>,
+    calendar: Arc<EventCalendar>,
+    token: String,
+}
+
+/// The API token, generated the first time it is needed.
+pub fn token() -> Result<String, String> {
+    let dir = crate::save::save_dir();
+    let path = dir.join(TOKEN_FILE);
+    if let Ok(token) = fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let mut rng = rand::thread_rng();
+    let token: String = (0..32)
+        .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0'))
+        .collect();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create save directory: {}", e))?;
+    write_private(&path, &token).map_err(|e| format!("Failed to write API token: {}", e))?;
+    Ok(token)
+}
+
+/// Write a file only the current user can read.
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// Send an app event to `/events` subscribers, if it is one that is streamed.
+pub fn publish<S: Serialize>(event: &str, payload: &S) {
+    if !STREAMED.contains(&event) {
+        return;
+    }
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|p| p.into_inner());
+    if subscribers.is_empty() {
+        return;
+    }
+    let Ok(data) = serde_json::to_string(payload) else {
+        return;
+    };
+    let message = format!("event: {}\ndata: {}\n\n", event, data);
+    subscribers.retain(|subscriber| subscriber.send(message.clone()).is_ok());
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    /// Names lowercased
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn parse(head: &str) -> Option<Self> {
+        let mut lines = head.lines();
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?;
+        if !request_line.next()?.starts_with("HTTP/") {
+            return None;
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+        let headers = lines
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+        Some(Self {
+            method,
+            path: path.to_string(),
+            query,
+            headers,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn authorized(&self, token: &str) -> bool {
+        let given = self
+            .header("authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .or_else(|| self.param("token"));
+        // Compared in full so timing does not reveal a matching prefix
+        given.is_some_and(|given| {
+            given.len() == token.len()
+                && given
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+}
+
+/// Read up to the blank line ending the request head.
+fn read_head(stream: &mut TcpStream) -> Option<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            head.truncate(end);
+            return String::from_utf8(head).ok();
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            return None;
+        }
+        let n = stream.read(&mut buf).ok().filter(|&n| n > 0)?;
+        head.extend_from_slice(&buf[..n]);
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &Value) {
+    let body = body.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+fn handle(mut stream: TcpStream, ctx: &Context) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Some(request) = read_head(&mut stream).as_deref().and_then(Request::parse) else {
+        respond(
+            &mut stream,
+            "400 Bad Request",
+            &json!({ "error": "Bad request" }),
+        );
+        return;
+    };
+    match request.method.as_str() {
+        "GET" => {}
+        "OPTIONS" => {
+            // CORS preflight for dashboards sending the Authorization header
+            let _ = write!(
+                stream,
+                "HTTP/1.1 204 No Content\r\nAccess-Control-Allow-Origin: *\r\n\
+                 Access-Control-Allow-Headers: Authorization\r\nAccess-Control-Allow-Methods: GET\r\n\
+                 Connection: close\r\n\r\n"
+            );
+            return;
+        }
+        _ => {
+            respond(
+                &mut stream,
+                "405 Method Not Allowed",
+                &json!({ "error": "Only GET is supported" }),
+            );
+            return;
+        }
+    }
+    if !request.authorized(&ctx.token) {
+        respond(
+            &mut stream,
+            "401 Unauthorized",
+            &json!({ "error": "Missing or wrong token" }),
+        );
+        return;
+    }
+    if request.path == "/events" {
+        stream_events(stream);
+        return;
+    }
+    match route(&request, ctx) {
+        Some(Ok(body)) => respond(&mut stream, "200 OK", &body),
+        Some(Err(e)) => respond(
+            &mut stream,
+            "500 Internal Server Error",
+            &json!({ "error": e }),
+        ),
+        None => respond(
+            &mut stream,
+            "404 Not Found",
+            &json!({ "error": format!("No such endpoint: {}", request.path) }),
+        ),
+    }
+}
+
+/// `/history`: the storage journal, else first sightings from the state.
+fn history(request: &Request, ctx: &Context) -> Result<Value, String> {
+    let limit = request
+        .param("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_HISTORY)
+        .clamp(1, MAX_HISTORY);
+    // Queried before taking the state lock, so a slow query can't stall
+    // the energy loop
+    if let Some(entries) = crate::save::journal(limit)? {
+        return Ok(json!({ "entries": entries }));
+    }
+    let guard = ctx.state.lock().map_err(|e| e.to_string())?;
+    Ok(json!({ "firstSightings": crate::save::first_sightings(&guard, limit) }))
+}
+
+/// The JSON for a read endpoint, `None` for unknown paths.
+fn route(request: &Request, ctx: &Context) -> Option<Result<Value, String>> {
+    if request.path == "/history" {
+        return Some(history(request, ctx));
+    }
+    let guard = match ctx.state.lock() {
+        Ok(guard) => guard,
+        Err(e) => return Some(Err(e.to_string())),
+    };
+    let pool = |name: &str| guard.pool_energy.get(name).copied().unwrap_or(0);
+    let body = match request.path.as_str() {
+        "/energy" => Ok(json!({
+            "typing": pool("typing"),
+            "click": pool("click"),
+            "audio": pool("audio"),
+            "threshold": ENERGY_THRESHOLD,
+        })),
+        "/collection" => {
+            let registry = ctx.registry.read().unwrap_or_else(|p| p.into_inner());
+            let creatures: Vec<Value> = crate::report::rows(&guard, &registry)
+                .into_iter()
+                .map(|row| {
+                    json!({
+                        "id": row.id,
+                        "name": row.name,
+                        "pool": row.pool,
+                        "rarity": row.rarity,
+                        "count": row.count,
+                        "firstSeen": row.first_seen,
+                        "event": row.event,
+                    })
+                })
+                .collect();
+            Ok(json!({
+                "totalDiscoveries": guard.total_discoveries,
+                "creatures": creatures,
+            }))
+        }
+        "/odds" => {
+            let active = ctx.calendar.active_now();
+            let boost = events::rarity_boost(&active);
+            let odds: serde_json::Map<String, Value> =
+                crate::rarity::tier_odds(&guard.pity, &boost)
+                    .iter()
+                    .map(|(rarity, chance)| (rarity.as_str().to_string(), json!(chance)))
+                    .collect();
+            Ok(json!({
+                "odds": odds,
+                "pity": guard.pity,
+                "activeEvents": active.iter().map(|e| &e.id).collect::<Vec<_>>(),
+            }))
+        }
+        _ => return None,
+    };
+    Some(body)
+}
+
+/// Hold the connection open and forward published events until the client
+/// goes away or the API is switched off.
+fn stream_events(mut stream: TcpStream) {
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\
+                Access-Control-Allow-Origin: *\r\nConnection: keep-alive\r\n\r\n";
+    if stream.write_all(head.as_bytes()).is_err() {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    SUBSCRIBERS
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .push(sender);
+    loop {
+        let message = match receiver.recv_timeout(KEEPALIVE) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if stream
+            .write_all(message.as_bytes())
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn bind(port: u16) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Serve the API whenever `apiEnabled` is on, rebinding when the port (or
+/// the profile, and with it the settings) changes.
+pub fn start(state: Arc<SharedState>, registry: Arc<SharedRegistry>, calendar: Arc<EventCalendar>) {
+    std::thread::spawn(move || {
+        let mut server: Option<(u16, TcpListener, Arc<Context>)> = None;
+        // Port that failed to bind, not retried until the settings change
+        let mut failed: Option<u16> = None;
+        let mut last_check: Option<Instant> = None;
+        loop {
+            if last_check.is_none_or(|t| t.elapsed() >= SETTINGS_POLL) {
+                last_check = Some(Instant::now());
+                let wanted = {
+                    let guard = state.lock().unwrap_or_else(|p| p.into_inner());
+                    guard
+                        .settings
+                        .api_enabled
+                        .then_some(guard.settings.api_port)
+                };
+                let current = server.as_ref().map(|(port, _, _)| *port);
+                if wanted != current && wanted != failed {
+                    if server.take().is_some() {
+                        // Ends open streams
+                        SUBSCRIBERS
+                            .lock()
+                            .unwrap_or_else(|p| p.into_inner())
+                            .clear();
+                        eprintln!("API: stopped");
+                    }
+                    failed = None;
+                    if let Some(port) = wanted {
+                        match token().and_then(|token| {
+                            let listener = bind(port)
+                                .map_err(|e| format!("cannot listen on port {}: {}", port, e))?;
+                            Ok((listener, token))
+                        }) {
+                            Ok((listener, token)) => {
+                                eprintln!("API: listening on http://127.0.0.1:{}", port);
+                                let ctx = Arc::new(Context {
+                                    state: state.clone(),
+                                    registry: registry.clone(),
+                                    calendar: calendar.clone(),
+                                    token,
+                                });
+                                server = Some((port, listener, ctx));
+                            }
+                            Err(e) => {
+                                eprintln!("API: {}", e);
+                                failed = Some(port);
+                            }
+                        }
+                    }
+                }
+                if wanted.is_none() {
+                    failed = None;
+                }
+            }
+
+            let Some((_, listener, ctx)) = &server else {
+                std::thread::sleep(SETTINGS_POLL);
+                continue;
+            };
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let ctx = ctx.clone();
+                    std::thread::spawn(move || handle(stream, &ctx));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+                Err(e) => {
+                    eprintln!("API: accept failed: {}", e);
+                    std::thread::sleep(ACCEPT_POLL);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::CreatureRegistry;
+    use crate::state::GameState;
+    use std::io::{BufRead, BufReader};
+    use std::sync::RwLock;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    /// Serve `connections` requests on a free port with a fresh state.
+    fn serve(connections: usize) -> u16 {
+        let mut state = GameState::default();
+        state.pool_energy.insert("typing".to_string(), 12);
+        let calendar = Arc::new(EventCalendar::default());
+        let registry = CreatureRegistry {
+            builtin: Vec::new(),
+            packs: Vec::new(),
+            calendar: calendar.clone(),
+        };
+        let ctx = Context {
+            state: Arc::new(Mutex::new(state)),
+            registry: Arc::new(RwLock::new(registry)),
+            calendar,
+            token: TOKEN.to_string(),
+        };
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                handle(stream.unwrap(), &ctx);
+            }
+        });
+        port
+    }
+
+    fn get(port: u16, target: &str, auth: Option<&str>) -> (String, String) {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let auth = auth.map_or(String::new(), |t| {
+            format!("Authorization: Bearer {}\r\n", t)
+        });
+        write!(stream, "GET {} HTTP/1.1\r\nHost: x\r\n{}\r\n", target, auth).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    #[test]
+    fn parses_requests_and_checks_tokens() {
+        let head = "GET /odds?limit=5&token=abc HTTP/1.1\r\nAuthorization: Bearer xyz";
+        let request = Request::parse(head).unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("GET", "/odds")
+        );
+        assert_eq!(request.param("limit"), Some("5"));
+        assert!(request.authorized("xyz"));
+        assert!(!request.authorized("abc"));
+        let by_query = Request::parse("GET /events?token=abc HTTP/1.1").unwrap();
+        assert!(by_query.authorized("abc"));
+        assert!(!by_query.authorized("abcd"));
+        assert!(Request::parse("GET /").is_none());
+    }
+
+    #[test]
+    fn serves_json_only_with_the_token() {
+        let port = serve(4);
+        let (status, _) = get(port, "/energy", None);
+        assert_eq!(status, "HTTP/1.1 401 Unauthorized");
+        let (status, _) = get(port, "/energy", Some("wrong"));
+        assert_eq!(status, "HTTP/1.1 401 Unauthorized");
+
+        let (status, body) = get(port, "/energy", Some(TOKEN));
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let energy: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(energy["typing"], 12);
+        assert_eq!(energy["threshold"], ENERGY_THRESHOLD);
+
+        let (status, body) = get(port, &format!("/odds?token={}", TOKEN), None);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let odds: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(odds["odds"]["legendary"], 0.005);
+    }
+
+    #[test]
+    fn streams_published_events() {
+        let port = serve(1);
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        write!(stream, "GET /events?token={} HTTP/1.1\r\n\r\n", TOKEN).unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line.trim_end(), "HTTP/1.1 200 OK");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while SUBSCRIBERS.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "stream never subscribed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        publish("settings-changed", &json!({}));
+        publish("discovery", &json!({ "creatureId": "fish" }));
+        let mut lines = reader.lines().map(Result::unwrap);
+        assert!(lines.by_ref().any(|l| l == "event: discovery"));
+        assert_eq!(
+            lines.next().as_deref(),
+            Some(r#"data: {"creatureId":"fish"}"#)
+        );
+    }
+}
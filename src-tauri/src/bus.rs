@@ -0,0 +1,351 @@
+//! `org.asciireef.Reef` on the Linux session bus, for shell scripts
+//! (`gdbus call`) and GNOME Shell extensions. Methods go through the same
+//! functions as `ctl`; discoveries are broadcast as a signal.
+//!
+//! - `Show()`, `Hide()`, `Toggle()`: the aquarium window
+//! - `SetSize(u preset)`: size preset, 0 = Small as in the tray
+//! - `GetStats() -> a{su}`: discoveries, owned and known species, energy per
+//!   pool and the discovery threshold
+//! - signal `Discovery(s creature_id, s rarity, b is_new)`
+use crate::ctl::Command;
+use crate::energy::ENERGY_THRESHOLD;
+use crate::registry::{CreatureRegistry, SharedRegistry};
+use crate::state::{GameState, SharedState};
+use dbus::blocking::Connection;
+use dbus::channel::{MatchingReceiver, Sender as _};
+use dbus::message::MatchRule;
+use dbus::strings::ErrorName;
+use dbus::Message;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const BUS_NAME: &str = "org.asciireef.Reef";
+pub const PATH: &str = "/org/asciireef/Reef";
+pub const INTERFACE: &str = "org.asciireef.Reef";
+const INTROSPECTABLE: &str = "org.freedesktop.DBus.Introspectable";
+const ERROR: &str = "org.asciireef.Reef.Error";
+/// How often queued signals are sent while the bus is quiet
+const POLL: Duration = Duration::from_millis(100);
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.asciireef.Reef">
+    <method name="Show"/>
+    <method name="Hide"/>
+    <method name="Toggle"/>
+    <method name="SetSize">
+      <arg name="preset" type="u" direction="in"/>
+    </method>
+    <method name="GetStats">
+      <arg name="stats" type="a{su}" direction="out"/>
+    </method>
+    <signal name="Discovery">
+      <arg name="creature_id" type="s"/>
+      <arg name="rarity" type="s"/>
+      <arg name="is_new" type="b"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// What the methods act on: the running app, or a stand-in in tests.
+trait Reef: Send + 'static {
+    fn run(&self, command: Command) -> Result<String, String>;
+    fn stats(&self) -> Result<HashMap<String, u32>, String>;
+}
+
+struct App {
+    app: tauri::AppHandle,
+    state: Arc<SharedState>,
+    registry: Arc<SharedRegistry>,
+}
+
+impl Reef for App {
+    fn run(&self, command: Command) -> Result<String, String> {
+        crate::ctl::execute(&self.app, &self.state, &self.registry, command)
+    }
+
+    fn stats(&self) -> Result<HashMap<String, u32>, String> {
+        let guard = self.state.lock().map_err(|e| e.to_string())?;
+        let registry = self.registry.read().map_err(|e| e.to_string())?;
+        Ok(stats(&guard, &registry))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Discovery {
+    creature_id: String,
+    rarity: String,
+    is_new: bool,
+}
+
+/// Discoveries waiting to be signalled, while the service is up
+static SIGNALS: Mutex<Option<Sender<Discovery>>> = Mutex::new(None);
+
+fn stats(state: &GameState, registry: &CreatureRegistry) -> HashMap<String, u32> {
+    let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+    let owned = registry
+        .all()
+        .filter(|def| state.collection.contains_key(&def.id))
+        .count();
+    let mut stats = HashMap::from([
+        ("discoveries".to_string(), state.total_discoveries),
+        ("owned".to_string(), count(owned)),
+        ("species".to_string(), count(registry.all().count())),
+        ("threshold".to_string(), ENERGY_THRESHOLD),
+    ]);
+    for pool in ["typing", "click", "audio"] {
+        let energy = state.pool_energy.get(pool).copied().unwrap_or(0);
+        stats.insert(pool.to_string(), energy);
+    }
+    stats
+}
+
+/// Queue an app event as a signal, if it is a discovery.
+pub fn publish<S: Serialize>(event: &str, payload: &S) {
+    if event != "discovery" {
+        return;
+    }
+    let signals = SIGNALS.lock().unwrap_or_else(|p| p.into_inner());
+    let (Some(tx), Ok(payload)) = (signals.as_ref(), serde_json::to_value(payload)) else {
+        return;
+    };
+    let text = |key: &str| payload[key].as_str().unwrap_or_default().to_string();
+    let _ = tx.send(Discovery {
+        creature_id: text("creatureId"),
+        rarity: text("rarity"),
+        is_new: payload["isNew"].as_bool().unwrap_or(false),
+    });
+}
+
+fn error(msg: &Message, name: &str, text: &str) -> Message {
+    let text = CString::new(text.replace('\0', "")).unwrap_or_default();
+    msg.error(&ErrorName::from(name.to_string()), &text)
+}
+
+/// The reply to one method call.
+fn reply(reef: &impl Reef, msg: &Message) -> Message {
+    if msg.path().as_deref() != Some(PATH) {
+        return error(
+            msg,
+            "org.freedesktop.DBus.Error.UnknownObject",
+            "No such object",
+        );
+    }
+    let member = msg.member().map(|m| m.to_string()).unwrap_or_default();
+    let interface = msg.interface().map(|i| i.to_string());
+    if interface.as_deref() == Some(INTROSPECTABLE) && member == "Introspect" {
+        return msg.method_return().append1(INTROSPECTION);
+    }
+    if interface.as_deref().is_some_and(|i| i != INTERFACE) {
+        return error(
+            msg,
+            "org.freedesktop.DBus.Error.UnknownInterface",
+            "No such interface",
+        );
+    }
+    let done = |result: Result<String, String>| result.map(|_| msg.method_return());
+    let result = match member.as_str() {
+        "Show" => done(reef.run(Command::Show)),
+        "Hide" => done(reef.run(Command::Hide)),
+        "Toggle" => done(reef.run(Command::Toggle)),
+        "SetSize" => match msg.read1::<u32>() {
+            Ok(preset) => done(reef.run(Command::Size(preset as usize))),
+            Err(e) => Err(format!("SetSize takes a preset index: {}", e)),
+        },
+        "GetStats" => reef.stats().map(|stats| msg.method_return().append1(stats)),
+        _ => {
+            return error(
+                msg,
+                "org.freedesktop.DBus.Error.UnknownMethod",
+                "No such method",
+            );
+        }
+    };
+    result.unwrap_or_else(|e| error(msg, ERROR, &e))
+}
+
+/// Take the well-known name; fails if another instance has it.
+fn claim(conn: &Connection) -> Result<(), String> {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::RequestNameReply;
+    match conn.request_name(BUS_NAME, false, false, true) {
+        Ok(RequestNameReply::PrimaryOwner) | Ok(RequestNameReply::AlreadyOwner) => Ok(()),
+        Ok(_) => Err(format!("{} is already taken", BUS_NAME)),
+        Err(e) => Err(format!("Failed to request {}: {}", BUS_NAME, e)),
+    }
+}
+
+/// Answer method calls and send queued signals until the bus goes away.
+fn serve(conn: Connection, reef: impl Reef, signals: Receiver<Discovery>) -> Result<(), String> {
+    conn.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            let _ = conn.send(reply(&reef, &msg));
+            true
+        }),
+    );
+    loop {
+        conn.process(POLL).map_err(|e| e.to_string())?;
+        while let Ok(discovery) = signals.try_recv() {
+            let signal = Message::new_signal(PATH, INTERFACE, "Discovery")?.append3(
+                discovery.creature_id,
+                discovery.rarity,
+                discovery.is_new,
+            );
+            let _ = conn.send(signal);
+        }
+    }
+}
+
+/// Serve the interface on the session bus, if there is one.
+pub fn start(app: tauri::AppHandle, state: Arc<SharedState>, registry: Arc<SharedRegistry>) {
+    std::thread::spawn(move || {
+        let result = Connection::new_session()
+            .map_err(|e| format!("No session bus: {}", e))
+            .and_then(|conn| {
+                claim(&conn)?;
+                let (tx, rx) = mpsc::channel();
+                *SIGNALS.lock().unwrap_or_else(|p| p.into_inner()) = Some(tx);
+                serve(
+                    conn,
+                    App {
+                        app,
+                        state,
+                        registry,
+                    },
+                    rx,
+                )
+            });
+        *SIGNALS.lock().unwrap_or_else(|p| p.into_inner()) = None;
+        if let Err(e) = result {
+            eprintln!("D-Bus: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::channel::Channel;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Stdio};
+
+    /// Answers like the app would, remembering the last size preset.
+    struct Stub(Arc<Mutex<usize>>);
+
+    impl Reef for Stub {
+        fn run(&self, command: Command) -> Result<String, String> {
+            match command {
+                Command::Size(n) if n < 8 => {
+                    *self.0.lock().unwrap() = n;
+                    Ok(String::new())
+                }
+                Command::Size(n) => Err(format!("No size preset {}", n)),
+                _ => Ok(String::new()),
+            }
+        }
+
+        fn stats(&self) -> Result<HashMap<String, u32>, String> {
+            Ok(HashMap::from([("discoveries".to_string(), 7)]))
+        }
+    }
+
+    /// A throwaway session bus, so the test never touches the user's.
+    struct PrivateBus(Child, String);
+
+    impl PrivateBus {
+        fn start() -> Option<Self> {
+            let mut child = std::process::Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address=1"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(child.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self(child, address.trim().to_string()))
+        }
+
+        fn connect(&self) -> Connection {
+            let mut channel = Channel::open_private(&self.1).unwrap();
+            channel.register().unwrap();
+            Connection::from(channel)
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    fn methods_and_signals_on_a_private_bus() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+        let server = bus.connect();
+        claim(&server).unwrap();
+        assert!(claim(&bus.connect()).is_err());
+        let size = Arc::new(Mutex::new(0));
+        let (tx, rx) = mpsc::channel();
+        let stub = Stub(size.clone());
+        std::thread::spawn(move || serve(server, stub, rx));
+
+        let client = bus.connect();
+        let proxy = client.with_proxy(BUS_NAME, PATH, Duration::from_secs(5));
+        let () = proxy.method_call(INTERFACE, "SetSize", (3u32,)).unwrap();
+        assert_eq!(*size.lock().unwrap(), 3);
+        let err = proxy
+            .method_call::<(), _, _, _>(INTERFACE, "SetSize", (99u32,))
+            .unwrap_err();
+        assert_eq!(err.name(), Some(ERROR));
+        assert_eq!(err.message(), Some("No size preset 99"));
+        let (stats,): (HashMap<String, u32>,) =
+            proxy.method_call(INTERFACE, "GetStats", ()).unwrap();
+        assert_eq!(stats["discoveries"], 7);
+        let (xml,): (String,) = proxy.method_call(INTROSPECTABLE, "Introspect", ()).unwrap();
+        assert!(xml.contains(r#"<signal name="Discovery">"#));
+
+        let received = Arc::new(Mutex::new(None));
+        let sink = received.clone();
+        client
+            .add_match(
+                MatchRule::new_signal(INTERFACE, "Discovery"),
+                move |signal: (String, String, bool), _: &Connection, _: &Message| {
+                    *sink.lock().unwrap() = Some(signal);
+                    true
+                },
+            )
+            .unwrap();
+        tx.send(Discovery {
+            creature_id: "t_common_01".to_string(),
+            rarity: "common".to_string(),
+            is_new: true,
+        })
+        .unwrap();
+        for _ in 0..50 {
+            if received.lock().unwrap().is_some() {
+                break;
+            }
+            client.process(POLL).unwrap();
+        }
+        assert_eq!(
+            *received.lock().unwrap(),
+            Some(("t_common_01".to_string(), "common".to_string(), true))
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Animated GIF or APNG clips of the tank, drawn with the JetBrains Mono
+//! font the frontend ships rather than through the webview, so they can be
+//! made headless (`--record-clip out.gif`) on CI for release screenshots.
+use crate::registry::CreatureRegistry;
+use crate::snapshot::{self, Rgb, Swimmer, Tank};
+use crate::state::GameState;
+use crate::tray::SIZE_PRESETS;
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use once_cell::sync::Lazy;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const RECORD_FLAG: &str = "--record-clip";
+pub const SECONDS_FLAG: &str = "--seconds";
+const DEFAULT_SECONDS: f64 = 5.0;
+const MAX_SECONDS: f64 = 60.0;
+const FPS: u32 = 10;
+/// Same size as the canvas renderer; cells are `FONT_SIZE + 2` tall
+const FONT_SIZE: f32 = 14.0;
+/// Fixed so clips of the same collection come out the same
+const SEED: u64 = 0x5eed;
+/// GIF quantiser speed, 1 (best) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+
+/// Background gradient stops (fraction of height, colour), close to the
+/// webview's daytime sky and water.
+const SKY: [(f64, Rgb); 2] = [(0.0, (28, 62, 110)), (1.0, (52, 110, 160))];
+const WATER: [(f64, Rgb); 3] = [
+    (0.0, (46, 128, 178)),
+    (0.3, (18, 58, 118)),
+    (1.0, (6, 20, 58)),
+];
+
+/// The webview's font, as a TTF since ab_glyph can't read WOFF2 (OFL, see
+/// `fonts/OFL.txt`)
+static FONT: Lazy<Result<FontRef<'static>, String>> = Lazy::new(|| {
+    FontRef::try_from_slice(include_bytes!("../fonts/JetBrainsMono-Regular.ttf"))
+        .map_err(|e| format!("Failed to load font: {}", e))
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipFormat {
+    Gif,
+    Apng,
+}
+
+impl ClipFormat {
+    /// The format for a file name: `.gif`, or `.png`/`.apng` for APNG.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        match ext.to_ascii_lowercase().as_str() {
+            "gif" => Ok(Self::Gif),
+            "png" | "apng" => Ok(Self::Apng),
+            _ => Err(format!(
+                "Unsupported clip format for {}: use .gif, .png or .apng",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// A rasterised glyph: coverage of `width` x `height` pixels with its top
+/// left at `left`, `top` from the cell's corner.
+struct Glyph {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    coverage: Vec<u8>,
+}
+
+struct Glyphs {
+    font: &'static FontRef<'static>,
+    cell: (usize, usize),
+    cache: HashMap<char, Glyph>,
+}
+
+impl Glyphs {
+    fn new() -> Result<Self, String> {
+        let font = FONT.as_ref().map_err(Clone::clone)?;
+        let scaled = font.as_scaled(PxScale::from(FONT_SIZE));
+        // As canvas.js measures it
+        let width = scaled.h_advance(font.glyph_id('M')).ceil() as usize;
+        Ok(Self {
+            font,
+            cell: (width, FONT_SIZE as usize + 2),
+            cache: HashMap::new(),
+        })
+    }
+
+    fn get(&mut self, ch: char) -> &Glyph {
+        let font = self.font;
+        self.cache.entry(ch).or_insert_with(|| {
+            let scaled = font.as_scaled(PxScale::from(FONT_SIZE));
+            // The canvas draws with textBaseline "top"
+            let glyph = scaled
+                .glyph_id(ch)
+                .with_scale_and_position(FONT_SIZE, ab_glyph::point(0.0, scaled.ascent()));
+            let Some(outline) = font.outline_glyph(glyph) else {
+                return Glyph {
+                    left: 0,
+                    top: 0,
+                    width: 0,
+                    height: 0,
+                    coverage: Vec::new(),
+                };
+            };
+            let bounds = outline.px_bounds();
+            let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+            let mut coverage = vec![0; width * height];
+            outline.draw(|x, y, c| {
+                if let Some(px) = coverage.get_mut(y as usize * width + x as usize) {
+                    *px = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            });
+            Glyph {
+                left: bounds.min.x as i32,
+                top: bounds.min.y as i32,
+                width,
+                height,
+                coverage,
+            }
+        })
+    }
+}
+
+fn gradient(stops: &[(f64, Rgb)], t: f64) -> Rgb {
+    let mix = |a: u8, b: u8, f: f64| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+    for pair in stops.windows(2) {
+        let ((t0, c0), (t1, c1)) = (pair[0], pair[1]);
+        if t <= t1 {
+            let f = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+            return (mix(c0.0, c1.0, f), mix(c0.1, c1.1, f), mix(c0.2, c1.2, f));
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// Draws tank frames as RGB pixels.
+struct Painter {
+    glyphs: Glyphs,
+    width: usize,
+    height: usize,
+    /// Background colour per pixel row
+    background: Vec<Rgb>,
+}
+
+impl Painter {
+    fn new(cols: usize, rows: usize) -> Result<Self, String> {
+        let glyphs = Glyphs::new()?;
+        let (cell_w, cell_h) = glyphs.cell;
+        let (width, height) = (cols * cell_w, rows * cell_h);
+        // Waterline halfway down the surface row, as in canvas.js
+        let waterline = (cell_h as f64 * 2.5) as usize;
+        let background = (0..height)
+            .map(|y| {
+                if y < waterline {
+                    gradient(&SKY, y as f64 / waterline as f64)
+                } else {
+                    let depth = height.saturating_sub(waterline).max(1);
+                    gradient(&WATER, (y - waterline) as f64 / depth as f64)
+                }
+            })
+            .collect();
+        Ok(Self {
+            glyphs,
+            width,
+            height,
+            background,
+        })
+    }
+
+    fn paint(&mut self, tank: &Tank) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height * 3);
+        for &(r, g, b) in &self.background {
+            for _ in 0..self.width {
+                pixels.extend_from_slice(&[r, g, b]);
+            }
+        }
+        let (cell_w, cell_h) = self.glyphs.cell;
+        for (row, cells) in tank.rows().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let Some(color) = cell.color.filter(|_| cell.ch != ' ') else {
+                    continue;
+                };
+                let glyph = self.glyphs.get(cell.ch);
+                let x0 = (col * cell_w) as i32 + glyph.left;
+                let y0 = (row * cell_h) as i32 + glyph.top;
+                for gy in 0..glyph.height {
+                    for gx in 0..glyph.width {
+                        let alpha = glyph.coverage[gy * glyph.width + gx] as u32;
+                        let (x, y) = (x0 + gx as i32, y0 + gy as i32);
+                        if alpha == 0
+                            || x < 0
+                            || y < 0
+                            || x as usize >= self.width
+                            || y as usize >= self.height
+                        {
+                            continue;
+                        }
+                        let i = (y as usize * self.width + x as usize) * 3;
+                        for (channel, ink) in [color.0, color.1, color.2].into_iter().enumerate() {
+                            let bg = pixels[i + channel] as u32;
+                            pixels[i + channel] =
+                                ((ink as u32 * alpha + bg * (255 - alpha)) / 255) as u8;
+                        }
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
+
+enum Encoder {
+    Gif(gif::Encoder<BufWriter<File>>),
+    Apng(png::Writer<BufWriter<File>>),
+}
+
+impl Encoder {
+    fn create(
+        path: &Path,
+        format: ClipFormat,
+        width: usize,
+        height: usize,
+        frames: usize,
+    ) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let out = BufWriter::new(file);
+        match format {
+            ClipFormat::Gif => {
+                let (w, h) = (gif_dimension(width)?, gif_dimension(height)?);
+                let mut encoder = gif::Encoder::new(out, w, h, &[])
+                    .map_err(|e| format!("Failed to start GIF: {}", e))?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(|e| format!("Failed to start GIF: {}", e))?;
+                Ok(Self::Gif(encoder))
+            }
+            ClipFormat::Apng => {
+                let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder
+                    .set_animated(frames as u32, 0)
+                    .and_then(|_| encoder.set_frame_delay(1, FPS as u16))
+                    .map_err(|e| format!("Failed to start APNG: {}", e))?;
+                let writer = encoder
+                    .write_header()
+                    .map_err(|e| format!("Failed to start APNG: {}", e))?;
+                Ok(Self::Apng(writer))
+            }
+        }
+    }
+
+    fn frame(&mut self, width: usize, height: usize, pixels: &[u8]) -> Result<(), String> {
+        match self {
+            Self::Gif(encoder) => {
+                let (w, h) = (gif_dimension(width)?, gif_dimension(height)?);
+                let mut frame = gif::Frame::from_rgb_speed(w, h, pixels, GIF_SPEED);
+                frame.delay = (100 / FPS) as u16;
+                encoder
+                    .write_frame(&frame)
+                    .map_err(|e| format!("Failed to write GIF frame: {}", e))
+            }
+            Self::Apng(writer) => writer
+                .write_image_data(pixels)
+                .map_err(|e| format!("Failed to write APNG frame: {}", e)),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Self::Gif(encoder) => encoder
+                .into_inner()
+                .and_then(|mut out| out.flush())
+                .map_err(|e| format!("Failed to finish GIF: {}", e)),
+            Self::Apng(writer) => writer
+                .finish()
+                .map_err(|e| format!("Failed to finish APNG: {}", e)),
+        }
+    }
+}
+
+fn gif_dimension(pixels: usize) -> Result<u16, String> {
+    u16::try_from(pixels).map_err(|_| format!("Clip is too large for GIF ({} px)", pixels))
+}
+
+/// Creatures laid out in a tank, ready to animate. Built under the state
+/// locks, recorded without them.
+pub struct Scene {
+    cols: usize,
+    rows: usize,
+    swimmers: Vec<Swimmer>,
+}
+
+impl Scene {
+    /// The player's tank at its current size, placed like a snapshot.
+    pub fn new(state: &GameState, registry: &CreatureRegistry) -> Self {
+        let (_, cols, rows, _, _) = SIZE_PRESETS
+            .get(state.settings.size_index)
+            .unwrap_or(&SIZE_PRESETS[0]);
+        Self::with_size(state, registry, *cols as usize, *rows as usize)
+    }
+
+    fn with_size(state: &GameState, registry: &CreatureRegistry, cols: usize, rows: usize) -> Self {
+        let mut rng = SmallRng::seed_from_u64(SEED);
+        let swimmers = snapshot::layout(state, registry, cols, rows, &mut rng)
+            .into_iter()
+            .map(|(sprite, col, row)| {
+                let mut swimmer = Swimmer::new(sprite, cols, row, &mut rng);
+                swimmer.col = col as f64;
+                swimmer
+            })
+            .collect();
+        Self {
+            cols,
+            rows,
+            swimmers,
+        }
+    }
+
+    /// Animate for `seconds` (capped at a minute) and write the clip to
+    /// `path`, as GIF or APNG by its extension.
+    pub fn record(mut self, seconds: f64, path: &Path) -> Result<(), String> {
+        let format = ClipFormat::from_path(path)?;
+        if seconds.is_nan() || seconds <= 0.0 {
+            return Err(format!("Clip length must be positive, got {}", seconds));
+        }
+        let frames = ((seconds.min(MAX_SECONDS) * FPS as f64).round() as usize).max(1);
+        let mut painter = Painter::new(self.cols, self.rows)?;
+        let (width, height) = (painter.width, painter.height);
+        let mut encoder = Encoder::create(path, format, width, height, frames)?;
+        let dt = 1.0 / FPS as f64;
+        for _ in 0..frames {
+            let mut tank = Tank::new(self.cols, self.rows);
+            for swimmer in &self.swimmers {
+                swimmer.draw(&mut tank);
+            }
+            encoder.frame(width, height, &painter.paint(&tank))?;
+            for swimmer in &mut self.swimmers {
+                swimmer.update(dt);
+                // Wrap around so the clip stays as full as it started
+                if swimmer.offscreen(self.cols) {
+                    swimmer.col = if swimmer.direction == 1 {
+                        -(swimmer.sprite.width as f64)
+                    } else {
+                        self.cols as f64
+                    };
+                }
+            }
+        }
+        encoder.finish()
+    }
+}
+
+/// Whether this launch only records a clip.
+pub fn requested() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    crate::location::flag_value(&args, RECORD_FLAG).is_some()
+}
+
+/// Handle `--record-clip <path> [--seconds N]`: record without starting the
+/// app. `None` when the flag is absent.
+pub fn record_from_args(
+    state: &GameState,
+    registry: &CreatureRegistry,
+) -> Option<Result<PathBuf, String>> {
+    let args: Vec<String> = std::env::args().collect();
+    let path = PathBuf::from(crate::location::flag_value(&args, RECORD_FLAG)?);
+    let seconds = match crate::location::flag_value(&args, SECONDS_FLAG) {
+        Some(value) => match value.parse::<f64>() {
+            Ok(seconds) => seconds,
+            Err(_) => return Some(Err(format!("Invalid {} value: {}", SECONDS_FLAG, value))),
+        },
+        None => DEFAULT_SECONDS,
+    };
+    Some(
+        Scene::new(state, registry)
+            .record(seconds, &path)
+            .map(|_| path),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::CreatureDef;
+    use crate::events::EventCalendar;
+    use crate::test_util::{owned, TempDir};
+    use std::sync::Arc;
+
+    fn setup() -> (GameState, CreatureRegistry) {
+        let mut extra = serde_json::Map::new();
+        extra.insert("category".to_string(), "swimmer".into());
+        extra.insert("frames".to_string(), serde_json::json!([["><>"], ["><=>"]]));
+        let registry = CreatureRegistry {
+            builtin: vec![CreatureDef {
+                id: "fish".to_string(),
+                pool: "typing".to_string(),
+                rarity: "common".to_string(),
+                extra,
+            }],
+            packs: Vec::new(),
+            calendar: Arc::new(EventCalendar::default()),
+        };
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("fish".to_string(), owned(3, "2025-01-01"));
+        (state, registry)
+    }
+
+    #[test]
+    fn records_gif_and_apng() {
+        let (state, registry) = setup();
+        let dir = TempDir::new("clip");
+        let (cell_w, cell_h) = Glyphs::new().unwrap().cell;
+        let (w, h) = (20 * cell_w as u32, 12 * cell_h as u32);
+
+        let gif = dir.join("reef.gif");
+        Scene::with_size(&state, &registry, 20, 12)
+            .record(0.5, &gif)
+            .unwrap();
+        let bytes = std::fs::read(&gif).unwrap();
+        assert_eq!(&bytes[..6], b"GIF89a");
+        let size = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u32;
+        assert_eq!((size(6), size(8)), (w, h));
+
+        let apng = dir.join("reef.png");
+        Scene::with_size(&state, &registry, 20, 12)
+            .record(0.5, &apng)
+            .unwrap();
+        let decoder = png::Decoder::new(File::open(&apng).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (w, h));
+        assert_eq!(info.animation_control.map(|a| a.num_frames), Some(5));
+
+        assert!(Scene::with_size(&state, &registry, 20, 12)
+            .record(1.0, &dir.join("reef.bmp"))
+            .is_err());
+    }
+
+    #[test]
+    fn glyphs_land_inside_their_cells() {
+        let mut painter = Painter::new(3, 4).unwrap();
+        let empty = painter.paint(&Tank::new(3, 4));
+        let mut tank = Tank::new(3, 4);
+        tank.draw(&["M".to_string()], 1, 1, (255, 255, 255));
+        let drawn = painter.paint(&tank);
+        let (cell_w, cell_h) = painter.glyphs.cell;
+        let changed: Vec<(usize, usize)> = (0..painter.height)
+            .flat_map(|y| (0..painter.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let i = (y * painter.width + x) * 3;
+                drawn[i..i + 3] != empty[i..i + 3]
+            })
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, y)| {
+            (cell_w..2 * cell_w).contains(&x) && (cell_h..2 * cell_h).contains(&y)
+        }));
+    }
+}
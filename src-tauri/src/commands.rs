@@ -1,6 +1,15 @@
 //! Tauri `invoke` command handlers exposed to the frontend.
 //! All state mutations go through the shared Arc<Mutex<GameState>>.
+use crate::location::Location;
+use crate::merge::{MergeDiff, MergeStrategy};
+use crate::persist::Dirty;
+use crate::registry::SharedRegistry;
+use crate::report::ReportFormat;
+use crate::save::{LoadNotice, SaveScope};
+use crate::snapshot::SnapshotFormat;
 use crate::state::SharedState;
+use crate::sync::SyncReport;
+use std::path::Path;
 use std::sync::Arc;
 use tauri::{Emitter, Manager, State};
 use tauri_plugin_autostart::ManagerExt;
@@ -16,221 +25,375 @@ pub fn get_state(
         .get_webview_window("main")
         .and_then(|window| window.is_visible().ok())
         .unwrap_or(true);
-    Ok(serde_json::json!({
+    let mut state = serde_json::json!({
         "collection": guard.collection,
         "poolEnergy": guard.pool_energy,
         "totalDiscoveries": guard.total_discoveries,
         "pity": guard.pity,
-        "position": guard.position,
-        "sendScores": guard.send_scores,
-        "soundEnabled": guard.sound_enabled,
-        "musicVolume": guard.music_volume,
-        "sizeIndex": guard.size_index,
-        "dayNightCycle": guard.day_night_cycle,
-        "messageBottlesEnabled": guard.message_bottles_enabled,
-        "messageBottlesPrompted": guard.message_bottles_prompted,
-        "closeBehavior": guard.close_behavior,
+        "position": guard.settings.position,
         "autostartEnabled": autostart_enabled,
         "windowVisible": window_visible,
-        "hiddenCreatures": guard.hidden_creatures,
-    }))
+    });
+    if let Some(map) = state.as_object_mut() {
+        map.extend(guard.settings.to_json());
+    }
+    Ok(state)
+}
+
+/// Every user-editable setting with its type, range, options and default.
+#[tauri::command]
+pub fn get_settings_schema() -> Result<Vec<serde_json::Value>, String> {
+    Ok(crate::settings::SCHEMA
+        .iter()
+        .map(|spec| spec.to_json())
+        .collect())
 }
 
 #[tauri::command]
-pub fn set_send_scores(
+pub fn set_setting(
     app: tauri::AppHandle,
-    enabled: bool,
+    key: String,
+    value: serde_json::Value,
     state: State<'_, Arc<SharedState>>,
 ) -> Result<(), String> {
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.send_scores = enabled;
-        crate::save::atomic_save(&guard)?;
-    }
-    let _ = app.emit("send-scores", serde_json::json!({ "enabled": enabled }));
-    Ok(())
+    crate::tray::apply_setting(&app, &state, &key, value)
 }
 
 #[tauri::command]
-pub fn set_sound_enabled(
+pub fn toggle_drag_mode(
     app: tauri::AppHandle,
-    enabled: bool,
     state: State<'_, Arc<SharedState>>,
 ) -> Result<(), String> {
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.sound_enabled = enabled;
-        crate::save::atomic_save(&guard)?;
-    }
-    let _ = app.emit("sound-settings", serde_json::json!({ "enabled": enabled }));
+    crate::tray::toggle_drag_mode(&app, &state);
     Ok(())
 }
 
+/// Export progress, settings or both (the default) to `path`.
 #[tauri::command]
-pub fn set_music_volume(
-    app: tauri::AppHandle,
-    volume: f32,
+pub fn export_save(
+    path: String,
+    scope: Option<SaveScope>,
     state: State<'_, Arc<SharedState>>,
 ) -> Result<(), String> {
-    let clamped = volume.clamp(0.0, 1.0);
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.music_volume = clamped;
-        crate::save::atomic_save(&guard)?;
-    }
-    let _ = app.emit("sound-settings", serde_json::json!({ "volume": clamped }));
-    Ok(())
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    crate::save::export(&guard, Path::new(&path), scope.unwrap_or_default())
 }
 
+/// Write a readable report of the collection to `path`.
 #[tauri::command]
-pub fn toggle_drag_mode(
-    app: tauri::AppHandle,
+pub fn export_report(
+    format: ReportFormat,
+    path: String,
     state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
 ) -> Result<(), String> {
-    crate::tray::toggle_drag_mode(&app, &state);
-    Ok(())
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    let registry = registry.read().map_err(|e| e.to_string())?;
+    crate::report::export(&guard, &registry, format, Path::new(&path))
 }
 
+/// The aquarium as plain or ANSI-coloured text.
 #[tauri::command]
-pub fn export_save(path: String, state: State<'_, Arc<SharedState>>) -> Result<(), String> {
+pub fn snapshot_tank(
+    format: Option<SnapshotFormat>,
+    state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
+) -> Result<String, String> {
     let guard = state.lock().map_err(|e| e.to_string())?;
-    crate::save::atomic_save(&guard)?;
+    let registry = registry.read().map_err(|e| e.to_string())?;
+    Ok(crate::snapshot::snapshot(
+        &guard,
+        &registry,
+        format.unwrap_or(SnapshotFormat::Plain),
+    ))
+}
 
-    let save_path = crate::save::save_path();
-    std::fs::copy(&save_path, &path).map_err(|e| format!("Failed to export: {}", e))?;
-    Ok(())
+/// Record `seconds` of the tank as an animated GIF or APNG (by the extension
+/// of `path`). Runs off the main thread since encoding takes a while.
+#[tauri::command(async)]
+pub fn record_tank_clip(
+    seconds: f64,
+    path: String,
+    state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
+) -> Result<(), String> {
+    let scene = {
+        let guard = state.lock().map_err(|e| e.to_string())?;
+        let registry = registry.read().map_err(|e| e.to_string())?;
+        crate::clip::Scene::new(&guard, &registry)
+    };
+    scene.record(seconds, Path::new(&path))
 }
 
+/// Token for the local status API, for showing to the user.
 #[tauri::command]
-pub fn import_save(path: String, state: State<'_, Arc<SharedState>>) -> Result<(), String> {
-    let data =
-        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read import file: {}", e))?;
-
-    let save: crate::save::SaveFile =
-        serde_json::from_str(&data).map_err(|e| format!("Invalid save file: {}", e))?;
-
-    let mut guard = state.lock().map_err(|e| e.to_string())?;
-    guard.collection = save.collection;
-    guard.pool_energy = if save.progression.pool_energy.is_empty() {
-        let legacy = save.progression.energy.unwrap_or(0);
-        let mut m = std::collections::HashMap::new();
-        m.insert("typing".to_string(), legacy);
-        m.insert("click".to_string(), 0);
-        m.insert("audio".to_string(), 0);
-        m
-    } else {
-        save.progression.pool_energy
-    };
-    guard.total_discoveries = save.progression.total_discoveries;
-    guard.pity = save.progression.pity;
-    guard.position = save.display.position;
-    guard.size_index = save.display.size_index;
-    guard.send_scores = save.display.send_scores;
-    guard.sound_enabled = save.display.sound_enabled;
-    guard.music_volume = save.display.music_volume;
-    guard.day_night_cycle = save.display.day_night_cycle;
-    guard.message_bottles_enabled = save.display.message_bottles_enabled;
-    guard.message_bottles_prompted = save.display.message_bottles_prompted;
-    guard.close_behavior = save.display.close_behavior;
-    guard.hidden_creatures = save.display.hidden_creatures;
-
-    crate::save::sanitize(&mut guard);
-    crate::save::atomic_save(&guard)?;
-    Ok(())
+pub fn get_api_token() -> Result<String, String> {
+    crate::api::token()
 }
 
+/// Merge progress and/or settings from a save or settings file. Progress is
+/// merged creature by creature; only settings present in the file change,
+/// and the window position always stays local.
 #[tauri::command]
-pub fn set_hidden_creatures(
+pub fn import_save(
     app: tauri::AppHandle,
+    path: String,
+    scope: Option<SaveScope>,
     state: State<'_, Arc<SharedState>>,
-    ids: Vec<String>,
+    registry: State<'_, Arc<SharedRegistry>>,
 ) -> Result<(), String> {
-    {
+    let imported = crate::save::read_import(Path::new(&path), scope.unwrap_or_default())?;
+
+    let changes = {
         let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.hidden_creatures = ids.clone();
-        crate::save::atomic_save(&guard)?;
+        if let Some(mut progress) = imported.progress {
+            let registry = registry.read().map_err(|e| e.to_string())?;
+            // Apply renames to each side before merging so they line up
+            crate::save::sanitize(&mut progress, &registry);
+            crate::merge::merge_progress(&mut guard, progress, MergeStrategy::Max);
+            crate::save::sanitize(&mut guard, &registry);
+            crate::persist::mark(Dirty::All);
+        }
+        imported
+            .settings
+            .map(|settings| guard.settings.changes_from(&settings))
+            .unwrap_or_default()
+    };
+    for (key, value) in changes {
+        if let Err(e) = crate::tray::apply_setting(&app, &state, key, value) {
+            eprintln!("Import: skipping setting: {}", e);
+        }
     }
-    let _ = app.emit("hidden-fish-changed", serde_json::json!({ "ids": ids }));
+    let _ = app.emit("save-imported", ());
     Ok(())
 }
 
+/// Combine another save's progress into this reef. With `dry_run` nothing
+/// changes and the returned diff is a preview of the merge.
 #[tauri::command]
-pub fn hide_window(app: tauri::AppHandle) -> Result<(), String> {
-    crate::tray::set_window_visibility(&app, false);
-    Ok(())
+pub fn merge_save(
+    app: tauri::AppHandle,
+    path: String,
+    strategy: MergeStrategy,
+    dry_run: Option<bool>,
+    state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
+) -> Result<MergeDiff, String> {
+    let mut progress = crate::save::read_import(Path::new(&path), SaveScope::Progress)?
+        .progress
+        .ok_or("Save file has no progress to merge")?;
+
+    let diff = {
+        let mut guard = state.lock().map_err(|e| e.to_string())?;
+        let registry = registry.read().map_err(|e| e.to_string())?;
+        crate::save::sanitize(&mut progress, &registry);
+        let mut merged = guard.clone();
+        crate::merge::merge_progress(&mut merged, progress, strategy);
+        crate::save::sanitize(&mut merged, &registry);
+        let diff = crate::merge::diff(&guard, &merged);
+        if dry_run.unwrap_or(false) {
+            return Ok(diff);
+        }
+        *guard = merged;
+        crate::persist::mark(Dirty::All);
+        diff
+    };
+    let _ = app.emit("save-imported", ());
+    Ok(diff)
 }
 
+/// What the last load recovered from, once; `None` after a clean load.
 #[tauri::command]
-pub fn open_collection(app: tauri::AppHandle) -> Result<(), String> {
-    crate::tray::open_collection_from_command(&app);
-    Ok(())
+pub fn take_load_notice() -> Option<LoadNotice> {
+    crate::save::take_load_notice()
 }
 
 #[tauri::command]
-pub fn open_settings(app: tauri::AppHandle) -> Result<(), String> {
-    crate::tray::open_settings_from_command(&app);
-    Ok(())
+pub fn get_save_location() -> Location {
+    crate::location::current()
 }
 
+/// Move all saved data to `path` (which must be empty) and keep using it.
 #[tauri::command]
-pub fn set_close_behavior(
+pub fn move_save_location(
     app: tauri::AppHandle,
-    behavior: String,
+    path: String,
     state: State<'_, Arc<SharedState>>,
-) -> Result<(), String> {
-    let behavior = match behavior.as_str() {
-        "ask" | "hide" | "close" => behavior,
-        _ => "ask".to_string(),
+) -> Result<Location, String> {
+    let location = {
+        let guard = state.lock().map_err(|e| e.to_string())?;
+        crate::persist::flush(&guard)?;
+        // The database must not be open while its files move
+        crate::save::close_storage();
+        crate::location::move_to(Path::new(&path))?;
+        crate::location::current()
     };
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.close_behavior = behavior.clone();
-        crate::save::atomic_save(&guard)?;
+    let _ = app.emit("save-location-changed", &location);
+    Ok(location)
+}
+
+/// Sync with the sync folder now instead of waiting for the next interval.
+#[tauri::command]
+pub fn sync_now(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
+) -> Result<SyncReport, String> {
+    let report = crate::sync::sync_now(&state, &registry)?;
+    if report.changed {
+        let _ = app.emit("sync-merged", &report);
     }
-    let _ = app.emit(
-        "close-behavior",
-        serde_json::json!({ "behavior": behavior }),
-    );
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn list_profiles() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "active": crate::profiles::active(),
+        "profiles": crate::profiles::list(),
+    }))
+}
+
+#[tauri::command]
+pub fn create_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    crate::profiles::create(&name)?;
+    crate::tray::refresh_profile_menu(&app);
+    let _ = app.emit("profiles-changed", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    crate::profiles::delete(&name)?;
+    crate::tray::refresh_profile_menu(&app);
+    let _ = app.emit("profiles-changed", ());
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_size_index(
+pub fn switch_profile(
     app: tauri::AppHandle,
-    idx: usize,
+    name: String,
     state: State<'_, Arc<SharedState>>,
+    registry: State<'_, Arc<SharedRegistry>>,
 ) -> Result<(), String> {
-    crate::tray::apply_size_index(&app, &state, idx)
+    crate::tray::switch_profile(&app, &state, &registry, &name)
 }
 
 #[tauri::command]
-pub fn set_day_night_cycle(
-    app: tauri::AppHandle,
-    cycle: String,
+pub fn get_active_events(
+    calendar: State<'_, Arc<crate::events::EventCalendar>>,
+) -> Result<Vec<crate::events::EventDef>, String> {
+    Ok(calendar.active_now().into_iter().cloned().collect())
+}
+
+/// Sprite definitions for every event-only creature, active or not, so owned
+/// event creatures still render after their event has ended.
+#[tauri::command]
+pub fn get_event_creatures(
+    calendar: State<'_, Arc<crate::events::EventCalendar>>,
+) -> Result<Vec<crate::energy::CreatureDef>, String> {
+    Ok(calendar
+        .events
+        .iter()
+        .flat_map(|e| e.creatures.iter().cloned())
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_creature_packs(
+    registry: State<'_, Arc<SharedRegistry>>,
     state: State<'_, Arc<SharedState>>,
-) -> Result<(), String> {
-    crate::tray::apply_day_night_cycle(&app, &state, cycle)
+) -> Result<serde_json::Value, String> {
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    let registry = registry.read().map_err(|e| e.to_string())?;
+    Ok(registry
+        .packs
+        .iter()
+        .map(|pack| {
+            serde_json::json!({
+                "id": pack.manifest.id,
+                "name": pack.manifest.name,
+                "version": pack.manifest.version,
+                "author": pack.manifest.author,
+                "path": pack.path,
+                "creatureCount": pack.creatures.len(),
+                "enabled": !guard.settings.disabled_packs.contains(&pack.manifest.id),
+                "errors": pack.errors,
+            })
+        })
+        .collect())
 }
 
 #[tauri::command]
-pub fn set_message_bottles_preferences(
+pub fn set_pack_enabled(
     app: tauri::AppHandle,
+    id: String,
     enabled: bool,
-    prompted: bool,
+    registry: State<'_, Arc<SharedRegistry>>,
     state: State<'_, Arc<SharedState>>,
 ) -> Result<(), String> {
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.message_bottles_enabled = enabled;
-        if prompted {
-            guard.message_bottles_prompted = true;
-        }
-        crate::save::atomic_save(&guard)?;
+    let known = registry
+        .read()
+        .map_err(|e| e.to_string())?
+        .packs
+        .iter()
+        .any(|p| p.manifest.id == id);
+    if !known {
+        return Err(format!("Unknown creature pack: {}", id));
+    }
+    let mut disabled = state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .settings
+        .disabled_packs
+        .clone();
+    disabled.retain(|p| *p != id);
+    if !enabled {
+        disabled.push(id);
     }
-    let _ = app.emit(
-        "message-bottles-settings",
-        serde_json::json!({ "enabled": enabled, "prompted": prompted }),
-    );
+    crate::tray::apply_setting(&app, &state, "disabledPacks", serde_json::json!(disabled))
+}
+
+/// Sprite definitions for every creature from a valid installed pack.
+#[tauri::command]
+pub fn get_pack_creatures(
+    registry: State<'_, Arc<SharedRegistry>>,
+) -> Result<Vec<crate::energy::CreatureDef>, String> {
+    let registry = registry.read().map_err(|e| e.to_string())?;
+    Ok(registry
+        .packs
+        .iter()
+        .flat_map(|p| p.creatures.iter().cloned())
+        .collect())
+}
+
+#[tauri::command]
+pub fn hide_window(app: tauri::AppHandle) -> Result<(), String> {
+    crate::tray::set_window_visibility(&app, false);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_collection(app: tauri::AppHandle) -> Result<(), String> {
+    crate::tray::open_collection_from_command(&app);
+    Ok(())
+}
+
+/// Bind a global shortcut to `action`; an empty accelerator unbinds it.
+#[tauri::command]
+pub fn set_hotkey(
+    app: tauri::AppHandle,
+    action: crate::hotkeys::Action,
+    accelerator: String,
+    state: State<'_, Arc<SharedState>>,
+) -> Result<(), String> {
+    crate::hotkeys::set_hotkey(&app, &state, action, &accelerator)
+}
+
+#[tauri::command]
+pub fn open_settings(app: tauri::AppHandle) -> Result<(), String> {
+    crate::tray::open_settings_from_command(&app);
     Ok(())
 }
 
@@ -270,7 +433,7 @@ pub fn reset_window_position(app: tauri::AppHandle) -> Result<(), String> {
 pub fn quit_app(app: tauri::AppHandle, state: State<'_, Arc<SharedState>>) -> Result<(), String> {
     {
         let guard = state.lock().map_err(|e| e.to_string())?;
-        crate::save::atomic_save(&guard)?;
+        crate::persist::flush(&guard)?;
     }
     app.exit(0);
     Ok(())
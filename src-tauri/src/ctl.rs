@@ -0,0 +1,457 @@
+//! `ascii-reef ctl <command>`: control the running app from a shell or a
+//! window manager key binding. The app listens on a local socket (a named
+//! pipe on Windows) named after its save directory; `ctl` sends the command
+//! as one JSON line and prints the one-line reply. Commands go through the
+//! same functions as the tray menu and the Tauri commands. The terminal UI
+//! listens too, and refuses the commands that need a window.
+use crate::energy::ENERGY_THRESHOLD;
+use crate::persist::Dirty;
+use crate::registry::{CreatureRegistry, SharedRegistry};
+use crate::report::ReportFormat;
+use crate::save::SaveScope;
+use crate::state::{GameState, SharedState};
+use crate::tray::SIZE_PRESETS;
+use interprocess::local_socket::{prelude::*, Listener, ListenerOptions, Name, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const CTL_ARG: &str = "ctl";
+pub const NOT_RUNNING: &str = "ASCII Reef is not running";
+pub const TERMINAL_MODE: &str = "ASCII Reef is running in terminal mode, without a window";
+/// Launch flags whose value is a separate argument
+const LAUNCH_VALUE_FLAGS: [&str; 2] = [crate::location::FLAG, crate::save::STORAGE_FLAG];
+const USAGE: &str = "Usage: ascii-reef ctl <command>
+
+Commands:
+  show, hide, toggle   show or hide the aquarium window
+  size <n>             switch to size preset n (0 = Small, as in the tray)
+  cycle <mode>         day/night cycle: computer, 5min, 10min, 60min, 3hours
+  stats                print discoveries, collection and energy
+  export <path>        export the save, or a report for .csv, .md or .html";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", content = "arg", rename_all = "lowercase")]
+pub enum Command {
+    Show,
+    Hide,
+    Toggle,
+    Size(usize),
+    Cycle(String),
+    Stats,
+    Export(PathBuf),
+}
+
+impl Command {
+    /// Parse the arguments after `ctl`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            ["show"] => Ok(Self::Show),
+            ["hide"] => Ok(Self::Hide),
+            ["toggle"] => Ok(Self::Toggle),
+            ["size", n] => n
+                .parse()
+                .map(Self::Size)
+                .map_err(|_| format!("Invalid size preset: {}", n)),
+            ["cycle", mode] => Ok(Self::Cycle(mode.to_string())),
+            ["stats"] => Ok(Self::Stats),
+            ["export", path] => Ok(Self::Export(PathBuf::from(path))),
+            _ => Err(USAGE.to_string()),
+        }
+    }
+
+    /// What a second launch asks of the running app: a command given as
+    /// plain arguments (`ascii-reef hide`), otherwise showing the window.
+    pub fn from_launch(args: &[String]) -> Self {
+        let mut words = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if LAUNCH_VALUE_FLAGS.contains(&arg.as_str()) {
+                args.next();
+            } else if !arg.starts_with("--") {
+                words.push(arg.clone());
+            }
+        }
+        Self::parse(&words).unwrap_or(Self::Show)
+    }
+
+    /// The app resolves paths against its own working directory.
+    fn absolute(self) -> Self {
+        match (self, std::env::current_dir()) {
+            (Self::Export(path), Ok(cwd)) => Self::Export(cwd.join(path)),
+            (command, _) => command,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    message: String,
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike `DefaultHasher`, so
+/// builds from different toolchains agree on the socket name.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Socket for the app using `dir`. Sockets live outside the save directory,
+/// which can be moved while the app is running.
+fn socket_name(dir: &Path) -> std::io::Result<Name<'static>> {
+    let id = format!(
+        "ascii-reef-{:016x}",
+        fnv1a(dir.as_os_str().as_encoded_bytes())
+    );
+    #[cfg(windows)]
+    {
+        use interprocess::local_socket::GenericNamespaced;
+        id.to_ns_name::<GenericNamespaced>()
+    }
+    #[cfg(not(windows))]
+    {
+        use interprocess::local_socket::GenericFilePath;
+        let base = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+        base.join(format!("{}.sock", id))
+            .to_fs_name::<GenericFilePath>()
+    }
+}
+
+/// Start listening for commands. Fails if another instance already is.
+fn listen(dir: &Path) -> Result<Listener, String> {
+    let name = socket_name(dir).map_err(|e| format!("Invalid socket name: {}", e))?;
+    if Stream::connect(name.borrow()).is_ok() {
+        return Err("another instance is already listening".to_string());
+    }
+    // Overwriting clears a socket file left behind by a crash
+    ListenerOptions::new()
+        .name(name)
+        .try_overwrite(true)
+        .create_sync()
+        .map_err(|e| format!("Failed to listen for ctl commands: {}", e))
+}
+
+/// Answer each connection's command with `handler` on its own thread.
+fn serve<F>(listener: Listener, handler: F)
+where
+    F: Fn(Command) -> Result<String, String> + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    std::thread::spawn(move || {
+        for conn in listener.incoming() {
+            let conn = match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Ctl connection failed: {}", e);
+                    continue;
+                }
+            };
+            let handler = handler.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(conn);
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() {
+                    return;
+                }
+                let result = serde_json::from_str(&line)
+                    .map_err(|e| format!("Invalid command: {}", e))
+                    .and_then(|command| handler(command));
+                let reply = match result {
+                    Ok(message) => Reply { ok: true, message },
+                    Err(message) => Reply { ok: false, message },
+                };
+                if let Ok(mut line) = serde_json::to_string(&reply) {
+                    line.push('\n');
+                    let _ = reader.get_mut().write_all(line.as_bytes());
+                }
+            });
+        }
+    });
+}
+
+/// Send `command` to the app using `dir` and return its reply.
+fn send(dir: &Path, command: &Command) -> Result<String, String> {
+    let name = socket_name(dir).map_err(|e| format!("Invalid socket name: {}", e))?;
+    let mut conn = Stream::connect(name).map_err(|_| NOT_RUNNING.to_string())?;
+    let mut line = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    line.push('\n');
+    conn.write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+    let mut line = String::new();
+    BufReader::new(conn)
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read reply: {}", e))?;
+    let reply: Reply = serde_json::from_str(&line).map_err(|e| format!("Invalid reply: {}", e))?;
+    if reply.ok {
+        Ok(reply.message)
+    } else {
+        Err(reply.message)
+    }
+}
+
+/// Hand a second launch's arguments to the running app.
+pub fn forward(args: &[String]) -> Result<String, String> {
+    let command = Command::from_launch(args).absolute();
+    send(&crate::location::instance_dir(), &command)
+}
+
+/// Discoveries, collection progress and energy per pool.
+fn stats(state: &GameState, registry: &CreatureRegistry) -> String {
+    let species = registry.all().count();
+    let owned = registry
+        .all()
+        .filter(|def| state.collection.contains_key(&def.id))
+        .count();
+    let pool = |name: &str| state.pool_energy.get(name).copied().unwrap_or(0);
+    format!(
+        "Discoveries: {}\nCollection: {}/{} species\nEnergy: typing {t}/{max}, click {c}/{max}, audio {a}/{max}",
+        state.total_discoveries,
+        owned,
+        species,
+        t = pool("typing"),
+        c = pool("click"),
+        a = pool("audio"),
+        max = ENERGY_THRESHOLD,
+    )
+}
+
+/// Save export, or a collection report for report file extensions.
+fn export(state: &GameState, registry: &CreatureRegistry, path: &Path) -> Result<(), String> {
+    match ReportFormat::from_path(path) {
+        Some(format) => crate::report::export(state, registry, format, path),
+        None => crate::save::export(state, path, SaveScope::default()),
+    }
+}
+
+/// Answer `Stats` and `Export`, which only read the reef.
+fn read(
+    state: &Arc<SharedState>,
+    registry: &SharedRegistry,
+    command: &Command,
+) -> Result<String, String> {
+    let guard = state.lock().map_err(|e| e.to_string())?;
+    let registry = registry.read().map_err(|e| e.to_string())?;
+    match command {
+        Command::Export(path) => {
+            export(&guard, &registry, path)?;
+            Ok(format!("Exported {}", path.display()))
+        }
+        _ => Ok(stats(&guard, &registry)),
+    }
+}
+
+/// Run `command` in the app, as the tray menu would.
+pub fn execute(
+    app: &tauri::AppHandle,
+    state: &Arc<SharedState>,
+    registry: &SharedRegistry,
+    command: Command,
+) -> Result<String, String> {
+    match command {
+        Command::Show => crate::tray::set_window_visibility(app, true),
+        Command::Hide => crate::tray::set_window_visibility(app, false),
+        Command::Toggle => crate::tray::toggle_window_visibility(app),
+        Command::Size(index) => {
+            crate::tray::apply_setting(app, state, "sizeIndex", json!(index))?;
+            return Ok(format!("Size: {}", SIZE_PRESETS[index].0));
+        }
+        Command::Cycle(mode) => {
+            crate::tray::apply_setting(app, state, "dayNightCycle", json!(mode))?;
+            return Ok(format!("Day/night cycle: {}", mode));
+        }
+        Command::Stats | Command::Export(_) => return read(state, registry, &command),
+    }
+    Ok(String::new())
+}
+
+/// Run `command` in the terminal UI. It has no window, so size and cycle
+/// only change the settings the next windowed launch starts with.
+pub fn execute_in_terminal(
+    state: &Arc<SharedState>,
+    registry: &SharedRegistry,
+    command: Command,
+) -> Result<String, String> {
+    let (key, value, message) = match command {
+        Command::Show | Command::Hide | Command::Toggle => return Err(TERMINAL_MODE.to_string()),
+        Command::Size(index) => (
+            "sizeIndex",
+            json!(index),
+            SIZE_PRESETS
+                .get(index)
+                .map(|preset| format!("Size: {}", preset.0)),
+        ),
+        Command::Cycle(mode) => (
+            "dayNightCycle",
+            json!(mode),
+            Some(format!("Day/night cycle: {}", mode)),
+        ),
+        Command::Stats | Command::Export(_) => return read(state, registry, &command),
+    };
+    let mut guard = state.lock().map_err(|e| e.to_string())?;
+    guard.settings.set(key, value)?;
+    crate::persist::mark(Dirty::Settings);
+    Ok(message.unwrap_or_default())
+}
+
+/// Listen for `ctl` commands for as long as the app runs.
+pub fn start_server(app: tauri::AppHandle, state: Arc<SharedState>, registry: Arc<SharedRegistry>) {
+    match listen(&crate::location::instance_dir()) {
+        Ok(listener) => serve(listener, move |command| {
+            execute(&app, &state, &registry, command)
+        }),
+        Err(e) => eprintln!("Ctl unavailable: {}", e),
+    }
+}
+
+/// Listen for `ctl` commands while the terminal UI runs.
+pub fn start_terminal_server(state: Arc<SharedState>, registry: Arc<SharedRegistry>) {
+    match listen(&crate::location::instance_dir()) {
+        Ok(listener) => serve(listener, move |command| {
+            execute_in_terminal(&state, &registry, command)
+        }),
+        Err(e) => eprintln!("Ctl unavailable: {}", e),
+    }
+}
+
+/// Release builds are GUI programs on Windows; write to the calling console.
+pub fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Run `ascii-reef ctl ...` against the running app and return the exit
+/// code, or `None` if this isn't a ctl invocation.
+pub fn run_client() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some(CTL_ARG) {
+        return None;
+    }
+    attach_console();
+    let command = match Command::parse(&args[1..]) {
+        Ok(command) => command.absolute(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return Some(2);
+        }
+    };
+    match send(&crate::location::instance_dir(), &command) {
+        Ok(message) => {
+            if !message.is_empty() {
+                println!("{}", message);
+            }
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventCalendar;
+    use crate::test_util::{owned, TempDir};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse(&args("show")), Ok(Command::Show));
+        assert_eq!(Command::parse(&args("size 3")), Ok(Command::Size(3)));
+        assert_eq!(
+            Command::parse(&args("cycle 10min")),
+            Ok(Command::Cycle("10min".to_string()))
+        );
+        assert_eq!(
+            Command::parse(&args("export reef.csv")),
+            Ok(Command::Export(PathBuf::from("reef.csv")))
+        );
+        assert!(Command::parse(&args("size big")).is_err());
+        assert!(Command::parse(&args("stats now")).is_err());
+        assert!(Command::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn launches_forward_commands_or_show() {
+        assert_eq!(Command::from_launch(&[]), Command::Show);
+        assert_eq!(
+            Command::from_launch(&args("--save-dir /tmp/reef --tui")),
+            Command::Show
+        );
+        assert_eq!(
+            Command::from_launch(&args("--storage sqlite hide")),
+            Command::Hide
+        );
+        assert_eq!(Command::from_launch(&args("size 2")), Command::Size(2));
+    }
+
+    #[test]
+    fn socket_ids_are_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn stats_count_known_species() {
+        let registry = CreatureRegistry::load(Arc::new(EventCalendar::default()));
+        let mut state = GameState::default();
+        let owned = owned(1, "2026-01-01");
+        let id = registry.all().next().map(|def| def.id.clone()).unwrap();
+        state.collection.insert(id, owned.clone());
+        state.collection.insert("retired".to_string(), owned);
+        state.total_discoveries = 7;
+        state.pool_energy.insert("click".to_string(), 12);
+        let text = stats(&state, &registry);
+        assert!(text.contains("Discoveries: 7"));
+        assert!(text.contains(&format!("Collection: 1/{} species", registry.all().count())));
+        assert!(text.contains(&format!("click 12/{}", ENERGY_THRESHOLD)));
+    }
+
+    #[test]
+    fn commands_round_trip_over_the_socket() {
+        let dir = TempDir::new("ctl");
+        assert_eq!(send(&dir, &Command::Stats), Err(NOT_RUNNING.to_string()));
+        serve(listen(&dir).unwrap(), |command| match command {
+            Command::Size(n) if n < SIZE_PRESETS.len() => Ok(format!("size {}", n)),
+            Command::Size(n) => Err(format!("no preset {}", n)),
+            _ => Ok(String::new()),
+        });
+        assert_eq!(send(&dir, &Command::Size(2)), Ok("size 2".to_string()));
+        assert_eq!(
+            send(&dir, &Command::Size(99)),
+            Err("no preset 99".to_string())
+        );
+        assert!(listen(&dir).is_err());
+    }
+
+    #[test]
+    fn terminal_mode_has_no_window() {
+        let registry = CreatureRegistry::load(Arc::new(EventCalendar::default()));
+        let registry = SharedRegistry::new(registry);
+        let state = Arc::new(SharedState::new(GameState::default()));
+        assert_eq!(
+            execute_in_terminal(&state, &registry, Command::Show),
+            Err(TERMINAL_MODE.to_string())
+        );
+        assert_eq!(
+            execute_in_terminal(&state, &registry, Command::Size(1)),
+            Ok(format!("Size: {}", SIZE_PRESETS[1].0))
+        );
+        assert_eq!(state.lock().unwrap().settings.size_index, 1);
+        assert!(execute_in_terminal(&state, &registry, Command::Size(99)).is_err());
+        let text = execute_in_terminal(&state, &registry, Command::Stats).unwrap();
+        assert!(text.starts_with("Discoveries: 0"));
+    }
+}
@@ -0,0 +1,74 @@
+//! Crash-safe file replacement. Data is synced to disk before the rename
+//! that publishes it, and the directory is synced after, so a power cut
+//! leaves either the old file or the new one, never a truncated one.
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write `bytes` to `path` and wait until they are on disk.
+pub fn write_synced(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(bytes)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Wait until `path`'s contents are on disk.
+pub fn sync_file(path: &Path) -> Result<(), String> {
+    File::open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to sync {}: {}", path.display(), e))
+}
+
+/// Make renames in `dir` durable. Windows cannot open directories; there the
+/// rename itself is flushed by the file system journal.
+pub fn sync_dir(dir: &Path) -> Result<(), String> {
+    if cfg!(windows) {
+        return Ok(());
+    }
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| format!("Failed to sync {}: {}", dir.display(), e))
+}
+
+/// Atomically replace `dest` with `src` (both in the same directory).
+/// `fs::rename` replaces an existing file on every platform.
+pub fn replace(src: &Path, dest: &Path) -> Result<(), String> {
+    fs::rename(src, dest).map_err(|e| format!("Failed to replace {}: {}", dest.display(), e))?;
+    match dest.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => sync_dir(dir),
+        _ => Ok(()),
+    }
+}
+
+/// Sibling of `path` used to stage its next version.
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Write `bytes` to `path` via a synced temporary file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp = tmp_path(path);
+    write_synced(&tmp, bytes)?;
+    replace(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn write_atomic_replaces_and_leaves_no_tmp() {
+        let dir = TempDir::new("durable");
+        let path = dir.join("settings.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+        assert_eq!(tmp_path(&path), dir.join("settings.json.tmp"));
+        assert!(!tmp_path(&path).exists());
+    }
+}
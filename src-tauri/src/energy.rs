@@ -1,13 +1,16 @@
+use crate::events::{self, EventCalendar};
 use crate::input::InputCounters;
-use crate::rarity::roll_rarity;
-use crate::save;
+use crate::notify::Notify;
+use crate::packs;
+use crate::persist::{self, Dirty};
+use crate::rarity::{record_outcome, roll_tier, Rarity};
+use crate::registry::SharedRegistry;
 use crate::state::{OwnedCreature, SharedState};
 use rand::seq::SliceRandom;
 use std::sync::Arc;
 use std::time::Instant;
-use tauri::{AppHandle, Emitter};
 
-const ENERGY_THRESHOLD: u32 = 40;
+pub const ENERGY_THRESHOLD: u32 = 40;
 const KEYS_PER_ENERGY: u64 = 2;
 const CLICKS_PER_ENERGY: u64 = 3;
 const AUDIO_SECONDS_PER_ENERGY: f64 = 8.0;
@@ -15,24 +18,69 @@ const IDLE_TIMEOUT_SECS: f64 = 900.0; // 15 minutes
 const IDLE_ENERGY_INTERVAL_SECS: f64 = 30.0;
 const TICK_INTERVAL_MS: u64 = 500;
 const AUTOSAVE_INTERVAL_SECS: f64 = 60.0;
+const POOLS: [&str; 3] = ["typing", "click", "audio"];
 
 /// Creature pool data (loaded from JSON at startup)
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CreatureDef {
     pub id: String,
     pub pool: String,
     pub rarity: String,
+    /// Display fields (name, frames, colours) passed through to the frontend untouched
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A creature that can be awarded, tagged with the event supplying it (if any)
+type Candidate<'a> = (&'a CreatureDef, Option<&'a str>);
+
+struct Discovery {
+    creature_id: String,
+    /// Rarity actually awarded (lower than `rolled_rarity` after a fallback)
+    rarity: Rarity,
+    rolled_rarity: Rarity,
+    is_new: bool,
+}
+
+/// Pick a creature for a discovery in `pool` after rolling `rolled`.
+///
+/// Fallback when the rolled bucket is empty: step down one tier at a time
+/// within the same pool; if the pool has nothing at or below `rolled`, step
+/// down again from `rolled` across the other pools. Returns the creature with
+/// the rarity actually awarded, or `None` only if there are no creatures at all.
+fn pick_creature<'a, R: rand::Rng>(
+    candidates: &[Candidate<'a>],
+    pool: &str,
+    rolled: Rarity,
+    rng: &mut R,
+) -> Option<(Candidate<'a>, Rarity)> {
+    for same_pool in [true, false] {
+        let mut tier = Some(rolled);
+        while let Some(rarity) = tier {
+            let bucket: Vec<&Candidate> = candidates
+                .iter()
+                .filter(|(c, _)| (c.pool == pool) == same_pool && c.rarity == rarity.as_str())
+                .collect();
+            if let Some(&&candidate) = bucket.choose(rng) {
+                return Some((candidate, rarity));
+            }
+            tier = rarity.below();
+        }
+    }
+    None
 }
 
 pub fn start_energy_loop(
-    app: AppHandle,
+    app: impl Notify,
     state: Arc<SharedState>,
     counters: Arc<InputCounters>,
     audio_active: Arc<std::sync::atomic::AtomicBool>,
-    creatures: Vec<CreatureDef>,
+    registry: Arc<SharedRegistry>,
+    calendar: Arc<EventCalendar>,
 ) {
     std::thread::spawn(move || {
         let mut last_tick = Instant::now();
+        let mut last_active_events: Vec<String> = Vec::new();
         let mut last_save = Instant::now();
         let mut key_accumulator: u64 = 0;
         let mut click_accumulator: u64 = 0;
@@ -53,12 +101,23 @@ pub fn start_energy_loop(
                 last_input_seen = now;
             }
 
+            let active_events = calendar.active_now();
+            let active_ids: Vec<String> = active_events.iter().map(|e| e.id.clone()).collect();
+            if active_ids != last_active_events {
+                app.notify(
+                    "events-changed",
+                    serde_json::json!({ "active": active_ids }),
+                );
+                last_active_events = active_ids;
+            }
+            let boost = events::rarity_boost(&active_events);
+
             // --- Collect all updates under the lock, then emit/save outside it ---
             struct TickResult {
                 typing_e: u32,
                 click_e: u32,
                 audio_e: u32,
-                discoveries: Vec<(String, String, bool)>, // (creature_id, rarity, is_new)
+                discoveries: Vec<Discovery>,
                 need_autosave: bool,
             }
 
@@ -130,45 +189,68 @@ pub fn start_energy_loop(
                 let audio_e = *state_guard.pool_energy.get("audio").unwrap_or(&0);
 
                 // Check each pool for discovery
+                let registry = registry.read().unwrap_or_else(|p| p.into_inner());
+                let candidates: Vec<Candidate> = registry
+                    .all()
+                    .filter(|c| {
+                        packs::pack_of(&c.id).is_none_or(|pack| {
+                            !state_guard
+                                .settings
+                                .disabled_packs
+                                .iter()
+                                .any(|d| d == pack)
+                        })
+                    })
+                    .map(|c| (c, None))
+                    .chain(active_events.iter().flat_map(|event| {
+                        event
+                            .creatures
+                            .iter()
+                            .map(move |c| (c, Some(event.id.as_str())))
+                    }))
+                    .collect();
                 let mut discoveries = Vec::new();
-                let pools = ["typing", "click", "audio"];
-                for pool_name in &pools {
-                    let pool_val = *state_guard.pool_energy.get(*pool_name).unwrap_or(&0);
+                for pool_name in POOLS {
+                    let pool_val = *state_guard.pool_energy.get(pool_name).unwrap_or(&0);
                     if pool_val >= ENERGY_THRESHOLD {
+                        let rolled = roll_tier(&state_guard.pity, &boost);
+                        let Some(((creature, event_id), awarded)) =
+                            pick_creature(&candidates, pool_name, rolled, &mut rand::thread_rng())
+                        else {
+                            // No creatures at all: keep the energy rather than lose the discovery
+                            continue;
+                        };
+
                         state_guard.pool_energy.insert(pool_name.to_string(), 0);
                         state_guard.total_discoveries += 1;
+                        record_outcome(&mut state_guard.pity, awarded);
 
-                        let rarity = roll_rarity(&mut state_guard.pity);
-                        let rarity_str = rarity.as_str();
-
-                        let candidates: Vec<&CreatureDef> = creatures
-                            .iter()
-                            .filter(|c| c.pool == *pool_name && c.rarity == rarity_str)
-                            .collect();
-
-                        if let Some(creature) = candidates.choose(&mut rand::thread_rng()) {
-                            let creature_id = creature.id.clone();
-                            let is_new = !state_guard.collection.contains_key(&creature_id);
-                            let entry = state_guard
-                                .collection
-                                .entry(creature_id.clone())
-                                .or_insert_with(|| OwnedCreature {
-                                    count: 0,
-                                    first_seen: chrono::Utc::now().to_rfc3339(),
-                                });
-                            entry.count += 1;
-                            discoveries.push((creature_id, rarity_str.to_string(), is_new));
-                        }
+                        let creature_id = creature.id.clone();
+                        let is_new = !state_guard.collection.contains_key(&creature_id);
+                        let entry = state_guard
+                            .collection
+                            .entry(creature_id.clone())
+                            .or_insert_with(|| OwnedCreature {
+                                count: 0,
+                                first_seen: chrono::Utc::now().to_rfc3339(),
+                                event: event_id.map(str::to_string),
+                            });
+                        entry.count += 1;
+                        discoveries.push(Discovery {
+                            creature_id,
+                            rarity: awarded,
+                            rolled_rarity: rolled,
+                            is_new,
+                        });
                     }
                 }
 
                 let need_autosave =
                     now.duration_since(last_save).as_secs_f64() >= AUTOSAVE_INTERVAL_SECS;
 
-                // Save inside the lock only if needed (discoveries or autosave)
+                // Discoveries are saved soon; energy alone only every autosave
                 if !discoveries.is_empty() || need_autosave {
-                    let _ = save::atomic_save(&state_guard);
-                    // state_guard dropped here
+                    persist::mark(Dirty::Progress);
                 }
 
                 TickResult {
@@ -182,7 +264,7 @@ pub fn start_energy_loop(
             };
 
             // --- Emit events outside the lock ---
-            let _ = app.emit(
+            app.notify(
                 "energy-update",
                 serde_json::json!({
                     "typing": result.typing_e,
@@ -192,13 +274,14 @@ pub fn start_energy_loop(
                 }),
             );
 
-            for (creature_id, rarity_str, is_new) in result.discoveries {
-                let _ = app.emit(
+            for discovery in result.discoveries {
+                app.notify(
                     "discovery",
                     serde_json::json!({
-                        "creatureId": creature_id,
-                        "rarity": rarity_str,
-                        "isNew": is_new,
+                        "creatureId": discovery.creature_id,
+                        "rarity": discovery.rarity.as_str(),
+                        "rolledRarity": discovery.rolled_rarity.as_str(),
+                        "isNew": discovery.is_new,
                     }),
                 );
             }
@@ -209,3 +292,89 @@ pub fn start_energy_loop(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    fn def(id: &str, pool: &str, rarity: &str) -> CreatureDef {
+        CreatureDef {
+            id: id.to_string(),
+            pool: pool.to_string(),
+            rarity: rarity.to_string(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    fn pick(defs: &[CreatureDef], pool: &str, rolled: Rarity) -> Option<(String, Rarity)> {
+        let candidates: Vec<Candidate> = defs.iter().map(|c| (c, None)).collect();
+        pick_creature(&candidates, pool, rolled, &mut SmallRng::seed_from_u64(1))
+            .map(|((c, _), rarity)| (c.id.clone(), rarity))
+    }
+
+    #[test]
+    fn rolled_bucket_is_used_when_present() {
+        let defs = [def("a", "typing", "epic"), def("b", "typing", "common")];
+        assert_eq!(
+            pick(&defs, "typing", Rarity::Epic),
+            Some(("a".to_string(), Rarity::Epic))
+        );
+    }
+
+    #[test]
+    fn empty_bucket_falls_down_tier_in_same_pool() {
+        let defs = [
+            def("t_rare", "typing", "rare"),
+            def("c_legendary", "click", "legendary"),
+        ];
+        assert_eq!(
+            pick(&defs, "typing", Rarity::Legendary),
+            Some(("t_rare".to_string(), Rarity::Rare))
+        );
+    }
+
+    #[test]
+    fn empty_pool_falls_back_to_closest_tier_in_other_pools() {
+        let defs = [
+            def("c_common", "click", "common"),
+            def("a_epic", "audio", "epic"),
+        ];
+        assert_eq!(
+            pick(&defs, "typing", Rarity::Epic),
+            Some(("a_epic".to_string(), Rarity::Epic))
+        );
+    }
+
+    #[test]
+    fn same_pool_down_tier_beats_other_pool_same_tier() {
+        let defs = [
+            def("t_common", "typing", "common"),
+            def("c_epic", "click", "epic"),
+        ];
+        assert_eq!(
+            pick(&defs, "typing", Rarity::Epic),
+            Some(("t_common".to_string(), Rarity::Common))
+        );
+    }
+
+    #[test]
+    fn no_creatures_yields_none() {
+        assert_eq!(pick(&[], "typing", Rarity::Common), None);
+    }
+
+    #[test]
+    fn event_tag_is_preserved() {
+        let defs = [def("ev", "audio", "rare")];
+        let candidates: Vec<Candidate> = defs.iter().map(|c| (c, Some("halloween"))).collect();
+        let ((_, event), _) = pick_creature(
+            &candidates,
+            "audio",
+            Rarity::Rare,
+            &mut SmallRng::seed_from_u64(1),
+        )
+        .unwrap();
+        assert_eq!(event, Some("halloween"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Seasonal / limited-time events. A calendar of events is loaded from the
+//! bundled `events.json` plus an optional user-supplied `events.json` in the
+//! save directory. While an event is active its rarity multipliers apply to
+//! every discovery roll and its event-only creatures join the candidate pool.
+//!
+//! Dates are either `MM-DD` (recurs every year, may wrap over New Year) or
+//! `YYYY-MM-DD` (one-off). Both ends are inclusive.
+use crate::energy::CreatureDef;
+use crate::rarity::RarityBoost;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// Upper bound for a single multiplier so a typo (e.g. `200` instead of `2.0`)
+/// cannot turn every roll into a legendary.
+const MAX_RARITY_MULTIPLIER: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDef {
+    pub id: String,
+    pub name: String,
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub rarity_multipliers: HashMap<String, f64>,
+    /// Event-only creatures, same schema as `creatures.json`
+    #[serde(default)]
+    pub creatures: Vec<CreatureDef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EventDate {
+    Annual { month: u32, day: u32 },
+    Fixed(NaiveDate),
+}
+
+fn parse_event_date(s: &str) -> Option<EventDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(EventDate::Fixed(date));
+    }
+    let (month, day) = s.split_once('-')?;
+    let month: u32 = month.parse().ok()?;
+    let day: u32 = day.parse().ok()?;
+    // Validate against a leap year so 02-29 is accepted
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+    Some(EventDate::Annual { month, day })
+}
+
+impl EventDef {
+    /// Whether the event covers `today`. Invalid or mixed date formats are never active.
+    pub fn is_active_on(&self, today: NaiveDate) -> bool {
+        match (parse_event_date(&self.start), parse_event_date(&self.end)) {
+            (Some(EventDate::Fixed(start)), Some(EventDate::Fixed(end))) => {
+                start <= today && today <= end
+            }
+            (
+                Some(EventDate::Annual { month: sm, day: sd }),
+                Some(EventDate::Annual { month: em, day: ed }),
+            ) => {
+                let t = (today.month(), today.day());
+                let (s, e) = ((sm, sd), (em, ed));
+                if s <= e {
+                    s <= t && t <= e
+                } else {
+                    // Wraps over New Year, e.g. 12-15 .. 01-05
+                    t >= s || t <= e
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// All known events, bundled first then user-supplied.
+#[derive(Debug, Clone, Default)]
+pub struct EventCalendar {
+    pub events: Vec<EventDef>,
+}
+
+pub fn user_calendar_path() -> PathBuf {
+    crate::save::save_dir().join("events.json")
+}
+
+/// Ids of the built-in creatures, which event creatures may not reuse.
+fn builtin_ids() -> HashSet<String> {
+    let defs: Vec<serde_json::Value> =
+        serde_json::from_str(include_str!("../../src/data/creatures.json")).unwrap_or_default();
+    defs.iter()
+        .filter_map(|def| def["id"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Check a single event definition, returning every problem found. Its
+/// creatures may not reuse an id in `taken`, since looking the id up would
+/// find the other creature instead.
+fn validate_event(event: &EventDef, taken: &HashSet<String>) -> Result<(), String> {
+    let mut errors = Vec::new();
+    let start = parse_event_date(&event.start);
+    let end = parse_event_date(&event.end);
+    if start.is_none() {
+        errors.push(format!("invalid start date {:?}", event.start));
+    }
+    if end.is_none() {
+        errors.push(format!("invalid end date {:?}", event.end));
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+        if std::mem::discriminant(&start) != std::mem::discriminant(&end) {
+            errors.push("start and end must both be MM-DD or both be YYYY-MM-DD".to_string());
+        }
+    }
+    for (rarity, mult) in &event.rarity_multipliers {
+        if RarityBoost::default().tier_mut(rarity).is_none() {
+            errors.push(format!("unknown rarity {:?} in rarityMultipliers", rarity));
+        } else if !mult.is_finite() || *mult <= 0.0 {
+            errors.push(format!("multiplier for {} must be positive", rarity));
+        }
+    }
+    let mut seen = HashSet::new();
+    for creature in &event.creatures {
+        match serde_json::to_value(creature) {
+            Ok(def) => crate::validate::check_creature(&def, &event.id, &mut seen, &mut errors),
+            Err(e) => errors.push(format!("{}: {}: {}", event.id, creature.id, e)),
+        }
+        if taken.contains(&creature.id) {
+            errors.push(format!(
+                "{}: {}: id already used by another creature",
+                event.id, creature.id
+            ));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+impl EventCalendar {
+    /// Add the valid events in `json`, each replacing a loaded event with the
+    /// same id.
+    fn add(&mut self, json: &str, source: &str) {
+        let events: Vec<EventDef> = match serde_json::from_str(json) {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Events: failed to parse {}: {}", source, e);
+                return;
+            }
+        };
+        let builtin = builtin_ids();
+        for event in events {
+            let mut taken = builtin.clone();
+            taken.extend(
+                self.events
+                    .iter()
+                    .filter(|e| e.id != event.id)
+                    .flat_map(|e| e.creatures.iter().map(|c| c.id.clone())),
+            );
+            match validate_event(&event, &taken) {
+                Ok(()) => {
+                    self.events.retain(|e| e.id != event.id);
+                    self.events.push(event);
+                }
+                Err(e) => eprintln!("Events: skipping {:?} from {}: {}", event.id, source, e),
+            }
+        }
+    }
+
+    /// Load the bundled calendar, then merge in the user calendar if present.
+    /// A user event with the same id as a bundled one replaces it.
+    pub fn load() -> Self {
+        let mut calendar = Self::default();
+        calendar.add(include_str!("../../src/data/events.json"), "bundled events");
+        let user_path = user_calendar_path();
+        if let Ok(json) = fs::read_to_string(&user_path) {
+            calendar.add(&json, &user_path.display().to_string());
+        }
+        calendar
+    }
+
+    pub fn active_on(&self, today: NaiveDate) -> Vec<&EventDef> {
+        self.events
+            .iter()
+            .filter(|e| e.is_active_on(today))
+            .collect()
+    }
+
+    pub fn active_now(&self) -> Vec<&EventDef> {
+        self.active_on(chrono::Local::now().date_naive())
+    }
+}
+
+/// Combined multipliers of all active events (overlapping events multiply).
+pub fn rarity_boost(active: &[&EventDef]) -> RarityBoost {
+    let mut boost = RarityBoost::default();
+    for event in active {
+        for (rarity, mult) in &event.rarity_multipliers {
+            if let Some(tier) = boost.tier_mut(rarity) {
+                *tier = (*tier * mult).min(MAX_RARITY_MULTIPLIER);
+            }
+        }
+    }
+    boost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start: &str, end: &str) -> EventDef {
+        EventDef {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+            rarity_multipliers: HashMap::new(),
+            creatures: Vec::new(),
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn annual_event_active_inside_range_inclusive() {
+        let e = event("10-24", "11-02");
+        assert!(e.is_active_on(date(2025, 10, 24)));
+        assert!(e.is_active_on(date(2025, 10, 31)));
+        assert!(e.is_active_on(date(2031, 11, 2)));
+        assert!(!e.is_active_on(date(2025, 10, 23)));
+        assert!(!e.is_active_on(date(2025, 11, 3)));
+    }
+
+    #[test]
+    fn annual_event_wraps_over_new_year() {
+        let e = event("12-15", "01-05");
+        assert!(e.is_active_on(date(2025, 12, 31)));
+        assert!(e.is_active_on(date(2026, 1, 1)));
+        assert!(e.is_active_on(date(2026, 1, 5)));
+        assert!(!e.is_active_on(date(2026, 1, 6)));
+        assert!(!e.is_active_on(date(2025, 12, 14)));
+    }
+
+    #[test]
+    fn fixed_event_only_active_that_year() {
+        let e = event("2026-03-01", "2026-03-07");
+        assert!(e.is_active_on(date(2026, 3, 4)));
+        assert!(!e.is_active_on(date(2027, 3, 4)));
+    }
+
+    #[test]
+    fn mixed_or_invalid_dates_are_rejected() {
+        let none = HashSet::new();
+        assert!(validate_event(&event("10-24", "2026-11-02"), &none).is_err());
+        assert!(validate_event(&event("13-01", "13-05"), &none).is_err());
+        assert!(validate_event(&event("02-29", "03-01"), &none).is_ok());
+        assert!(!event("garbage", "11-02").is_active_on(date(2025, 10, 30)));
+    }
+
+    #[test]
+    fn unknown_multiplier_rarity_is_rejected() {
+        let mut e = event("10-24", "11-02");
+        e.rarity_multipliers.insert("mythic".to_string(), 2.0);
+        assert!(validate_event(&e, &HashSet::new()).is_err());
+    }
+
+    #[test]
+    fn overlapping_boosts_multiply_and_are_capped() {
+        let mut a = event("01-01", "12-31");
+        a.rarity_multipliers.insert("epic".to_string(), 2.0);
+        a.rarity_multipliers.insert("legendary".to_string(), 8.0);
+        let mut b = a.clone();
+        b.rarity_multipliers.insert("epic".to_string(), 1.5);
+        let boost = rarity_boost(&[&a, &b]);
+        assert_eq!(boost.epic, 3.0);
+        assert_eq!(boost.legendary, MAX_RARITY_MULTIPLIER);
+        assert_eq!(boost.rare, 1.0);
+    }
+
+    #[test]
+    fn bundled_calendar_parses_without_dropping_events() {
+        let json = include_str!("../../src/data/events.json");
+        let raw: Vec<serde_json::Value> = serde_json::from_str(json).unwrap();
+        let mut calendar = EventCalendar::default();
+        calendar.add(json, "bundled events");
+        assert_eq!(calendar.events.len(), raw.len());
+    }
+
+    #[test]
+    fn event_creatures_cannot_reuse_ids() {
+        let mut calendar = EventCalendar::default();
+        calendar.add(include_str!("../../src/data/events.json"), "bundled events");
+        let bundled = calendar.events[0].clone();
+        let builtin = builtin_ids().into_iter().next().unwrap();
+        let other = calendar.events[1].creatures[0].id.clone();
+
+        let mut clash = bundled.clone();
+        clash.id = "clash".to_string();
+        clash.start = "13-01".to_string();
+        clash.creatures[0].id = builtin.clone();
+        clash.creatures[1].id = other.clone();
+        calendar.add(&serde_json::to_string(&[&clash]).unwrap(), "user events");
+        assert!(calendar.events.iter().all(|e| e.id != "clash"));
+
+        // Every problem is reported, not just the first
+        let taken = HashSet::from([builtin.clone(), other.clone()]);
+        let errors = validate_event(&clash, &taken).unwrap_err();
+        assert!(errors.contains("invalid start date"), "{}", errors);
+        assert!(
+            errors.contains(&format!("{}: id already used", builtin)),
+            "{}",
+            errors
+        );
+        assert!(
+            errors.contains(&format!("{}: id already used", other)),
+            "{}",
+            errors
+        );
+
+        // Replacing an event may keep its own creature ids
+        calendar.add(&serde_json::to_string(&[&bundled]).unwrap(), "user events");
+        assert_eq!(calendar.events.len(), 2);
+        assert_eq!(calendar.events[1].id, bundled.id);
+    }
+}
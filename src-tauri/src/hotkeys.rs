@@ -0,0 +1,133 @@
+//! Global keyboard shortcuts for common actions. Each action's accelerator is
+//! a setting (`hotkey*`, empty when unassigned); the whole set is registered
+//! with the OS at startup and again whenever one of them changes.
+use crate::settings::Settings;
+use crate::state::SharedState;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    ToggleWindow,
+    ToggleDragMode,
+    OpenCollection,
+    MuteMusic,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [
+        Action::ToggleWindow,
+        Action::ToggleDragMode,
+        Action::OpenCollection,
+        Action::MuteMusic,
+    ];
+
+    /// Setting holding this action's accelerator
+    pub fn setting(self) -> &'static str {
+        match self {
+            Action::ToggleWindow => "hotkeyToggleWindow",
+            Action::ToggleDragMode => "hotkeyToggleDragMode",
+            Action::OpenCollection => "hotkeyOpenCollection",
+            Action::MuteMusic => "hotkeyMuteMusic",
+        }
+    }
+
+    fn run(self, app: &AppHandle, state: &Arc<SharedState>) {
+        match self {
+            Action::ToggleWindow => crate::tray::toggle_window_visibility(app),
+            Action::ToggleDragMode => crate::tray::toggle_drag_mode(app, state),
+            Action::OpenCollection => crate::tray::open_collection_from_command(app),
+            Action::MuteMusic => {
+                if let Err(e) = crate::tray::toggle_setting(app, state, "soundEnabled") {
+                    eprintln!("Hotkeys: failed to toggle sound: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Shortcuts registered with the OS and the action each one triggers
+static REGISTERED: Mutex<Vec<(Shortcut, Action)>> = Mutex::new(Vec::new());
+
+/// The global shortcut plugin, dispatching presses to their action.
+pub fn plugin(state: Arc<SharedState>) -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(move |app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            let action = REGISTERED
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .iter()
+                .find(|(registered, _)| registered == shortcut)
+                .map(|(_, action)| *action);
+            if let Some(action) = action {
+                action.run(app, &state);
+            }
+        })
+        .build()
+}
+
+/// Register every assigned shortcut in place of the previous set. Shortcuts
+/// the OS refuses, usually because another app holds them, are logged and
+/// left out.
+pub fn register(app: &AppHandle, settings: &Settings) {
+    let shortcuts = app.global_shortcut();
+    let mut registered = REGISTERED.lock().unwrap_or_else(|p| p.into_inner());
+    for (shortcut, _) in registered.drain(..) {
+        let _ = shortcuts.unregister(shortcut);
+    }
+    for action in Action::ALL {
+        let accelerator = settings
+            .get(action.setting())
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        if accelerator.is_empty() {
+            continue;
+        }
+        let result = crate::settings::parse_shortcut(&accelerator).and_then(|shortcut| {
+            shortcuts.register(shortcut).map_err(|e| e.to_string())?;
+            Ok(shortcut)
+        });
+        match result {
+            Ok(shortcut) => registered.push((shortcut, action)),
+            Err(e) => eprintln!("Hotkeys: cannot register {}: {}", accelerator, e),
+        }
+    }
+}
+
+fn is_registered(action: Action) -> bool {
+    REGISTERED
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .iter()
+        .any(|(_, registered)| *registered == action)
+}
+
+/// Assign `accelerator` to `action`, or unassign it with an empty string.
+/// Rejects shortcuts used by another action or held by another app, keeping
+/// the previous one.
+pub fn set_hotkey(
+    app: &AppHandle,
+    state: &Arc<SharedState>,
+    action: Action,
+    accelerator: &str,
+) -> Result<(), String> {
+    let accelerator = accelerator.trim();
+    let previous = {
+        let guard = state.lock().map_err(|e| e.to_string())?;
+        guard.settings.get(action.setting()).unwrap_or(json!(""))
+    };
+    crate::tray::apply_setting(app, state, action.setting(), json!(accelerator))?;
+    if accelerator.is_empty() || is_registered(action) {
+        return Ok(());
+    }
+    crate::tray::apply_setting(app, state, action.setting(), previous)?;
+    Err(format!("{} is already in use by another app", accelerator))
+}
@@ -0,0 +1,87 @@
+//! One running app per save directory. The first launch holds a lock on
+//! `instance.lock`; a later launch forwards its arguments to it over the ctl
+//! socket and exits, so two energy loops never count the same input or race
+//! on the save files.
+use crate::ctl::{NOT_RUNNING, TERMINAL_MODE};
+use once_cell::sync::OnceCell;
+use std::fs::{self, File, TryLockError};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const LOCK_FILE: &str = "instance.lock";
+/// How long to wait for a just-started instance to begin listening
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+const FORWARD_RETRY: Duration = Duration::from_millis(200);
+
+/// Kept open for the life of the process; the OS drops the lock on exit
+static LOCK: OnceCell<File> = OnceCell::new();
+
+/// Lock `LOCK_FILE` in `dir`, or `None` if another process holds it.
+fn try_lock(dir: &Path) -> Result<Option<File>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create save dir: {}", e))?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))
+        .map_err(|e| format!("Failed to open {}: {}", LOCK_FILE, e))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(format!("Failed to lock {}: {}", LOCK_FILE, e)),
+    }
+}
+
+/// Become the running instance, or hand this launch's arguments to the one
+/// already running and return the exit code. Runs without the lock if it
+/// cannot be taken at all, as before.
+pub fn claim() -> Option<i32> {
+    let file = match try_lock(&crate::location::instance_dir()) {
+        Ok(Some(file)) => file,
+        Ok(None) => return Some(forward()),
+        Err(e) => {
+            eprintln!("Single instance: {}", e);
+            return None;
+        }
+    };
+    let _ = LOCK.set(file);
+    None
+}
+
+fn forward() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let started = Instant::now();
+    loop {
+        match crate::ctl::forward(&args) {
+            Ok(_) => return 0,
+            // The other instance may still be starting up
+            Err(e) if e == NOT_RUNNING && started.elapsed() < FORWARD_TIMEOUT => {
+                std::thread::sleep(FORWARD_RETRY);
+            }
+            Err(e) => {
+                match e.as_str() {
+                    NOT_RUNNING => eprintln!("ASCII Reef is already running but not answering"),
+                    TERMINAL_MODE => eprintln!("{}", e),
+                    _ => eprintln!("ASCII Reef is already running: {}", e),
+                }
+                return 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn second_lock_waits_for_the_first() {
+        let dir = TempDir::new("instance");
+        let first = try_lock(&dir).unwrap();
+        assert!(first.is_some());
+        assert!(try_lock(&dir).unwrap().is_none());
+        drop(first);
+        assert!(try_lock(&dir).unwrap().is_some());
+    }
+}
@@ -1,40 +1,85 @@
 //! ASCII Reef — Tauri app entry point.
 //! Initialises shared state, spawns the input/audio/energy threads,
-//! sets up the system tray, and wires Tauri window events.
+//! sets up the system tray, and wires Tauri window events. With `--tui` the
+//! same threads run behind a terminal UI instead; `--record-clip` writes an
+//! animated clip of the tank and exits, and `ctl <command>` controls the
+//! running app.
+mod api;
 mod audio;
+#[cfg(target_os = "linux")]
+mod bus;
+mod clip;
 mod commands;
+mod ctl;
+mod durable;
 mod energy;
+mod events;
+mod hotkeys;
 mod input;
+mod instance;
+mod location;
+mod merge;
+mod notify;
+mod packs;
+mod persist;
+mod profiles;
 mod rarity;
+mod registry;
+mod report;
 mod save;
+mod settings;
+mod snapshot;
+mod sqlite;
 mod state;
+mod sync;
+#[cfg(test)]
+mod test_util;
 mod tray;
+mod tui;
+mod validate;
 
 use input::InputCounters;
-use state::{GameState, SharedState};
+use state::SharedState;
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use tauri::{Listener, Manager};
 
-/// Load creature definitions from the bundled JSON
-fn load_creature_defs() -> Vec<energy::CreatureDef> {
-    let json = include_str!("../../src/data/creatures.json");
-    serde_json::from_str(json).expect("Failed to parse creatures.json")
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Load saved state or create fresh (log any load error)
-    let game_state = match save::load() {
-        Ok(state) => state,
-        Err(err) => {
-            eprintln!(
-                "Failed to load save file, falling back to defaults: {}",
-                err
-            );
-            GameState::default()
+    // `ascii-reef ctl ...` talks to the running app and exits
+    if let Some(code) = ctl::run_client() {
+        std::process::exit(code);
+    }
+
+    // One app per save directory: a second launch hands its arguments to the
+    // first and exits. Recording a clip only reads the save.
+    if !clip::requested() {
+        if let Some(code) = instance::claim() {
+            std::process::exit(code);
         }
-    };
+    }
+
+    // Load creature definitions (built-in + packs) and the seasonal event calendar
+    let calendar = Arc::new(events::EventCalendar::load());
+    let registry = registry::CreatureRegistry::load(calendar.clone());
+
+    // Load the active profile's saved state or create fresh (log any load error)
+    let game_state = save::load_or_default(&registry);
+
+    // Headless clip recording for CI: write the file and exit
+    if let Some(result) = clip::record_from_args(&game_state, &registry) {
+        ctl::attach_console();
+        match result {
+            Ok(path) => println!("Recorded {}", path.display()),
+            Err(e) => {
+                eprintln!("Failed to record clip: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let registry = Arc::new(RwLock::new(registry) as registry::SharedRegistry);
+
     let shared_state = Arc::new(Mutex::new(game_state) as SharedState);
 
     // Input counters (atomic, shared with rdev listener thread)
@@ -43,42 +88,71 @@ pub fn run() {
     // Audio detection flag
     let audio_active = Arc::new(AtomicBool::new(false));
 
-    // Load creature definitions
-    let creatures = load_creature_defs();
+    // Status API for widgets; serves only while enabled in the settings
+    api::start(shared_state.clone(), registry.clone(), calendar.clone());
+
+    if tui::requested() {
+        tui::run(
+            shared_state,
+            registry,
+            input_counters,
+            audio_active,
+            calendar,
+        );
+        return;
+    }
 
     let state_for_builder = shared_state.clone();
     let counters_for_setup = input_counters.clone();
     let audio_for_setup = audio_active.clone();
-    let creatures_for_setup = creatures.clone();
+    let registry_for_setup = registry.clone();
+    let calendar_for_setup = calendar.clone();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             None,
         ))
+        .plugin(hotkeys::plugin(shared_state.clone()))
         .manage(shared_state.clone())
+        .manage(calendar)
+        .manage(registry)
         .invoke_handler(tauri::generate_handler![
             commands::get_state,
             commands::toggle_drag_mode,
             commands::export_save,
+            commands::export_report,
+            commands::snapshot_tank,
+            commands::record_tank_clip,
+            commands::get_api_token,
             commands::import_save,
+            commands::merge_save,
+            commands::sync_now,
+            commands::take_load_notice,
+            commands::get_save_location,
+            commands::move_save_location,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::delete_profile,
+            commands::switch_profile,
             commands::hide_window,
             commands::open_collection,
             commands::open_settings,
-            commands::set_close_behavior,
-            commands::set_send_scores,
-            commands::set_sound_enabled,
-            commands::set_music_volume,
-            commands::set_size_index,
-            commands::set_day_night_cycle,
-            commands::set_message_bottles_preferences,
+            commands::get_settings_schema,
+            commands::set_setting,
+            commands::set_hotkey,
             commands::set_autostart,
             commands::set_main_window_visibility,
             commands::reset_aquarium,
             commands::reset_window_position,
             commands::quit_app,
-            commands::set_hidden_creatures,
+            commands::get_active_events,
+            commands::get_event_creatures,
+            commands::list_creature_packs,
+            commands::set_pack_enabled,
+            commands::get_pack_creatures,
         ])
         .setup(move |app| {
             let handle = app.handle().clone();
@@ -111,8 +185,8 @@ pub fn run() {
             // Apply saved size and position
             {
                 let guard = state_for_builder.lock().unwrap_or_else(|p| p.into_inner());
-                let idx = guard.size_index;
-                let saved_pos = guard.position;
+                let idx = guard.settings.size_index;
+                let saved_pos = guard.settings.position;
                 if idx < tray::SIZE_PRESETS.len() {
                     let (_, cols, rows, w, h) = tray::SIZE_PRESETS[idx];
                     if let Some(window) = app.get_webview_window("main") {
@@ -143,6 +217,13 @@ pub fn run() {
                 eprintln!("Failed to setup tray: {}", e);
             }
 
+            // Register the configured global shortcuts
+            let settings = {
+                let guard = state_for_builder.lock().unwrap_or_else(|p| p.into_inner());
+                guard.settings.clone()
+            };
+            hotkeys::register(&handle, &settings);
+
             // Handle tray left-click events
             let state_for_tray = state_for_builder.clone();
             let handle_for_tray = handle.clone();
@@ -150,6 +231,9 @@ pub fn run() {
                 tray::toggle_drag_mode(&handle_for_tray, &state_for_tray);
             });
 
+            // Write changes in the background, coalescing bursts
+            persist::start_persistence_thread(state_for_builder.clone());
+
             // Start input listener
             input::start_input_listener(counters_for_setup.clone());
 
@@ -162,7 +246,37 @@ pub fn run() {
                 state_for_builder.clone(),
                 counters_for_setup,
                 audio_for_setup,
-                creatures_for_setup,
+                registry_for_setup.clone(),
+                calendar_for_setup,
+            );
+
+            // Rebuild creature definitions when the dev JSON or packs change
+            registry::start_registry_watcher(
+                handle.clone(),
+                registry_for_setup.clone(),
+                state_for_builder.clone(),
+            );
+
+            // Reconcile with other devices through the sync folder, if set
+            sync::start_sync_loop(
+                handle.clone(),
+                state_for_builder.clone(),
+                registry_for_setup.clone(),
+            );
+
+            // Serve org.asciireef.Reef on the session bus
+            #[cfg(target_os = "linux")]
+            bus::start(
+                handle.clone(),
+                state_for_builder.clone(),
+                registry_for_setup.clone(),
+            );
+
+            // Accept `ascii-reef ctl` commands
+            ctl::start_server(
+                handle.clone(),
+                state_for_builder.clone(),
+                registry_for_setup,
             );
 
             // Track position changes and save on close
@@ -171,12 +285,12 @@ pub fn run() {
                 window.on_window_event(move |event| match event {
                     tauri::WindowEvent::Moved(pos) => {
                         if let Ok(mut guard) = state_for_close.lock() {
-                            guard.position = (pos.x as f64, pos.y as f64);
+                            guard.settings.position = (pos.x as f64, pos.y as f64);
                         }
                     }
                     tauri::WindowEvent::Destroyed => {
                         if let Ok(guard) = state_for_close.lock() {
-                            let _ = save::atomic_save(&guard);
+                            let _ = persist::flush(&guard);
                         }
                     }
                     _ => {}
@@ -0,0 +1,388 @@
+//! Where the save directory lives. In order of precedence:
+//!
+//! 1. `--save-dir <path>` on the command line
+//! 2. the `ASCII_REEF_SAVE_DIR` environment variable
+//! 3. portable mode: a `portable` file next to the executable keeps data in
+//!    `data/` beside it
+//! 4. a location chosen in the app (`move_save_location`), remembered in
+//!    `location.json` in the default directory
+//! 5. the platform config directory
+//!
+//! The first three are overrides managed outside the app, so moving is only
+//! possible for the last two.
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const FLAG: &str = "--save-dir";
+pub const ENV_VAR: &str = "ASCII_REEF_SAVE_DIR";
+pub const PORTABLE_MARKER: &str = "portable";
+const POINTER_FILE: &str = "location.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Flag,
+    Env,
+    Portable,
+    Setting,
+    Default,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Location {
+    pub path: PathBuf,
+    pub source: Source,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Pointer {
+    path: PathBuf,
+}
+
+static LOCATION: Lazy<RwLock<Location>> = Lazy::new(|| {
+    let args: Vec<String> = std::env::args().collect();
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    RwLock::new(resolve(
+        &args,
+        std::env::var(ENV_VAR).ok(),
+        exe_dir.as_deref(),
+        &default_dir(),
+    ))
+});
+
+fn default_dir() -> PathBuf {
+    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    base.join("ascii-reef")
+}
+
+/// Value of `<flag> <value>` or `<flag>=<value>` on a command line.
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn read_pointer(default: &Path) -> Option<PathBuf> {
+    let json = fs::read_to_string(default.join(POINTER_FILE)).ok()?;
+    match serde_json::from_str::<Pointer>(&json) {
+        Ok(pointer) if pointer.path.is_absolute() => Some(pointer.path),
+        _ => {
+            eprintln!("Save location: ignoring invalid {}", POINTER_FILE);
+            None
+        }
+    }
+}
+
+fn resolve(
+    args: &[String],
+    env: Option<String>,
+    exe_dir: Option<&Path>,
+    default: &Path,
+) -> Location {
+    let location = |path: PathBuf, source| Location { path, source };
+    if let Some(path) = flag_value(args, FLAG).filter(|p| !p.is_empty()) {
+        return location(PathBuf::from(path), Source::Flag);
+    }
+    if let Some(path) = env.filter(|p| !p.is_empty()) {
+        return location(PathBuf::from(path), Source::Env);
+    }
+    if let Some(dir) = exe_dir.filter(|dir| dir.join(PORTABLE_MARKER).is_file()) {
+        return location(dir.join("data"), Source::Portable);
+    }
+    if let Some(path) = read_pointer(default) {
+        return location(path, Source::Setting);
+    }
+    location(default.to_path_buf(), Source::Default)
+}
+
+pub fn current() -> Location {
+    LOCATION.read().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+pub fn save_dir() -> PathBuf {
+    LOCATION
+        .read()
+        .unwrap_or_else(|p| p.into_inner())
+        .path
+        .clone()
+}
+
+/// Identifies this app's data across moves: the override in effect, or the
+/// default directory, which holds the pointer to a chosen location.
+pub fn instance_dir() -> PathBuf {
+    let location = current();
+    match location.source {
+        Source::Setting => default_dir(),
+        _ => location.path,
+    }
+}
+
+/// Files tied to the directory rather than the data: the location pointer and
+/// the running instance's lock.
+fn stays(name: &std::ffi::OsStr) -> bool {
+    name == POINTER_FILE || name == crate::instance::LOCK_FILE
+}
+
+/// Whether `dir` is missing or holds nothing but the location pointer.
+fn is_vacant(dir: &Path) -> Result<bool, String> {
+    match fs::read_dir(dir) {
+        Ok(mut entries) => Ok(entries.all(|e| e.is_ok_and(|e| stays(&e.file_name())))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(format!("Failed to read {}: {}", dir.display(), e)),
+    }
+}
+
+fn copy_file(src: &Path, dst: &Path) -> Result<(), String> {
+    let copied =
+        fs::copy(src, dst).map_err(|e| format!("Failed to copy {}: {}", src.display(), e))?;
+    let expected = fs::metadata(src).map_err(|e| e.to_string())?.len();
+    if copied != expected {
+        return Err(format!("Short copy of {}", src.display()));
+    }
+    Ok(())
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    for entry in
+        fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?
+    {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let (src, dst) = (entry.path(), to.join(entry.file_name()));
+        if entry.file_type().map_err(|e| e.to_string())?.is_dir() {
+            copy_tree(&src, &dst)?;
+        } else {
+            copy_file(&src, &dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy the save directory `from` into `to` through a staging directory next
+/// to `to`, so `to` only ever holds a complete copy. The location pointer and
+/// instance lock are not copied. Returns the names of the entries moved.
+fn migrate(from: &Path, to: &Path) -> Result<Vec<std::ffi::OsString>, String> {
+    if !is_vacant(to)? {
+        return Err(format!("{} is not empty", to.display()));
+    }
+    let name = to
+        .file_name()
+        .ok_or("Save location needs a directory name")?;
+    let staging = to.with_file_name(format!(".{}.moving", name.to_string_lossy()));
+    let _ = fs::remove_dir_all(&staging);
+    let entries: Vec<_> = match fs::read_dir(from) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.file_name())
+            .filter(|name| !stays(name))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", from.display(), e)),
+    };
+
+    let staged = (|| {
+        fs::create_dir_all(&staging)
+            .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+        for name in &entries {
+            let src = from.join(name);
+            if src.is_dir() {
+                copy_tree(&src, &staging.join(name))?;
+            } else {
+                copy_file(&src, &staging.join(name))?;
+            }
+        }
+        fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+        for name in &entries {
+            fs::rename(staging.join(name), to.join(name))
+                .map_err(|e| format!("Failed to move {:?} into place: {}", name, e))?;
+        }
+        Ok::<(), String>(())
+    })();
+    let _ = fs::remove_dir_all(&staging);
+    if let Err(e) = staged {
+        for name in &entries {
+            let _ = fs::remove_dir_all(to.join(name)).or_else(|_| fs::remove_file(to.join(name)));
+        }
+        return Err(e);
+    }
+    Ok(entries)
+}
+
+/// Remember `path` as the save location, or forget it if it is the default.
+fn write_pointer(default: &Path, path: &Path) -> Result<(), String> {
+    let pointer = default.join(POINTER_FILE);
+    if path == default {
+        return match fs::remove_file(&pointer) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to reset save location: {}", e))
+            }
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(default).map_err(|e| format!("Failed to create save dir: {}", e))?;
+    let json = serde_json::to_string_pretty(&Pointer {
+        path: path.to_path_buf(),
+    })
+    .map_err(|e| format!("Failed to serialize save location: {}", e))?;
+    crate::durable::write_atomic(&pointer, json.as_bytes())
+}
+
+fn move_in(current: &Location, default: &Path, to: &Path) -> Result<(), String> {
+    match current.source {
+        Source::Flag => return Err(format!("Save location is set by {}", FLAG)),
+        Source::Env => return Err(format!("Save location is set by {}", ENV_VAR)),
+        Source::Portable => {
+            return Err("Save location is fixed in portable mode".to_string());
+        }
+        Source::Setting | Source::Default => {}
+    }
+    if !to.is_absolute() {
+        return Err("Save location must be an absolute path".to_string());
+    }
+    if to == current.path {
+        return Ok(());
+    }
+    if to.starts_with(&current.path) {
+        return Err("Save location cannot be inside the current one".to_string());
+    }
+    let moved = migrate(&current.path, to)?;
+    // The switch: until the pointer is written the old location stays in use
+    write_pointer(default, to)?;
+    for name in moved {
+        let old = current.path.join(name);
+        let removed = if old.is_dir() {
+            fs::remove_dir_all(&old)
+        } else {
+            fs::remove_file(&old)
+        };
+        if let Err(e) = removed {
+            eprintln!("Save location: failed to remove {}: {}", old.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Move every save, profile and pack file to `to` and use it from now on.
+/// Callers must hold the game state lock and have saved everything.
+pub fn move_to(to: &Path) -> Result<(), String> {
+    let mut location = LOCATION.write().unwrap_or_else(|p| p.into_inner());
+    move_in(&location, &default_dir(), to)?;
+    *location = Location {
+        path: to.to_path_buf(),
+        source: if to == default_dir() {
+            Source::Default
+        } else {
+            Source::Setting
+        },
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let root = TempDir::new("location-resolve");
+        let default = root.join("config");
+        let exe = root.join("bin");
+        fs::create_dir_all(&exe).unwrap();
+        let resolve_with = |a: &[&str], env: Option<&str>| {
+            resolve(&args(a), env.map(String::from), Some(&exe), &default)
+        };
+
+        assert_eq!(resolve_with(&["reef"], None).source, Source::Default);
+        write_pointer(&default, &root.join("chosen")).unwrap();
+        assert_eq!(
+            resolve_with(&["reef"], None),
+            Location {
+                path: root.join("chosen"),
+                source: Source::Setting
+            }
+        );
+        fs::write(exe.join(PORTABLE_MARKER), "").unwrap();
+        assert_eq!(resolve_with(&["reef"], None).path, exe.join("data"));
+        assert_eq!(
+            resolve_with(&["reef"], Some("/env/dir")).source,
+            Source::Env
+        );
+        let flagged = resolve_with(&["reef", "--save-dir", "/flag/dir"], Some("/env/dir"));
+        assert_eq!(flagged.path, PathBuf::from("/flag/dir"));
+        assert_eq!(flagged.source, Source::Flag);
+        assert_eq!(
+            flag_value(&args(&["reef", "--save-dir=/x"]), FLAG),
+            Some("/x".to_string())
+        );
+    }
+
+    #[test]
+    fn move_copies_everything_and_switches() {
+        let root = TempDir::new("location-move");
+        let default = root.join("config");
+        fs::create_dir_all(default.join("profiles/work")).unwrap();
+        fs::write(default.join("save.reef"), "{}").unwrap();
+        fs::write(default.join("profiles/work/save.reef"), "{}").unwrap();
+        fs::write(default.join(crate::instance::LOCK_FILE), "").unwrap();
+        let current = Location {
+            path: default.clone(),
+            source: Source::Default,
+        };
+
+        let volume = root.join("volume/reef");
+        move_in(&current, &default, &volume).unwrap();
+        assert!(volume.join("save.reef").is_file());
+        assert!(volume.join("profiles/work/save.reef").is_file());
+        assert!(!default.join("save.reef").exists());
+        assert!(default.join(crate::instance::LOCK_FILE).is_file());
+        assert!(!volume.join(crate::instance::LOCK_FILE).exists());
+        assert!(!root.join("volume/.reef.moving").exists());
+        assert_eq!(read_pointer(&default), Some(volume.clone()));
+
+        // And back again, which forgets the pointer
+        let current = Location {
+            path: volume.clone(),
+            source: Source::Setting,
+        };
+        move_in(&current, &default, &default).unwrap();
+        assert!(default.join("profiles/work/save.reef").is_file());
+        assert_eq!(read_pointer(&default), None);
+    }
+
+    #[test]
+    fn move_refuses_unsafe_targets() {
+        let root = TempDir::new("location-refuse");
+        let current = Location {
+            path: root.join("a"),
+            source: Source::Default,
+        };
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("full")).unwrap();
+        fs::write(root.join("full/other"), "").unwrap();
+        assert!(move_in(&current, &root.join("a"), Path::new("relative")).is_err());
+        assert!(move_in(&current, &root.join("a"), &root.join("a/inner")).is_err());
+        assert!(move_in(&current, &root.join("a"), &root.join("full")).is_err());
+        let env = Location {
+            source: Source::Env,
+            ..current
+        };
+        assert!(move_in(&env, &root.join("a"), &root.join("b")).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Combining two saves into one reef, and the diff shown before committing
+//! to a merge.
+use crate::state::{GameState, OwnedCreature};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How counts from the two saves combine. `Max` suits one history copied to
+/// two machines, `Sum` two reefs grown independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    #[default]
+    Max,
+    Sum,
+}
+
+impl MergeStrategy {
+    fn combine(self, a: u32, b: u32) -> u32 {
+        match self {
+            MergeStrategy::Max => a.max(b),
+            MergeStrategy::Sum => a.saturating_add(b),
+        }
+    }
+}
+
+/// Fold imported progress into `local`: the union of creatures, counts and
+/// `total_discoveries` combined per `strategy`, the earliest sighting kept.
+/// Pool energy follows the strategy too; pity counters take the larger
+/// value. Settings are left alone.
+pub fn merge_progress(local: &mut GameState, imported: GameState, strategy: MergeStrategy) {
+    let merge_collection = |local: &mut HashMap<String, OwnedCreature>,
+                            imported: HashMap<String, OwnedCreature>| {
+        for (id, theirs) in imported {
+            match local.get_mut(&id) {
+                Some(ours) => match strategy {
+                    MergeStrategy::Max => ours.merge(theirs),
+                    MergeStrategy::Sum => ours.absorb(theirs),
+                },
+                None => {
+                    local.insert(id, theirs);
+                }
+            }
+        }
+    };
+    merge_collection(&mut local.collection, imported.collection);
+    merge_collection(&mut local.orphaned.collection, imported.orphaned.collection);
+    local
+        .orphaned
+        .hidden_creatures
+        .extend(imported.orphaned.hidden_creatures);
+    crate::save::dedup_ids(&mut local.orphaned.hidden_creatures);
+
+    for (pool, energy) in imported.pool_energy {
+        let ours = local.pool_energy.entry(pool).or_insert(0);
+        *ours = strategy.combine(*ours, energy);
+    }
+    local.total_discoveries = strategy.combine(local.total_discoveries, imported.total_discoveries);
+    let (pity, theirs) = (&mut local.pity, imported.pity);
+    pity.legendary = pity.legendary.max(theirs.legendary);
+    pity.epic = pity.epic.max(theirs.epic);
+    pity.rare = pity.rare.max(theirs.rare);
+    pity.uncommon = pity.uncommon.max(theirs.uncommon);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatureChange {
+    pub id: String,
+    /// `None` if the creature is new to this reef
+    pub before: Option<OwnedCreature>,
+    pub after: OwnedCreature,
+}
+
+/// What a merge changes, for a dry-run preview.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeDiff {
+    /// Creatures added or whose count or first sighting changed, by id
+    pub creatures: Vec<CreatureChange>,
+    pub unique_creatures: Change<usize>,
+    pub total_discoveries: Change<u32>,
+    /// Ids from the imported save with no known definition (quarantined)
+    pub orphaned: Vec<String>,
+}
+
+pub fn diff(before: &GameState, after: &GameState) -> MergeDiff {
+    let mut creatures: Vec<CreatureChange> = after
+        .collection
+        .iter()
+        .filter_map(|(id, now)| {
+            let was = before.collection.get(id);
+            let changed =
+                was.is_none_or(|was| was.count != now.count || was.first_seen != now.first_seen);
+            changed.then(|| CreatureChange {
+                id: id.clone(),
+                before: was.cloned(),
+                after: now.clone(),
+            })
+        })
+        .collect();
+    creatures.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut orphaned: Vec<String> = after
+        .orphaned
+        .collection
+        .keys()
+        .filter(|id| !before.orphaned.collection.contains_key(*id))
+        .cloned()
+        .collect();
+    orphaned.sort();
+
+    MergeDiff {
+        creatures,
+        unique_creatures: Change {
+            before: before.collection.len(),
+            after: after.collection.len(),
+        },
+        total_discoveries: Change {
+            before: before.total_discoveries,
+            after: after.total_discoveries,
+        },
+        orphaned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::owned;
+
+    fn two_reefs() -> (GameState, GameState) {
+        let mut local = GameState::default();
+        local
+            .collection
+            .insert("t_common_01".to_string(), owned(5, "2025-03-01"));
+        local
+            .collection
+            .insert("t_common_03".to_string(), owned(2, "2025-01-01"));
+        local.total_discoveries = 7;
+        local.pool_energy.insert("typing".to_string(), 40);
+        local.settings.size_index = 6;
+
+        let mut imported = GameState::default();
+        imported
+            .collection
+            .insert("t_common_01".to_string(), owned(3, "2025-01-01"));
+        imported
+            .collection
+            .insert("t_common_02".to_string(), owned(1, "2025-02-01"));
+        imported.total_discoveries = 4;
+        imported.pool_energy.insert("click".to_string(), 7);
+        imported.pity.legendary = 12;
+        (local, imported)
+    }
+
+    #[test]
+    fn max_strategy_keeps_the_best_of_both() {
+        let (mut local, imported) = two_reefs();
+        merge_progress(&mut local, imported, MergeStrategy::Max);
+        assert_eq!(local.collection["t_common_01"].count, 5);
+        assert_eq!(local.collection["t_common_01"].first_seen, "2025-01-01");
+        assert_eq!(local.collection["t_common_02"].count, 1);
+        assert_eq!(local.total_discoveries, 7);
+        assert_eq!(local.pool_energy["typing"], 40);
+        assert_eq!(local.pool_energy["click"], 7);
+        assert_eq!(local.pity.legendary, 12);
+        assert_eq!(local.settings.size_index, 6);
+    }
+
+    #[test]
+    fn sum_strategy_adds_counts() {
+        let (mut local, imported) = two_reefs();
+        merge_progress(&mut local, imported, MergeStrategy::Sum);
+        assert_eq!(local.collection["t_common_01"].count, 8);
+        assert_eq!(local.collection["t_common_01"].first_seen, "2025-01-01");
+        assert_eq!(local.total_discoveries, 11);
+    }
+
+    #[test]
+    fn diff_lists_only_changed_creatures() {
+        let (local, imported) = two_reefs();
+        let mut merged = local.clone();
+        merge_progress(&mut merged, imported, MergeStrategy::Sum);
+        let diff = diff(&local, &merged);
+        let ids: Vec<&str> = diff.creatures.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["t_common_01", "t_common_02"]);
+        assert_eq!(diff.creatures[0].before.as_ref().unwrap().count, 5);
+        assert_eq!(diff.creatures[0].after.count, 8);
+        assert!(diff.creatures[1].before.is_none());
+        assert_eq!(
+            diff.unique_creatures,
+            Change {
+                before: 2,
+                after: 3
+            }
+        );
+        assert_eq!(
+            diff.total_discoveries,
+            Change {
+                before: 7,
+                after: 11
+            }
+        );
+    }
+}
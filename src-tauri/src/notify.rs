@@ -0,0 +1,23 @@
+//! Where background threads report what happened: the webview in the desktop
+//! app, or the terminal UI in `--tui` mode. Either way outside listeners get
+//! a copy (`broadcast`).
+use serde::Serialize;
+
+pub trait Notify: Send + 'static {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S);
+}
+
+/// Copy an app event to the status API stream and, on Linux, D-Bus signals.
+pub fn broadcast<S: Serialize>(event: &str, payload: &S) {
+    crate::api::publish(event, payload);
+    #[cfg(target_os = "linux")]
+    crate::bus::publish(event, payload);
+}
+
+impl Notify for tauri::AppHandle {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        use tauri::Emitter;
+        broadcast(event, &payload);
+        let _ = self.emit(event, payload);
+    }
+}
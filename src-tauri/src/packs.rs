@@ -0,0 +1,214 @@
+//! User-installable creature packs. Every `*.json` file in
+//! `<save dir>/creatures.d/` is one pack:
+//!
+//! ```json
+//! { "manifest": { "id": "office", "version": "1.0.0", "author": "Sam" },
+//!   "creatures": [ { "id": "stapler_fish", "pool": "typing", ... } ] }
+//! ```
+//!
+//! Creature ids are namespaced as `packid:creature` so packs can never clash
+//! with the built-in set or each other. A pack that fails validation is
+//! listed with its problems but contributes no creatures.
+use crate::energy::CreatureDef;
+use crate::validate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub id: String,
+    /// Display name, defaults to the id
+    #[serde(default)]
+    pub name: String,
+    pub version: String,
+    pub author: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackFile {
+    manifest: PackManifest,
+    #[serde(default)]
+    creatures: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreaturePack {
+    pub manifest: PackManifest,
+    pub path: PathBuf,
+    /// Namespaced creature definitions; empty if the pack has errors
+    pub creatures: Vec<CreatureDef>,
+    pub errors: Vec<String>,
+}
+
+pub fn packs_dir() -> PathBuf {
+    crate::save::save_dir().join("creatures.d")
+}
+
+/// The pack a namespaced creature id belongs to, or `None` for built-in creatures.
+pub fn pack_of(creature_id: &str) -> Option<&str> {
+    creature_id.split_once(':').map(|(pack, _)| pack)
+}
+
+fn valid_pack_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Parse and validate one pack file. `taken` holds pack ids already loaded.
+fn load_pack(path: &Path, taken: &HashSet<String>) -> Result<CreaturePack, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read pack: {}", e))?;
+    let file: PackFile =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid pack file: {}", e))?;
+    let mut manifest = file.manifest;
+    if manifest.name.is_empty() {
+        manifest.name = manifest.id.clone();
+    }
+
+    let mut errors = Vec::new();
+    if !valid_pack_id(&manifest.id) {
+        errors.push(format!(
+            "pack id {:?} must be lowercase letters, digits, '_' or '-'",
+            manifest.id
+        ));
+    } else if taken.contains(&manifest.id) {
+        errors.push(format!(
+            "pack id {:?} is already used by another pack",
+            manifest.id
+        ));
+    }
+
+    let source = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut seen = HashSet::new();
+    let mut creatures = Vec::new();
+    for mut def in file.creatures {
+        match def["id"].as_str() {
+            Some(id) if id.contains(':') => {
+                errors.push(format!(
+                    "{}: creature id {:?} must not contain ':'",
+                    source, id
+                ));
+                continue;
+            }
+            Some(id) => def["id"] = format!("{}:{}", manifest.id, id).into(),
+            None => {}
+        }
+        validate::check_creature(&def, &source, &mut seen, &mut errors);
+        if let Ok(creature) = serde_json::from_value::<CreatureDef>(def) {
+            creatures.push(creature);
+        }
+    }
+    if !errors.is_empty() {
+        creatures.clear();
+    }
+
+    Ok(CreaturePack {
+        manifest,
+        path: path.to_path_buf(),
+        creatures,
+        errors,
+    })
+}
+
+/// Load every pack in `dir`, in file-name order so results are stable.
+pub fn load_packs(dir: &Path) -> Vec<CreaturePack> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    paths.sort();
+
+    let mut taken = HashSet::new();
+    let mut packs = Vec::new();
+    for path in paths {
+        match load_pack(&path, &taken) {
+            Ok(pack) => {
+                for err in &pack.errors {
+                    eprintln!("Packs: {}: {}", pack.manifest.id, err);
+                }
+                taken.insert(pack.manifest.id.clone());
+                packs.push(pack);
+            }
+            Err(e) => eprintln!("Packs: skipping {}: {}", path.display(), e),
+        }
+    }
+    packs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn creature(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "name": "Stapler Fish", "pool": "typing", "rarity": "common",
+            "category": "swimmer", "width": 5, "height": 1,
+            "frames": [["><[=>"], ["><[->"]], "naturalColor": "#AAAAAA"
+        })
+    }
+
+    fn write_pack(dir: &Path, file: &str, id: &str, creatures: Vec<serde_json::Value>) {
+        let pack = serde_json::json!({
+            "manifest": { "id": id, "version": "1.0.0", "author": "Test" },
+            "creatures": creatures,
+        });
+        fs::write(dir.join(file), pack.to_string()).unwrap();
+    }
+
+    #[test]
+    fn creature_ids_are_namespaced() {
+        let dir = TempDir::new("packs-namespace");
+        write_pack(&dir, "office.json", "office", vec![creature("stapler")]);
+        let packs = load_packs(&dir);
+        assert_eq!(packs.len(), 1);
+        assert!(packs[0].errors.is_empty(), "{:?}", packs[0].errors);
+        assert_eq!(packs[0].creatures[0].id, "office:stapler");
+        assert_eq!(pack_of(&packs[0].creatures[0].id), Some("office"));
+        assert_eq!(packs[0].manifest.name, "office");
+    }
+
+    #[test]
+    fn invalid_creature_rejects_whole_pack() {
+        let dir = TempDir::new("packs-invalid");
+        let mut bad = creature("wide");
+        bad["width"] = 3.into();
+        write_pack(&dir, "a.json", "office", vec![creature("ok"), bad]);
+        let packs = load_packs(&dir);
+        assert!(!packs[0].errors.is_empty());
+        assert!(packs[0].creatures.is_empty());
+    }
+
+    #[test]
+    fn duplicate_pack_ids_and_bad_ids_are_reported() {
+        let dir = TempDir::new("packs-dupes");
+        write_pack(&dir, "a.json", "office", vec![creature("one")]);
+        write_pack(&dir, "b.json", "office", vec![creature("two")]);
+        write_pack(&dir, "c.json", "Bad Id", vec![creature("three")]);
+        write_pack(&dir, "d.json", "colon", vec![creature("x:y")]);
+        let packs = load_packs(&dir);
+        assert!(packs[0].errors.is_empty());
+        assert!(!packs[1].errors.is_empty());
+        assert!(!packs[2].errors.is_empty());
+        assert!(!packs[3].errors.is_empty());
+    }
+
+    #[test]
+    fn missing_dir_and_non_json_files_are_ignored() {
+        assert!(load_packs(Path::new("/nonexistent/ascii-reef")).is_empty());
+        let dir = TempDir::new("packs-nonjson");
+        fs::write(dir.join("notes.txt"), "hello").unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+        assert!(load_packs(&dir).is_empty());
+    }
+}
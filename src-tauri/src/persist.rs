@@ -0,0 +1,95 @@
+//! Background saving. Changes only mark the state dirty; a persistence
+//! thread writes them shortly afterwards, outside the state lock, so a burst
+//! of changes (dragging the volume slider, a run of discoveries) becomes a
+//! single write and a slow disk never stalls the UI. Exit paths and profile
+//! or location switches call `flush` to write immediately instead.
+//!
+//! Every write holds `WRITE`, which is taken while the state lock is still
+//! held, so an older snapshot can never land after a newer one or in a
+//! profile that has since been switched away from.
+use crate::state::{GameState, SharedState};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How long to let changes pile up before writing them.
+const DEBOUNCE_MS: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dirty {
+    Progress,
+    Settings,
+    All,
+}
+
+static DIRTY_PROGRESS: AtomicBool = AtomicBool::new(false);
+static DIRTY_SETTINGS: AtomicBool = AtomicBool::new(false);
+/// Set when anything is marked, to wake the persistence thread
+static PENDING: Lazy<(Mutex<bool>, Condvar)> = Lazy::new(|| (Mutex::new(false), Condvar::new()));
+static WRITE: Mutex<()> = Mutex::new(());
+
+/// Schedule a save of `what`.
+pub fn mark(what: Dirty) {
+    if what != Dirty::Settings {
+        DIRTY_PROGRESS.store(true, Ordering::SeqCst);
+    }
+    if what != Dirty::Progress {
+        DIRTY_SETTINGS.store(true, Ordering::SeqCst);
+    }
+    let (pending, wake) = &*PENDING;
+    *pending.lock().unwrap_or_else(|p| p.into_inner()) = true;
+    wake.notify_one();
+}
+
+/// Write everything now, superseding any scheduled save. Pass the state from
+/// a held lock.
+pub fn flush(state: &GameState) -> Result<(), String> {
+    let _write = WRITE.lock().unwrap_or_else(|p| p.into_inner());
+    DIRTY_PROGRESS.store(false, Ordering::SeqCst);
+    DIRTY_SETTINGS.store(false, Ordering::SeqCst);
+    crate::save::save_all(state)
+}
+
+pub fn start_persistence_thread(state: Arc<SharedState>) {
+    std::thread::spawn(move || loop {
+        {
+            let (pending, wake) = &*PENDING;
+            let mut pending = pending.lock().unwrap_or_else(|p| p.into_inner());
+            while !*pending {
+                pending = wake.wait(pending).unwrap_or_else(|p| p.into_inner());
+            }
+            *pending = false;
+        }
+        std::thread::sleep(Duration::from_millis(DEBOUNCE_MS));
+
+        let guard = state.lock().unwrap_or_else(|p| p.into_inner());
+        let progress = DIRTY_PROGRESS.swap(false, Ordering::SeqCst);
+        let settings = DIRTY_SETTINGS.swap(false, Ordering::SeqCst);
+        if !progress && !settings {
+            // Flushed in the meantime
+            continue;
+        }
+        let snapshot = guard.clone();
+        let write = WRITE.lock().unwrap_or_else(|p| p.into_inner());
+        drop(guard);
+
+        let mut result = Ok(());
+        if progress {
+            result = crate::save::atomic_save(&snapshot);
+        }
+        if settings {
+            result = result.and(crate::settings::save(&snapshot.settings));
+        }
+        if let Err(e) = result {
+            eprintln!("Save: background write failed, will retry: {}", e);
+            drop(write);
+            std::thread::sleep(Duration::from_secs(5));
+            mark(match (progress, settings) {
+                (true, true) => Dirty::All,
+                (true, false) => Dirty::Progress,
+                _ => Dirty::Settings,
+            });
+        }
+    });
+}
@@ -0,0 +1,180 @@
+//! Named profiles, each with its own save, backup and settings. The
+//! `default` profile lives directly in the save directory (where saves were
+//! before profiles existed); others live in `profiles/<name>/`. Creature
+//! packs and the event calendar are shared by all profiles.
+//!
+//! The active profile is process-wide so `active_dir()` and the paths built on it need no
+//! parameter. It only changes while the game state lock is held, so every
+//! save lands in the profile its state came from.
+use crate::save::save_dir;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileIndex {
+    active: String,
+}
+
+static ACTIVE: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(read_active(&save_dir())));
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join("profiles.json")
+}
+
+fn profile_dir_in(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join("profiles").join(name)
+    }
+}
+
+/// Active profile from the index, falling back to the default if it is gone.
+fn read_active(root: &Path) -> String {
+    fs::read_to_string(index_path(root))
+        .ok()
+        .and_then(|json| serde_json::from_str::<ProfileIndex>(&json).ok())
+        .map(|index| index.active)
+        .filter(|name| valid_name(name) && profile_dir_in(root, name).is_dir())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn list_in(root: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(root.join("profiles"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|name| valid_name(name) && name != DEFAULT_PROFILE)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+fn create_in(root: &Path, name: &str) -> Result<(), String> {
+    if !valid_name(name) {
+        return Err(format!(
+            "Profile name {:?} must be 1-32 lowercase letters, digits, '_' or '-'",
+            name
+        ));
+    }
+    if list_in(root).iter().any(|p| p == name) {
+        return Err(format!("Profile {:?} already exists", name));
+    }
+    fs::create_dir_all(profile_dir_in(root, name))
+        .map_err(|e| format!("Failed to create profile: {}", e))
+}
+
+fn delete_in(root: &Path, name: &str, active: &str) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile cannot be deleted".to_string());
+    }
+    if name == active {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    if !valid_name(name) || !list_in(root).iter().any(|p| p == name) {
+        return Err(format!("Unknown profile: {}", name));
+    }
+    fs::remove_dir_all(profile_dir_in(root, name))
+        .map_err(|e| format!("Failed to delete profile: {}", e))
+}
+
+pub fn active() -> String {
+    ACTIVE.read().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+/// Directory holding the active profile's save and settings.
+pub fn active_dir() -> PathBuf {
+    profile_dir_in(&save_dir(), &active())
+}
+
+/// All profiles, `default` first then by name.
+pub fn list() -> Vec<String> {
+    list_in(&save_dir())
+}
+
+pub fn create(name: &str) -> Result<(), String> {
+    create_in(&save_dir(), name)
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    delete_in(&save_dir(), name, &active())
+}
+
+/// Make `name` the active profile and remember it across restarts. Callers
+/// must hold the game state lock and save the outgoing profile first.
+pub fn set_active(name: &str) -> Result<(), String> {
+    let root = save_dir();
+    if !list_in(&root).iter().any(|p| p == name) {
+        return Err(format!("Unknown profile: {}", name));
+    }
+    fs::create_dir_all(&root).map_err(|e| format!("Failed to create save dir: {}", e))?;
+    let json = serde_json::to_string_pretty(&ProfileIndex {
+        active: name.to_string(),
+    })
+    .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    crate::durable::write_atomic(&index_path(&root), json.as_bytes())?;
+    *ACTIVE.write().unwrap_or_else(|p| p.into_inner()) = name.to_string();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn names_are_validated() {
+        assert!(valid_name("work"));
+        assert!(valid_name("kid-2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("Work"));
+        assert!(!valid_name("../etc"));
+        assert!(!valid_name(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn create_list_and_delete() {
+        let root = TempDir::new("profiles-crud");
+        assert_eq!(list_in(&root), vec![DEFAULT_PROFILE.to_string()]);
+        create_in(&root, "work").unwrap();
+        create_in(&root, "family").unwrap();
+        assert!(create_in(&root, "work").is_err());
+        assert!(create_in(&root, DEFAULT_PROFILE).is_err());
+        assert_eq!(list_in(&root), vec!["default", "family", "work"]);
+
+        assert!(delete_in(&root, DEFAULT_PROFILE, "work").is_err());
+        assert!(delete_in(&root, "work", "work").is_err());
+        assert!(delete_in(&root, "nope", "default").is_err());
+        delete_in(&root, "work", "default").unwrap();
+        assert_eq!(list_in(&root), vec!["default", "family"]);
+    }
+
+    #[test]
+    fn missing_active_profile_falls_back_to_default() {
+        let root = TempDir::new("profiles-active");
+        assert_eq!(read_active(&root), DEFAULT_PROFILE);
+        fs::write(index_path(&root), r#"{"active":"gone"}"#).unwrap();
+        assert_eq!(read_active(&root), DEFAULT_PROFILE);
+        create_in(&root, "gone").unwrap();
+        assert_eq!(read_active(&root), "gone");
+        assert_eq!(profile_dir_in(&root, "gone"), root.join("profiles/gone"));
+    }
+}
@@ -22,6 +22,51 @@ impl Rarity {
             Rarity::Legendary => "legendary",
         }
     }
+
+    /// The next tier down, or `None` for common.
+    pub fn below(&self) -> Option<Rarity> {
+        match self {
+            Rarity::Common => None,
+            Rarity::Uncommon => Some(Rarity::Common),
+            Rarity::Rare => Some(Rarity::Uncommon),
+            Rarity::Epic => Some(Rarity::Rare),
+            Rarity::Legendary => Some(Rarity::Epic),
+        }
+    }
+}
+
+/// Per-tier probability multipliers applied on top of the pity-modified rate.
+/// Used by limited-time events; `RarityBoost::default()` leaves every rate unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RarityBoost {
+    pub legendary: f64,
+    pub epic: f64,
+    pub rare: f64,
+    pub uncommon: f64,
+}
+
+impl Default for RarityBoost {
+    fn default() -> Self {
+        Self {
+            legendary: 1.0,
+            epic: 1.0,
+            rare: 1.0,
+            uncommon: 1.0,
+        }
+    }
+}
+
+impl RarityBoost {
+    /// Mutable access to the multiplier for a tier name (common has none).
+    pub fn tier_mut(&mut self, rarity: &str) -> Option<&mut f64> {
+        match rarity {
+            "legendary" => Some(&mut self.legendary),
+            "epic" => Some(&mut self.epic),
+            "rare" => Some(&mut self.rare),
+            "uncommon" => Some(&mut self.uncommon),
+            _ => None,
+        }
+    }
 }
 
 struct TierParams {
@@ -52,49 +97,132 @@ const UNCOMMON_PARAMS: TierParams = TierParams {
 };
 
 /// Roll for rarity using top-down check with incremental pity.
-/// Returns the rarity and updates pity counters in place.
-pub fn roll_rarity(pity: &mut PityCounters) -> Rarity {
-    roll_rarity_with_rng(pity, &mut rand::thread_rng())
+/// Does not touch the pity counters — call [`record_outcome`] with the rarity
+/// that was actually awarded, which may be lower if the rolled bucket was empty.
+pub fn roll_tier(pity: &PityCounters, boost: &RarityBoost) -> Rarity {
+    roll_tier_with_rng(pity, boost, &mut rand::thread_rng())
 }
 
+/// Roll and record in one step, as the energy loop did before fallbacks existed.
 /// Inner implementation accepting any RNG — used directly in tests for deterministic results.
+#[cfg(test)]
+fn roll_rarity_boosted_with_rng<R: rand::Rng>(
+    pity: &mut PityCounters,
+    boost: &RarityBoost,
+    rng: &mut R,
+) -> Rarity {
+    let rarity = roll_tier_with_rng(pity, boost, rng);
+    record_outcome(pity, rarity);
+    rarity
+}
+
+#[cfg(test)]
 fn roll_rarity_with_rng<R: rand::Rng>(pity: &mut PityCounters, rng: &mut R) -> Rarity {
+    roll_rarity_boosted_with_rng(pity, &RarityBoost::default(), rng)
+}
+
+/// `num / den` scaled by a multiplier, as the ratio actually rolled. A
+/// multiplier of exactly 1.0 keeps the plain ratio so unboosted rolls consume
+/// the RNG identically to before.
+fn boosted(num: u32, den: u32, multiplier: f64) -> (u32, u32) {
+    if multiplier == 1.0 {
+        return (num, den);
+    }
+    const SCALE: u32 = 1_000;
+    let scaled_den = den * SCALE;
+    let scaled_num = (num as f64 * multiplier * SCALE as f64).round() as u32;
+    (scaled_num.min(scaled_den), scaled_den)
+}
+
+/// `gen_ratio` scaled by a multiplier.
+fn boosted_ratio<R: rand::Rng>(rng: &mut R, num: u32, den: u32, multiplier: f64) -> bool {
+    let (num, den) = boosted(num, den, multiplier);
+    rng.gen_ratio(num, den)
+}
+
+/// Chance of each tier on the next roll, legendary first. Follows the
+/// top-down checks of `roll_tier`, so the chances add up to 1.
+pub fn tier_odds(pity: &PityCounters, boost: &RarityBoost) -> [(Rarity, f64); 5] {
+    let checks = [
+        (
+            Rarity::Legendary,
+            &LEGENDARY_PARAMS,
+            pity.legendary,
+            boost.legendary,
+        ),
+        (Rarity::Epic, &EPIC_PARAMS, pity.epic, boost.epic),
+        (Rarity::Rare, &RARE_PARAMS, pity.rare, boost.rare),
+        (
+            Rarity::Uncommon,
+            &UNCOMMON_PARAMS,
+            pity.uncommon,
+            boost.uncommon,
+        ),
+    ];
+    let mut odds = [(Rarity::Common, 0.0); 5];
+    let mut remaining = 1.0;
+    for (i, (rarity, params, counter, multiplier)) in checks.into_iter().enumerate() {
+        let prob = (params.base_num + counter).min(params.cap);
+        let (num, den) = boosted(prob, params.base_den, multiplier);
+        let chance = num as f64 / den as f64;
+        odds[i] = (rarity, remaining * chance);
+        remaining *= 1.0 - chance;
+    }
+    odds[4] = (Rarity::Common, remaining);
+    odds
+}
+
+fn roll_tier_with_rng<R: rand::Rng>(
+    pity: &PityCounters,
+    boost: &RarityBoost,
+    rng: &mut R,
+) -> Rarity {
     // Legendary check
     let leg_prob = (LEGENDARY_PARAMS.base_num + pity.legendary).min(LEGENDARY_PARAMS.cap);
-    if rng.gen_ratio(leg_prob, LEGENDARY_PARAMS.base_den) {
-        pity.legendary = 0;
+    if boosted_ratio(rng, leg_prob, LEGENDARY_PARAMS.base_den, boost.legendary) {
         return Rarity::Legendary;
     }
-    pity.legendary = (pity.legendary + 1).min(LEGENDARY_PARAMS.cap);
 
     // Epic check
     let epic_prob = (EPIC_PARAMS.base_num + pity.epic).min(EPIC_PARAMS.cap);
-    if rng.gen_ratio(epic_prob, EPIC_PARAMS.base_den) {
-        pity.epic = 0;
+    if boosted_ratio(rng, epic_prob, EPIC_PARAMS.base_den, boost.epic) {
         return Rarity::Epic;
     }
-    pity.epic = (pity.epic + 1).min(EPIC_PARAMS.cap);
 
     // Rare check
     let rare_prob = (RARE_PARAMS.base_num + pity.rare).min(RARE_PARAMS.cap);
-    if rng.gen_ratio(rare_prob, RARE_PARAMS.base_den) {
-        pity.rare = 0;
+    if boosted_ratio(rng, rare_prob, RARE_PARAMS.base_den, boost.rare) {
         return Rarity::Rare;
     }
-    pity.rare = (pity.rare + 1).min(RARE_PARAMS.cap);
 
     // Uncommon check
     let uncommon_prob = (UNCOMMON_PARAMS.base_num + pity.uncommon).min(UNCOMMON_PARAMS.cap);
-    if rng.gen_ratio(uncommon_prob, UNCOMMON_PARAMS.base_den) {
-        pity.uncommon = 0;
+    if boosted_ratio(rng, uncommon_prob, UNCOMMON_PARAMS.base_den, boost.uncommon) {
         return Rarity::Uncommon;
     }
-    pity.uncommon = (pity.uncommon + 1).min(UNCOMMON_PARAMS.cap);
 
     // Fallback: Common
     Rarity::Common
 }
 
+/// Update pity counters as if `awarded` had been rolled: every tier above it
+/// counts as a failed check (incremented up to its cap) and its own counter resets.
+pub fn record_outcome(pity: &mut PityCounters, awarded: Rarity) {
+    let tiers = [
+        (Rarity::Legendary, &mut pity.legendary, LEGENDARY_PARAMS.cap),
+        (Rarity::Epic, &mut pity.epic, EPIC_PARAMS.cap),
+        (Rarity::Rare, &mut pity.rare, RARE_PARAMS.cap),
+        (Rarity::Uncommon, &mut pity.uncommon, UNCOMMON_PARAMS.cap),
+    ];
+    for (tier, counter, cap) in tiers {
+        if tier == awarded {
+            *counter = 0;
+            return;
+        }
+        *counter = (*counter + 1).min(cap);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,4 +363,103 @@ mod tests {
             "Max pity ({max_pity}) should yield more legendaries than no pity ({no_pity})"
         );
     }
+
+    #[test]
+    fn event_boost_increases_legendary_rate() {
+        let n = 50_000u32;
+        let mut rng = SmallRng::seed_from_u64(4_242);
+        let boost = RarityBoost {
+            legendary: 4.0,
+            ..RarityBoost::default()
+        };
+        let (mut plain, mut boosted) = (0u32, 0u32);
+        for _ in 0..n {
+            let mut pity = PityCounters::default();
+            if roll_rarity_with_rng(&mut pity, &mut rng) == Rarity::Legendary {
+                plain += 1;
+            }
+        }
+        for _ in 0..n {
+            let mut pity = PityCounters::default();
+            if roll_rarity_boosted_with_rng(&mut pity, &boost, &mut rng) == Rarity::Legendary {
+                boosted += 1;
+            }
+        }
+        assert!(
+            boosted > plain * 2,
+            "4x boost ({boosted}) should clearly exceed the base rate ({plain})"
+        );
+    }
+
+    #[test]
+    fn huge_boost_saturates_instead_of_panicking() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut pity = PityCounters::default();
+        let boost = RarityBoost {
+            legendary: 1_000.0,
+            ..RarityBoost::default()
+        };
+        assert_eq!(
+            roll_rarity_boosted_with_rng(&mut pity, &boost, &mut rng),
+            Rarity::Legendary
+        );
+    }
+
+    #[test]
+    fn record_outcome_matches_a_direct_roll_of_that_tier() {
+        let mut pity = PityCounters {
+            legendary: 2,
+            epic: 3,
+            rare: 1,
+            uncommon: 4,
+        };
+        record_outcome(&mut pity, Rarity::Rare);
+        assert_eq!(pity.legendary, 3);
+        assert_eq!(pity.epic, EPIC_PARAMS.cap);
+        assert_eq!(pity.rare, 0, "awarded tier resets");
+        assert_eq!(pity.uncommon, 4, "tiers below the award are untouched");
+    }
+
+    #[test]
+    fn record_outcome_common_increments_every_tier() {
+        let mut pity = PityCounters::default();
+        record_outcome(&mut pity, Rarity::Common);
+        assert_eq!(pity.legendary, 1);
+        assert_eq!(pity.epic, 1);
+        assert_eq!(pity.rare, 1);
+        assert_eq!(pity.uncommon, 1);
+    }
+
+    #[test]
+    fn below_walks_down_to_common() {
+        let mut tier = Some(Rarity::Legendary);
+        let mut seen = Vec::new();
+        while let Some(t) = tier {
+            seen.push(t.as_str());
+            tier = t.below();
+        }
+        assert_eq!(seen, ["legendary", "epic", "rare", "uncommon", "common"]);
+    }
+
+    #[test]
+    fn tier_odds_follow_the_top_down_checks() {
+        let odds = tier_odds(&PityCounters::default(), &RarityBoost::default());
+        assert_eq!(odds[0], (Rarity::Legendary, 1.0 / 200.0));
+        assert!((odds[1].1 - (199.0 / 200.0) / 50.0).abs() < 1e-12);
+        assert_eq!(odds[4].0, Rarity::Common);
+        assert!((odds.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-12);
+
+        // Capped pity and a saturating boost make the top tier certain
+        let pity = PityCounters {
+            legendary: 10,
+            ..Default::default()
+        };
+        let boost = RarityBoost {
+            legendary: 100.0,
+            ..Default::default()
+        };
+        let odds = tier_odds(&pity, &boost);
+        assert_eq!(odds[0].1, 1.0);
+        assert!(odds[1..].iter().all(|(_, p)| *p == 0.0));
+    }
 }
@@ -0,0 +1,199 @@
+//! The live set of creature definitions: the built-in creatures plus every
+//! installed pack. A polling watcher rebuilds the whole set when any of the
+//! files behind it change and swaps it in under a write lock, so readers
+//! never see a half-loaded registry.
+//!
+//! In debug builds the built-in set is read from `src/data/creatures.json` on
+//! disk rather than the copy compiled in, so sprite edits show up without a
+//! rebuild.
+use crate::energy::CreatureDef;
+use crate::events::EventCalendar;
+use crate::notify::Notify;
+use crate::packs::{self, CreaturePack};
+use crate::persist::Dirty;
+use crate::state::SharedState;
+use crate::validate;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+const WATCH_INTERVAL_SECS: u64 = 1;
+
+pub struct CreatureRegistry {
+    pub builtin: Vec<CreatureDef>,
+    pub packs: Vec<CreaturePack>,
+    /// Event-only creatures are known (never orphaned) but only rollable
+    /// while their event is active, so they are kept out of `all()`
+    pub calendar: Arc<EventCalendar>,
+}
+
+pub type SharedRegistry = RwLock<CreatureRegistry>;
+
+/// On-disk creature JSON used instead of the compiled-in copy (debug builds only)
+fn dev_creatures_path() -> Option<PathBuf> {
+    if !cfg!(debug_assertions) {
+        return None;
+    }
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../src/data/creatures.json");
+    path.exists().then_some(path)
+}
+
+/// Parse and validate a built-in creature list with the same rules as `build.rs`.
+fn parse_builtin(json: &str) -> Result<Vec<CreatureDef>, Vec<String>> {
+    let defs: Vec<serde_json::Value> =
+        serde_json::from_str(json).map_err(|e| vec![format!("creatures.json: {}", e)])?;
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    for def in &defs {
+        validate::check_creature(def, "creatures.json", &mut seen, &mut errors);
+    }
+    validate::check_buckets(&defs, "creatures.json", &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    defs.into_iter()
+        .map(|d| serde_json::from_value(d).map_err(|e| format!("creatures.json: {}", e)))
+        .collect::<Result<_, _>>()
+        .map_err(|e| vec![e])
+}
+
+fn bundled_creatures() -> Vec<CreatureDef> {
+    // Validated by build.rs, so this cannot fail in a binary that compiled
+    parse_builtin(include_str!("../../src/data/creatures.json"))
+        .expect("Failed to parse creatures.json")
+}
+
+impl CreatureRegistry {
+    /// Build a registry from the current files. Fails only if the dev
+    /// creature JSON exists but is invalid.
+    pub fn try_load(calendar: Arc<EventCalendar>) -> Result<Self, Vec<String>> {
+        let builtin = match dev_creatures_path() {
+            Some(path) => {
+                let json = std::fs::read_to_string(&path)
+                    .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+                parse_builtin(&json)?
+            }
+            None => bundled_creatures(),
+        };
+        Ok(Self {
+            builtin,
+            packs: packs::load_packs(&packs::packs_dir()),
+            calendar,
+        })
+    }
+
+    /// Startup load: falls back to the bundled creatures if the dev JSON is broken.
+    pub fn load(calendar: Arc<EventCalendar>) -> Self {
+        Self::try_load(calendar.clone()).unwrap_or_else(|errors| {
+            for e in errors {
+                eprintln!("Creatures: {}", e);
+            }
+            Self {
+                builtin: bundled_creatures(),
+                packs: packs::load_packs(&packs::packs_dir()),
+                calendar,
+            }
+        })
+    }
+
+    /// Built-in creatures followed by creatures from every valid pack.
+    pub fn all(&self) -> impl Iterator<Item = &CreatureDef> {
+        self.builtin
+            .iter()
+            .chain(self.packs.iter().flat_map(|p| p.creatures.iter()))
+    }
+
+    /// The definition of `id`, including event-only creatures.
+    pub fn get(&self, id: &str) -> Option<&CreatureDef> {
+        self.all().find(|c| c.id == id).or_else(|| {
+            self.calendar
+                .events
+                .iter()
+                .flat_map(|e| e.creatures.iter())
+                .find(|c| c.id == id)
+        })
+    }
+
+    /// Whether `id` names any known creature, including event-only ones.
+    pub fn is_known(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+}
+
+/// Modification time and size of every file the registry is built from.
+fn fingerprint() -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let mut paths: Vec<PathBuf> = dev_creatures_path().into_iter().collect();
+    if let Ok(entries) = std::fs::read_dir(packs::packs_dir()) {
+        paths.extend(entries.flatten().map(|e| e.path()));
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map(|m| m.len()).unwrap_or(0);
+            (path, modified, len)
+        })
+        .collect()
+}
+
+/// Poll the creature files and rebuild the registry whenever they change.
+/// An invalid edit keeps the previous definitions in place.
+pub fn start_registry_watcher(
+    app: impl Notify,
+    registry: Arc<SharedRegistry>,
+    state: Arc<SharedState>,
+) {
+    std::thread::spawn(move || {
+        let mut last = fingerprint();
+        loop {
+            std::thread::sleep(Duration::from_secs(WATCH_INTERVAL_SECS));
+            let current = fingerprint();
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            let calendar = registry
+                .read()
+                .unwrap_or_else(|p| p.into_inner())
+                .calendar
+                .clone();
+            let fresh = match CreatureRegistry::try_load(calendar) {
+                Ok(fresh) => fresh,
+                Err(errors) => {
+                    for e in errors {
+                        eprintln!("Creatures: reload rejected: {}", e);
+                    }
+                    continue;
+                }
+            };
+            let count = fresh.all().count();
+            let pack_count = fresh.packs.len();
+
+            // Swap first (write lock released), then re-check the collection
+            // taking state before registry, the same order as the energy loop
+            *registry.write().unwrap_or_else(|p| p.into_inner()) = fresh;
+
+            let orphaned = {
+                let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
+                let reg = registry.read().unwrap_or_else(|p| p.into_inner());
+                let orphaned = crate::save::reconcile_creatures(&mut guard, &reg);
+                // Hidden creature ids live in settings, so save both
+                crate::persist::mark(Dirty::All);
+                orphaned
+            };
+
+            app.notify(
+                "creatures-reloaded",
+                serde_json::json!({
+                    "count": count,
+                    "packs": pack_count,
+                    "orphaned": orphaned,
+                }),
+            );
+        }
+    });
+}
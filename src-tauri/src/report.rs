@@ -0,0 +1,400 @@
+//! Human-readable collection reports for sharing a reef outside the app:
+//! CSV for spreadsheets, Markdown for wikis and a self-contained HTML page.
+//! Each owned creature is listed with its details and ASCII sprite frames.
+use crate::registry::CreatureRegistry;
+use crate::state::{GameState, OwnedCreature};
+use crate::validate::RARITIES;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// The format for a file name's extension, if it is a report format.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// One owned creature as it appears in a report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow {
+    pub id: String,
+    pub name: String,
+    pub pool: String,
+    pub rarity: String,
+    pub count: u32,
+    pub first_seen: String,
+    pub event: Option<String>,
+    /// Animation frames, each a list of sprite lines
+    pub frames: Vec<Vec<String>>,
+    /// Sprite colour, only if it is a plain `#rrggbb` value
+    pub color: Option<String>,
+}
+
+/// A `#rrggbb` colour, as creature definitions give them.
+pub(crate) fn parse_color(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn row(id: &str, owned: &OwnedCreature, registry: &CreatureRegistry) -> ReportRow {
+    let def = registry.get(id);
+    let extra = |key: &str| def.and_then(|d| d.extra.get(key));
+    let frames = extra("frames")
+        .and_then(|f| f.as_array())
+        .map(|frames| {
+            frames
+                .iter()
+                .filter_map(|frame| frame.as_array())
+                .map(|lines| {
+                    lines
+                        .iter()
+                        .filter_map(|l| l.as_str().map(str::to_string))
+                        .collect()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let color = extra("naturalColor")
+        .and_then(|c| c.as_str())
+        .filter(|c| parse_color(c).is_some())
+        .map(str::to_string);
+    ReportRow {
+        id: id.to_string(),
+        name: extra("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or(id)
+            .to_string(),
+        pool: def.map(|d| d.pool.clone()).unwrap_or_default(),
+        rarity: def.map(|d| d.rarity.clone()).unwrap_or_default(),
+        count: owned.count,
+        first_seen: owned.first_seen.clone(),
+        event: owned.event.clone(),
+        frames,
+        color,
+    }
+}
+
+/// The known creatures in the collection, rarest first, then by name.
+pub fn rows(state: &GameState, registry: &CreatureRegistry) -> Vec<ReportRow> {
+    let mut rows: Vec<ReportRow> = state
+        .collection
+        .iter()
+        .map(|(id, owned)| row(id, owned, registry))
+        .collect();
+    let rank = |rarity: &str| RARITIES.iter().position(|r| *r == rarity).unwrap_or(0);
+    rows.sort_by(|a, b| {
+        rank(&b.rarity)
+            .cmp(&rank(&a.rarity))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    rows
+}
+
+/// Frames as one block of text, separated by blank lines.
+fn frames_text(frames: &[Vec<String>]) -> String {
+    frames
+        .iter()
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(rows: &[ReportRow]) -> String {
+    let mut out = String::from("id,name,pool,rarity,count,first_seen,event,frames\r\n");
+    for row in rows {
+        let fields = [
+            row.id.clone(),
+            row.name.clone(),
+            row.pool.clone(),
+            row.rarity.clone(),
+            row.count.to_string(),
+            row.first_seen.clone(),
+            row.event.clone().unwrap_or_default(),
+            frames_text(&row.frames),
+        ];
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// `value` on one line, so it cannot end a table row or heading early.
+fn md_line(value: &str) -> String {
+    value.replace("\r\n", " ").replace(['\r', '\n'], " ")
+}
+
+/// Text safe inside a Markdown table cell.
+fn md_cell(value: &str) -> String {
+    md_line(value).replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// A code fence longer than any run of backticks in `text`.
+fn md_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn markdown(rows: &[ReportRow], generated: &str) -> String {
+    let total: u32 = rows.iter().map(|r| r.count).sum();
+    let mut out = format!(
+        "# ASCII Reef Collection\n\n{} species, {} creatures. Generated {}.\n\n",
+        rows.len(),
+        total,
+        generated
+    );
+    out.push_str("| Name | Pool | Rarity | Count | First seen | Event |\n");
+    out.push_str("| --- | --- | --- | ---: | --- | --- |\n");
+    for row in rows {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            md_cell(&row.name),
+            md_cell(&row.pool),
+            md_cell(&row.rarity),
+            row.count,
+            md_cell(&row.first_seen),
+            md_cell(row.event.as_deref().unwrap_or("")),
+        ));
+    }
+    for row in rows.iter().filter(|r| !r.frames.is_empty()) {
+        let sprite = frames_text(&row.frames);
+        let fence = md_fence(&sprite);
+        out.push_str(&format!(
+            "\n## {}\n\n{}\n{}\n{}\n",
+            md_line(&row.name),
+            fence,
+            sprite,
+            fence
+        ));
+    }
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+const HTML_STYLE: &str = "body{background:#0b1a2a;color:#d8e4ec;font-family:system-ui,sans-serif;margin:2em}\
+h1{font-weight:normal}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border-bottom:1px solid #2a3f55;padding:.3em .8em;text-align:left}\
+td.count{text-align:right}\
+.creatures{display:flex;flex-wrap:wrap;gap:1em}\
+.creature{background:#10263b;border-radius:6px;padding:.8em}\
+.creature h2{font-size:1em;margin:0 0 .5em}\
+pre{font-family:ui-monospace,Menlo,Consolas,monospace;margin:.3em 0;color:#b8c4cc}\
+.legendary h2{color:#ffc857}.epic h2{color:#c792ea}.rare h2{color:#6fb7ff}.uncommon h2{color:#7fd67f}";
+
+fn html(rows: &[ReportRow], generated: &str) -> String {
+    let total: u32 = rows.iter().map(|r| r.count).sum();
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>ASCII Reef Collection</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <h1>ASCII Reef Collection</h1>\n<p>{} species, {} creatures. Generated {}.</p>\n",
+        HTML_STYLE,
+        rows.len(),
+        total,
+        html_escape(generated)
+    );
+    out.push_str(
+        "<table>\n<tr><th>Name</th><th>Pool</th><th>Rarity</th><th>Count</th>\
+         <th>First seen</th><th>Event</th></tr>\n",
+    );
+    for row in rows {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"count\">{}</td><td>{}</td><td>{}</td></tr>\n",
+            html_escape(&row.name),
+            html_escape(&row.pool),
+            html_escape(&row.rarity),
+            row.count,
+            html_escape(&row.first_seen),
+            html_escape(row.event.as_deref().unwrap_or("")),
+        ));
+    }
+    out.push_str("</table>\n<div class=\"creatures\">\n");
+    for row in rows.iter().filter(|r| !r.frames.is_empty()) {
+        let style = row
+            .color
+            .as_ref()
+            .map(|c| format!(" style=\"color:{}\"", c))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "<div class=\"creature {}\">\n<h2>{} &times;{}</h2>\n",
+            html_escape(&row.rarity),
+            html_escape(&row.name),
+            row.count
+        ));
+        for frame in &row.frames {
+            out.push_str(&format!(
+                "<pre{}>{}</pre>\n",
+                style,
+                html_escape(&frame.join("\n"))
+            ));
+        }
+        out.push_str("</div>\n");
+    }
+    out.push_str("</div>\n</body>\n</html>\n");
+    out
+}
+
+pub fn render(rows: &[ReportRow], format: ReportFormat, generated: &str) -> String {
+    match format {
+        ReportFormat::Csv => csv(rows),
+        ReportFormat::Markdown => markdown(rows, generated),
+        ReportFormat::Html => html(rows, generated),
+    }
+}
+
+/// Write a report of the collection to `path`.
+pub fn export(
+    state: &GameState,
+    registry: &CreatureRegistry,
+    format: ReportFormat,
+    path: &Path,
+) -> Result<(), String> {
+    let generated = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    let report = render(&rows(state, registry), format, &generated);
+    fs::write(path, report).map_err(|e| format!("Failed to export report: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::CreatureDef;
+    use crate::events::EventCalendar;
+    use crate::test_util::owned;
+    use std::sync::Arc;
+
+    fn def(id: &str, name: &str, rarity: &str) -> CreatureDef {
+        let mut extra = serde_json::Map::new();
+        extra.insert("name".to_string(), name.into());
+        extra.insert("frames".to_string(), serde_json::json!([["><>"], ["<><"]]));
+        extra.insert("naturalColor".to_string(), "red;x:y".into());
+        CreatureDef {
+            id: id.to_string(),
+            pool: "typing".to_string(),
+            rarity: rarity.to_string(),
+            extra,
+        }
+    }
+
+    #[test]
+    fn rows_are_rarest_first_with_display_fields() {
+        let registry = CreatureRegistry {
+            builtin: vec![
+                def("a", "Zebra Fish", "common"),
+                def("b", "Angelfish", "common"),
+                def("c", "Kraken", "legendary"),
+            ],
+            packs: Vec::new(),
+            calendar: Arc::new(EventCalendar::default()),
+        };
+        let mut state = GameState::default();
+        for id in ["a", "b", "c"] {
+            state
+                .collection
+                .insert(id.to_string(), owned(1, "2025-01-01"));
+        }
+        let rows = rows(&state, &registry);
+        let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Kraken", "Angelfish", "Zebra Fish"]);
+        assert_eq!(rows[0].frames, vec![vec!["><>"], vec!["<><"]]);
+        assert_eq!(rows[0].color, None);
+    }
+
+    fn sample() -> Vec<ReportRow> {
+        vec![ReportRow {
+            id: "t_rare_01".to_string(),
+            name: "Puffer, \"Spiky\" | <b>".to_string(),
+            pool: "typing".to_string(),
+            rarity: "rare".to_string(),
+            count: 3,
+            first_seen: "2025-01-01".to_string(),
+            event: None,
+            frames: vec![vec!["<`(o)>".to_string(), " ``` ".to_string()]],
+            color: Some("#ffcc00".to_string()),
+        }]
+    }
+
+    #[test]
+    fn colours_are_strict_hex() {
+        assert_eq!(parse_color("#ff8000"), Some((255, 128, 0)));
+        for text in ["ff8000", "#ff800", "#+f8000", "#ff80zz", "#ff80000"] {
+            assert_eq!(parse_color(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let out = render(&sample(), ReportFormat::Csv, "now");
+        let mut lines = out.split("\r\n");
+        assert_eq!(
+            lines.next(),
+            Some("id,name,pool,rarity,count,first_seen,event,frames")
+        );
+        assert_eq!(
+            lines.next(),
+            Some(
+                "t_rare_01,\"Puffer, \"\"Spiky\"\" | <b>\",typing,rare,3,2025-01-01,,\"<`(o)>\n ``` \""
+            )
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_cells_and_outfences_sprites() {
+        let out = render(&sample(), ReportFormat::Markdown, "now");
+        assert!(out.contains("| Puffer, \"Spiky\" \\| <b> | typing | rare | 3 |"));
+        assert!(out.contains("\n````\n<`(o)>\n ``` \n````\n"));
+    }
+
+    #[test]
+    fn markdown_keeps_names_on_one_line() {
+        let mut rows = sample();
+        rows[0].name = "Two\r\nline\rname\n".to_string();
+        let out = render(&rows, ReportFormat::Markdown, "now");
+        assert!(out.contains("| Two line name  | typing |"));
+        assert!(out.contains("\n## Two line name \n"));
+        assert!(!out.contains('\r'));
+    }
+
+    #[test]
+    fn html_escapes_text() {
+        let out = render(&sample(), ReportFormat::Html, "now");
+        assert!(out.contains("Puffer, &quot;Spiky&quot; | &lt;b&gt;"));
+        assert!(out.contains("<pre style=\"color:#ffcc00\">&lt;`(o)&gt;\n ``` </pre>"));
+        assert!(!out.contains("<b>"));
+    }
+}
@@ -1,7 +1,23 @@
-use crate::state::GameState;
+use crate::durable;
+use crate::registry::CreatureRegistry;
+use crate::settings::{Settings, SettingsFile};
+use crate::state::{GameState, OrphanedCreatures};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// v3 moved `display` settings out to their own `settings.json`
+const SAVE_VERSION: u32 = 3;
+
+/// Creature ids renamed between releases (`old id -> new id`), applied on load
+/// and import so catches survive the rename.
+static CREATURE_RENAMES: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../../src/data/creature_renames.json"))
+        .expect("Failed to parse creature_renames.json")
+});
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaveFile {
@@ -10,7 +26,12 @@ pub struct SaveFile {
     #[serde(default)]
     pub collection: std::collections::HashMap<String, crate::state::OwnedCreature>,
     pub progression: SaveProgression,
-    pub display: SaveDisplay,
+    /// Settings, present in v2 saves and `both`-scope exports only; regular
+    /// saves keep them in `settings.json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<Settings>,
+    #[serde(default, skip_serializing_if = "OrphanedCreatures::is_empty")]
+    pub orphaned: OrphanedCreatures,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,168 +56,480 @@ pub struct SaveProgression {
     pub pity: crate::state::PityCounters,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SaveDisplay {
-    #[serde(default = "default_position")]
-    pub position: (f64, f64),
-    #[serde(default = "default_size_index")]
-    pub size_index: usize,
-    #[serde(default = "default_send_scores")]
-    pub send_scores: bool,
-    #[serde(default = "default_sound_enabled")]
-    pub sound_enabled: bool,
-    #[serde(default = "default_music_volume")]
-    pub music_volume: f32,
-    #[serde(default = "default_day_night_cycle")]
-    pub day_night_cycle: String,
-    #[serde(default = "default_message_bottles_enabled")]
-    pub message_bottles_enabled: bool,
-    #[serde(default = "default_message_bottles_prompted")]
-    pub message_bottles_prompted: bool,
-    #[serde(default = "default_close_behavior")]
-    pub close_behavior: String,
-    #[serde(default)]
-    pub hidden_creatures: Vec<String>,
+/// Which part of the player's data an export or import covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveScope {
+    Progress,
+    Settings,
+    #[default]
+    Both,
 }
 
-fn default_size_index() -> usize {
-    1
+impl SaveScope {
+    fn includes_progress(self) -> bool {
+        self != SaveScope::Settings
+    }
+
+    fn includes_settings(self) -> bool {
+        self != SaveScope::Progress
+    }
 }
 
-fn default_position() -> (f64, f64) {
-    (0.0, 0.0)
+impl SaveFile {
+    pub fn from_state(state: &GameState, created: String, include_settings: bool) -> Self {
+        SaveFile {
+            version: SAVE_VERSION,
+            meta: SaveMeta {
+                created,
+                last_saved: chrono::Utc::now().to_rfc3339(),
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            collection: state.collection.clone(),
+            progression: SaveProgression {
+                pool_energy: state.pool_energy.clone(),
+                energy: None,
+                total_discoveries: state.total_discoveries,
+                pity: state.pity.clone(),
+            },
+            display: include_settings.then(|| state.settings.clone()),
+            orphaned: state.orphaned.clone(),
+        }
+    }
+
+    /// The game state in this save, with default settings unless it bundles some.
+    pub fn into_state(self) -> GameState {
+        // Restore pool_energy, with backward compat for old saves
+        let pool_energy = if self.progression.pool_energy.is_empty() {
+            // Old save format: put legacy energy into typing pool
+            let legacy = self.progression.energy.unwrap_or(0);
+            let mut m = std::collections::HashMap::new();
+            m.insert("typing".to_string(), legacy);
+            m.insert("click".to_string(), 0);
+            m.insert("audio".to_string(), 0);
+            m
+        } else {
+            self.progression.pool_energy
+        };
+        GameState {
+            collection: self.collection,
+            pool_energy,
+            total_discoveries: self.progression.total_discoveries,
+            pity: self.progression.pity,
+            settings: self.display.unwrap_or_default(),
+            orphaned: self.orphaned,
+        }
+    }
 }
 
-fn default_send_scores() -> bool {
-    true
+/// Root of all saved data; see `location` for how it is chosen.
+pub fn save_dir() -> PathBuf {
+    crate::location::save_dir()
 }
 
-fn default_sound_enabled() -> bool {
-    false
+pub const SAVE_FILE: &str = "save.reef";
+/// The JSON save after `sqlite` has imported it
+pub const MIGRATED_FILE: &str = "save.reef.migrated";
+const BACKUP_FILE: &str = "save.reef.bak";
+const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOTS_KEPT: usize = 7;
+
+pub const STORAGE_FLAG: &str = "--storage";
+pub const STORAGE_ENV_VAR: &str = "ASCII_REEF_STORAGE";
+
+/// Backend for new profiles, chosen at startup with `--storage` or
+/// `ASCII_REEF_STORAGE`. A profile that already has a database keeps it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    Json,
+    Sqlite,
 }
 
-fn default_music_volume() -> f32 {
-    0.08
+impl StorageKind {
+    fn selected() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let Some(value) = crate::location::flag_value(&args, STORAGE_FLAG)
+            .or_else(|| std::env::var(STORAGE_ENV_VAR).ok())
+        else {
+            return StorageKind::Json;
+        };
+        match value.as_str() {
+            "json" => StorageKind::Json,
+            "sqlite" => StorageKind::Sqlite,
+            other => {
+                eprintln!("Save: unknown storage {:?}, using json", other);
+                StorageKind::Json
+            }
+        }
+    }
 }
 
-fn default_day_night_cycle() -> String {
-    "computer".to_string()
+/// Where a profile's progress is kept. Settings always live in
+/// `settings.json`.
+pub trait Storage: Send {
+    /// Profile directory this storage belongs to
+    fn dir(&self) -> &Path;
+    /// Saved progress, or `None` for a new reef, plus a notice if anything
+    /// had to be recovered
+    fn load(&mut self) -> (Option<SaveFile>, Option<LoadNotice>);
+    fn save(&mut self, state: &GameState) -> Result<(), String>;
+    /// When the reef was first saved
+    fn created(&self) -> Option<String>;
+    /// Recorded count increases, newest first, or `None` if this storage
+    /// keeps no history
+    fn history(&mut self, _limit: usize) -> Result<Option<Vec<HistoryEntry>>, String> {
+        Ok(None)
+    }
 }
 
-fn default_message_bottles_enabled() -> bool {
-    false
+/// One increase in a creature's count.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub at: String,
+    pub creature_id: String,
+    pub delta: u32,
 }
 
-fn default_message_bottles_prompted() -> bool {
-    false
+/// One `save.reef` per profile, rewritten whole on every save, with a backup
+/// and daily snapshots.
+pub struct JsonStorage {
+    dir: PathBuf,
 }
 
-fn default_close_behavior() -> String {
-    "ask".to_string()
+impl JsonStorage {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
 }
 
-pub fn save_dir() -> PathBuf {
-    let base = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-    base.join("ascii-reef")
+impl Storage for JsonStorage {
+    fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn load(&mut self) -> (Option<SaveFile>, Option<LoadNotice>) {
+        let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        load_from(&self.dir, &now)
+    }
+
+    fn save(&mut self, state: &GameState) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Failed to create save dir: {}", e))?;
+        let created = self
+            .created()
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+        let save = SaveFile::from_state(state, created, false);
+        let json = serde_json::to_string_pretty(&save)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        write_save(&self.dir, &json, &mut |_| Ok(()))?;
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        if let Err(e) = snapshot_daily(&self.dir, &today) {
+            eprintln!("Save: {}", e);
+        }
+        Ok(())
+    }
+
+    fn created(&self) -> Option<String> {
+        fs::read_to_string(self.dir.join(SAVE_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| v["meta"]["created"].as_str().map(|s| s.to_string()))
+    }
+}
+
+static STORAGE: Mutex<Option<Box<dyn Storage>>> = Mutex::new(None);
+
+/// Storage for the profile in `dir`: SQLite once it has a database or when
+/// selected (migrating its JSON save the first time), JSON otherwise.
+fn open_storage(dir: &Path) -> Box<dyn Storage> {
+    if StorageKind::selected() == StorageKind::Json && !crate::sqlite::exists(dir) {
+        return Box::new(JsonStorage::new(dir));
+    }
+    match crate::sqlite::SqliteStorage::open_or_migrate(dir) {
+        Ok(storage) => Box::new(storage),
+        Err(e) => {
+            eprintln!("Save: SQLite storage unavailable, using JSON: {}", e);
+            Box::new(JsonStorage::new(dir))
+        }
+    }
+}
+
+/// Run `f` with the active profile's storage, opening it on first use or
+/// after a profile switch.
+fn with_storage<T>(f: impl FnOnce(&mut dyn Storage) -> T) -> T {
+    let dir = crate::profiles::active_dir();
+    let mut storage = STORAGE.lock().unwrap_or_else(|p| p.into_inner());
+    if storage.as_ref().is_some_and(|s| s.dir() != dir) {
+        *storage = None;
+    }
+    f(storage.get_or_insert_with(|| open_storage(&dir)).as_mut())
 }
 
-pub fn save_path() -> PathBuf {
-    save_dir().join("save.reef")
+/// Close the active storage, e.g. so its files can be moved.
+pub fn close_storage() {
+    *STORAGE.lock().unwrap_or_else(|p| p.into_inner()) = None;
 }
 
-fn backup_path() -> PathBuf {
-    save_dir().join("save.reef.bak")
+/// Points between the steps of `write_save`, where tests inject crashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    TmpWritten,
+    BackupStaged,
+    BackupReplaced,
+    SaveReplaced,
 }
 
-fn tmp_path() -> PathBuf {
-    save_dir().join("save.reef.tmp")
+/// Replace the save in `dir` with `json`, keeping the previous save as the
+/// backup. Each file is staged, synced and then renamed over its target, so
+/// a crash at any point leaves a complete save and backup on disk.
+fn write_save(
+    dir: &Path,
+    json: &str,
+    crash: &mut dyn FnMut(Step) -> Result<(), String>,
+) -> Result<(), String> {
+    let main = dir.join(SAVE_FILE);
+    let bak = dir.join(BACKUP_FILE);
+    let tmp = durable::tmp_path(&main);
+    durable::write_synced(&tmp, json.as_bytes())?;
+    crash(Step::TmpWritten)?;
+
+    // Only a save that parses becomes the backup, so a damaged file never
+    // replaces a good one
+    let current = fs::read_to_string(&main)
+        .ok()
+        .filter(|s| serde_json::from_str::<SaveFile>(s).is_ok());
+    if let Some(current) = current {
+        let bak_tmp = durable::tmp_path(&bak);
+        durable::write_synced(&bak_tmp, current.as_bytes())?;
+        crash(Step::BackupStaged)?;
+        durable::replace(&bak_tmp, &bak)?;
+        crash(Step::BackupReplaced)?;
+    }
+
+    durable::replace(&tmp, &main)?;
+    crash(Step::SaveReplaced)
 }
 
+/// Write game progress to the active profile's storage. Settings are saved
+/// separately (`settings::save`).
 pub fn atomic_save(state: &GameState) -> Result<(), String> {
-    let dir = save_dir();
-    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create save dir: {}", e))?;
+    with_storage(|storage| storage.save(state))
+}
+
+/// Recent discoveries from the active storage's journal, newest first, or
+/// `None` for JSON saves, which keep no journal.
+pub fn journal(limit: usize) -> Result<Option<Vec<HistoryEntry>>, String> {
+    with_storage(|storage| storage.history(limit))
+}
 
-    let now = chrono::Utc::now().to_rfc3339();
+/// When an owned creature was first seen.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirstSighting {
+    pub at: String,
+    pub creature_id: String,
+}
 
-    // Preserve the original creation timestamp from the existing save file
-    let created = fs::read_to_string(save_path())
-        .ok()
-        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .and_then(|v| v["meta"]["created"].as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| now.clone());
-
-    let save = SaveFile {
-        version: 2,
-        meta: SaveMeta {
-            created,
-            last_saved: now,
-            app_version: env!("CARGO_PKG_VERSION").to_string(),
-        },
-        collection: state.collection.clone(),
-        progression: SaveProgression {
-            pool_energy: state.pool_energy.clone(),
-            energy: None,
-            total_discoveries: state.total_discoveries,
-            pity: state.pity.clone(),
-        },
-        display: SaveDisplay {
-            position: state.position,
-            size_index: state.size_index,
-            send_scores: state.send_scores,
-            sound_enabled: state.sound_enabled,
-            music_volume: state.music_volume,
-            day_night_cycle: state.day_night_cycle.clone(),
-            message_bottles_enabled: state.message_bottles_enabled,
-            message_bottles_prompted: state.message_bottles_prompted,
-            close_behavior: state.close_behavior.clone(),
-            hidden_creatures: state.hidden_creatures.clone(),
-        },
-    };
+/// First sightings, newest first: all a JSON save knows about the past, as
+/// it keeps counts but not when they grew.
+pub fn first_sightings(state: &GameState, limit: usize) -> Vec<FirstSighting> {
+    let mut sightings: Vec<FirstSighting> = state
+        .collection
+        .iter()
+        .map(|(id, owned)| FirstSighting {
+            at: owned.first_seen.clone(),
+            creature_id: id.clone(),
+        })
+        .collect();
+    sightings.sort_by(|a, b| {
+        b.at.cmp(&a.at)
+            .then_with(|| a.creature_id.cmp(&b.creature_id))
+    });
+    sightings.truncate(limit);
+    sightings
+}
 
-    let json =
-        serde_json::to_string_pretty(&save).map_err(|e| format!("Failed to serialize: {}", e))?;
+/// Write progress and settings, e.g. on exit when the window position has
+/// only been tracked in memory.
+pub fn save_all(state: &GameState) -> Result<(), String> {
+    atomic_save(state)?;
+    crate::settings::save(&state.settings)
+}
 
-    let tmp = tmp_path();
-    let main = save_path();
-    let bak = backup_path();
+/// Write the chosen part of the player's data to `path`. A `settings` export
+/// is a settings file; the others are save files, `both` with `display` set.
+pub fn export(state: &GameState, path: &Path, scope: SaveScope) -> Result<(), String> {
+    let json = match scope {
+        SaveScope::Settings => serde_json::to_string_pretty(&SettingsFile::new(&state.settings)),
+        _ => serde_json::to_string_pretty(&SaveFile::from_state(
+            state,
+            with_storage(|storage| storage.created())
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            scope == SaveScope::Both,
+        )),
+    }
+    .map_err(|e| format!("Failed to serialize: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to export: {}", e))
+}
 
-    // Write to tmp
-    fs::write(&tmp, &json).map_err(|e| format!("Failed to write tmp: {}", e))?;
+/// What an import file offers, already narrowed to the requested scope.
+pub struct Imported {
+    pub progress: Option<GameState>,
+    /// Raw settings object, so only keys actually present get applied
+    pub settings: Option<serde_json::Value>,
+}
 
-    // Backup existing save
-    if main.exists() {
-        let _ = fs::copy(&main, &bak);
+/// Read a save file (v2, v3 or a `both` export) or a settings file.
+pub fn read_import(path: &Path, scope: SaveScope) -> Result<Imported, String> {
+    let data =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&data).map_err(|e| format!("Invalid save file: {}", e))?;
+
+    let (progress, settings) = if value.get("progression").is_some() {
+        let save: SaveFile = serde_json::from_value(value.clone())
+            .map_err(|e| format!("Invalid save file: {}", e))?;
+        (Some(save.into_state()), value.get("display").cloned())
+    } else if value.get("settings").is_some() {
+        serde_json::from_value::<SettingsFile>(value.clone())
+            .map_err(|e| format!("Invalid settings file: {}", e))?;
+        (None, value.get("settings").cloned())
+    } else {
+        return Err("Not a save or settings file".to_string());
+    };
+
+    let imported = Imported {
+        progress: progress.filter(|_| scope.includes_progress()),
+        settings: settings.filter(|_| scope.includes_settings()),
+    };
+    if imported.progress.is_none() && imported.settings.is_none() {
+        return Err(format!("File has nothing to import for scope {:?}", scope));
     }
+    Ok(imported)
+}
 
-    // Rename tmp to main
-    fs::rename(&tmp, &main).map_err(|e| format!("Failed to rename: {}", e))?;
+/// Follow `renames` from `id` to its current name (chains allowed, cycles cut off).
+fn resolve_rename<'a>(renames: &'a HashMap<String, String>, mut id: &'a str) -> &'a str {
+    for _ in 0..renames.len() {
+        match renames.get(id) {
+            Some(next) => id = next,
+            None => break,
+        }
+    }
+    id
+}
 
-    Ok(())
+/// Rewrite renamed creature ids in the collection, hidden list and quarantine.
+/// If both the old and new id are owned the two records are merged.
+fn apply_renames(state: &mut GameState, renames: &HashMap<String, String>) {
+    if renames.is_empty() {
+        return;
+    }
+    for collection in [&mut state.collection, &mut state.orphaned.collection] {
+        let renamed: Vec<String> = collection
+            .keys()
+            .filter(|id| renames.contains_key(*id))
+            .cloned()
+            .collect();
+        for old in renamed {
+            let new = resolve_rename(renames, &old).to_string();
+            if let Some(owned) = collection.remove(&old) {
+                eprintln!("Save: creature {:?} renamed to {:?}", old, new);
+                match collection.get_mut(&new) {
+                    Some(existing) => existing.absorb(owned),
+                    None => {
+                        collection.insert(new, owned);
+                    }
+                }
+            }
+        }
+    }
+    for hidden in [
+        &mut state.settings.hidden_creatures,
+        &mut state.orphaned.hidden_creatures,
+    ] {
+        for id in hidden.iter_mut() {
+            *id = resolve_rename(renames, id).to_string();
+        }
+        dedup_ids(hidden);
+    }
+}
+
+pub fn dedup_ids(ids: &mut Vec<String>) {
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(id.clone()));
+}
+
+/// Move collection and hidden entries with no known definition into the
+/// quarantined `orphaned` section, and restore quarantined ones whose
+/// definition is back. Returns the ids left in quarantine.
+pub fn reconcile_creatures(state: &mut GameState, registry: &CreatureRegistry) -> Vec<String> {
+    let unknown: Vec<String> = state
+        .collection
+        .keys()
+        .filter(|id| !registry.is_known(id))
+        .cloned()
+        .collect();
+    for id in unknown {
+        if let Some(owned) = state.collection.remove(&id) {
+            eprintln!("Save: unknown creature {:?}, moving to orphaned", id);
+            match state.orphaned.collection.get_mut(&id) {
+                Some(existing) => existing.absorb(owned),
+                None => {
+                    state.orphaned.collection.insert(id, owned);
+                }
+            }
+        }
+    }
+    let restored: Vec<String> = state
+        .orphaned
+        .collection
+        .keys()
+        .filter(|id| registry.is_known(id))
+        .cloned()
+        .collect();
+    for id in restored {
+        if let Some(owned) = state.orphaned.collection.remove(&id) {
+            eprintln!("Save: creature {:?} is known again, restoring", id);
+            match state.collection.get_mut(&id) {
+                Some(existing) => existing.absorb(owned),
+                None => {
+                    state.collection.insert(id, owned);
+                }
+            }
+        }
+    }
+
+    let (known, unknown): (Vec<String>, Vec<String>) = state
+        .settings
+        .hidden_creatures
+        .drain(..)
+        .chain(state.orphaned.hidden_creatures.drain(..))
+        .partition(|id| registry.is_known(id));
+    state.settings.hidden_creatures = known;
+    state.orphaned.hidden_creatures = unknown;
+    dedup_ids(&mut state.settings.hidden_creatures);
+    dedup_ids(&mut state.orphaned.hidden_creatures);
+
+    let mut orphaned: Vec<String> = state.orphaned.collection.keys().cloned().collect();
+    orphaned.sort();
+    orphaned
 }
 
 /// Clamp and validate all fields of a freshly-loaded or freshly-imported
 /// GameState. Logs a warning and resets any field that is out of range or
-/// contains an unrecognised value. Called after both `load()` and
+/// contains an unrecognised value. Called after both `load_or_default()` and
 /// `import_save` to guard against hand-edited or corrupted save files.
-pub fn sanitize(state: &mut GameState) {
-    // music_volume must be in [0.0, 1.0]
-    if !state.music_volume.is_finite() || !(0.0..=1.0).contains(&state.music_volume) {
-        eprintln!(
-            "Save: music_volume {:?} out of range, resetting to default",
-            state.music_volume
-        );
-        state.music_volume = 0.08;
-    }
+/// Creature ids are renamed per `creature_renames.json`, then any the
+/// registry does not know are quarantined rather than dropped.
+pub fn sanitize(state: &mut GameState, registry: &CreatureRegistry) {
+    apply_renames(state, &CREATURE_RENAMES);
+    reconcile_creatures(state, registry);
 
-    // size_index must map to a known preset
-    if state.size_index >= crate::tray::SIZE_PRESETS.len() {
-        eprintln!(
-            "Save: size_index {} out of range (max {}), resetting to default",
-            state.size_index,
-            crate::tray::SIZE_PRESETS.len() - 1
-        );
-        state.size_index = 1;
-    }
+    // Settings are checked against their schema (ranges, known enum values)
+    state.settings.sanitize();
 
     // pool_energy: remove unknown pool keys, then ensure all required ones exist
     state
@@ -217,124 +550,218 @@ pub fn sanitize(state: &mut GameState) {
             *val = MAX_POOL_ENERGY;
         }
     }
+}
 
-    // day_night_cycle must be one of the known modes
-    if !matches!(
-        state.day_night_cycle.as_str(),
-        "computer" | "5min" | "10min" | "60min" | "3hours"
-    ) {
-        eprintln!(
-            "Save: unknown day_night_cycle {:?}, resetting to default",
-            state.day_night_cycle
-        );
-        state.day_night_cycle = "computer".to_string();
-    }
+/// How the last load went when it was not a plain read of the main save.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadNotice {
+    /// File the reef was recovered from, or `None` if none could be read
+    pub recovered_from: Option<String>,
+    /// Unreadable saves, renamed aside so nothing overwrites them
+    pub quarantined: Vec<String>,
+}
 
-    // close_behavior must be one of the known values
-    if !matches!(state.close_behavior.as_str(), "ask" | "hide" | "close") {
-        eprintln!(
-            "Save: unknown close_behavior {:?}, resetting to default",
-            state.close_behavior
-        );
-        state.close_behavior = "ask".to_string();
+/// Kept until the aquarium asks for it, since it may not be listening yet.
+static LOAD_NOTICE: Mutex<Option<LoadNotice>> = Mutex::new(None);
+
+pub fn take_load_notice() -> Option<LoadNotice> {
+    LOAD_NOTICE.lock().unwrap_or_else(|p| p.into_inner()).take()
+}
+
+/// Daily snapshots with extension `ext`, newest first.
+pub(crate) fn snapshot_paths(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let suffix = format!(".{}", ext);
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir.join(SNAPSHOT_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("save-") && n.ends_with(&suffix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Dated names sort oldest first
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+/// Where the snapshot with extension `ext` taken on `day` goes.
+pub(crate) fn snapshot_path(dir: &Path, day: &str, ext: &str) -> PathBuf {
+    dir.join(SNAPSHOT_DIR).join(format!("save-{}.{}", day, ext))
+}
+
+/// Drop all but the newest `SNAPSHOTS_KEPT` snapshots with extension `ext`.
+pub(crate) fn prune_snapshots(dir: &Path, ext: &str) {
+    for old in snapshot_paths(dir, ext).iter().skip(SNAPSHOTS_KEPT) {
+        let _ = fs::remove_file(old);
     }
+}
 
-    // position must be finite (no NaN/Inf from corrupted saves)
-    if !state.position.0.is_finite() || !state.position.1.is_finite() {
-        state.position = (0.0, 0.0);
+/// Copy the save into `snapshots/` once a day, keeping the newest few.
+fn snapshot_daily(dir: &Path, today: &str) -> Result<(), String> {
+    let path = snapshot_path(dir, today, "reef");
+    let main = dir.join(SAVE_FILE);
+    if path.exists() || !main.exists() {
+        return Ok(());
     }
+    fs::create_dir_all(dir.join(SNAPSHOT_DIR))
+        .map_err(|e| format!("Failed to create snapshot dir: {}", e))?;
+    let data = fs::read(&main).map_err(|e| format!("Failed to read save: {}", e))?;
+    durable::write_atomic(&path, &data)?;
+    prune_snapshots(dir, "reef");
+    Ok(())
 }
 
-pub fn load() -> Result<GameState, String> {
-    let main = save_path();
-    let bak = backup_path();
+/// Rename an unreadable save out of the way, stamped with `now`.
+pub fn quarantine(path: &Path, now: &str) -> Result<PathBuf, String> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now));
+    let target = path.with_file_name(name);
+    fs::rename(path, &target)
+        .map_err(|e| format!("Failed to quarantine {}: {}", path.display(), e))?;
+    Ok(target)
+}
 
-    let data = if main.exists() {
-        fs::read_to_string(&main).map_err(|e| format!("Failed to read save: {}", e))?
-    } else if bak.exists() {
-        eprintln!("Main save not found, loading backup");
-        fs::read_to_string(&bak).map_err(|e| format!("Failed to read backup: {}", e))?
-    } else {
-        return Ok(GameState::default());
-    };
+/// Load the first save in `dir` that reads and parses, trying the main
+/// save, then the backup, then snapshots newest first. Unreadable ones are
+/// quarantined. `None` means no save at all, so no notice either.
+fn load_from(dir: &Path, now: &str) -> (Option<SaveFile>, Option<LoadNotice>) {
+    let mut candidates = vec![dir.join(SAVE_FILE), dir.join(BACKUP_FILE)];
+    candidates.extend(snapshot_paths(dir, "reef"));
+
+    let mut quarantined = Vec::new();
+    for (i, path) in candidates.iter().enumerate() {
+        if !path.exists() {
+            continue;
+        }
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_str::<SaveFile>(&data).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(save) => {
+                let notice = (i > 0 || !quarantined.is_empty()).then(|| LoadNotice {
+                    recovered_from: Some(path.display().to_string()),
+                    quarantined: quarantined.clone(),
+                });
+                return (Some(save), notice);
+            }
+            Err(e) => {
+                eprintln!("Save: {} is unreadable: {}", path.display(), e);
+                match quarantine(path, now) {
+                    Ok(moved) => quarantined.push(moved.display().to_string()),
+                    Err(e) => eprintln!("Save: {}", e),
+                }
+            }
+        }
+    }
+    let notice = (!quarantined.is_empty()).then_some(LoadNotice {
+        recovered_from: None,
+        quarantined,
+    });
+    (None, notice)
+}
 
-    let save: SaveFile =
-        serde_json::from_str(&data).map_err(|e| format!("Failed to parse save: {}", e))?;
-
-    // Restore pool_energy, with backward compat for old saves
-    let pool_energy = if save.progression.pool_energy.is_empty() {
-        // Old save format: put legacy energy into typing pool
-        let legacy = save.progression.energy.unwrap_or(0);
-        let mut m = std::collections::HashMap::new();
-        m.insert("typing".to_string(), legacy);
-        m.insert("click".to_string(), 0);
-        m.insert("audio".to_string(), 0);
-        m
-    } else {
-        save.progression.pool_energy
-    };
+/// Load the active profile, recovering from the backup or a snapshot if the
+/// main save is damaged. Starts a fresh reef (keeping any saved settings)
+/// only when nothing can be read; damaged files are kept aside either way.
+pub fn load_or_default(registry: &CreatureRegistry) -> GameState {
+    let saved_settings = crate::settings::load();
+    let (save, notice) = with_storage(|storage| storage.load());
+    if let Some(notice) = &notice {
+        eprintln!("Save: recovered with {:?}", notice);
+    }
+    *LOAD_NOTICE.lock().unwrap_or_else(|p| p.into_inner()) = notice;
 
-    let mut state = GameState {
-        collection: save.collection,
-        pool_energy,
-        total_discoveries: save.progression.total_discoveries,
-        pity: save.progression.pity,
-        position: save.display.position,
-        size_index: save.display.size_index,
-        send_scores: save.display.send_scores,
-        sound_enabled: save.display.sound_enabled,
-        music_volume: save.display.music_volume,
-        day_night_cycle: save.display.day_night_cycle,
-        message_bottles_enabled: save.display.message_bottles_enabled,
-        message_bottles_prompted: save.display.message_bottles_prompted,
-        close_behavior: save.display.close_behavior,
-        hidden_creatures: save.display.hidden_creatures,
+    let Some(save) = save else {
+        return GameState {
+            settings: saved_settings.unwrap_or_default(),
+            ..GameState::default()
+        };
     };
-    sanitize(&mut state);
-    Ok(state)
+    let migrate = saved_settings.is_none() && save.display.is_some();
+    let mut state = save.into_state();
+    if let Some(settings) = saved_settings {
+        state.settings = settings;
+    }
+    sanitize(&mut state, registry);
+
+    // v2 saves carried settings inline; move them out before the next
+    // progress save drops them
+    if migrate {
+        if let Err(e) = crate::settings::save(&state.settings) {
+            eprintln!("Save: failed to migrate settings: {}", e);
+        }
+    }
+    state
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::energy::CreatureDef;
+    use crate::events::EventCalendar;
+    use crate::settings::{CloseBehavior, DayNightCycle};
     use crate::state::GameState;
+    use crate::test_util::{owned, TempDir};
+    use std::sync::Arc;
 
     fn make_state() -> GameState {
         GameState::default()
     }
 
+    fn registry() -> CreatureRegistry {
+        let def = |id: &str| CreatureDef {
+            id: id.to_string(),
+            pool: "typing".to_string(),
+            rarity: "common".to_string(),
+            extra: serde_json::Map::new(),
+        };
+        CreatureRegistry {
+            builtin: vec![def("t_common_01"), def("t_common_02")],
+            packs: Vec::new(),
+            calendar: Arc::new(EventCalendar::default()),
+        }
+    }
+
     // --- sanitize: valid inputs are unchanged ---
 
     #[test]
     fn sanitize_valid_state_unchanged() {
         let mut s = make_state();
-        s.music_volume = 0.5;
-        s.size_index = 2;
-        s.day_night_cycle = "10min".to_string();
-        s.close_behavior = "hide".to_string();
-        s.position = (100.0, 200.0);
+        s.settings.music_volume = 0.5;
+        s.settings.size_index = 2;
+        s.settings.day_night_cycle = DayNightCycle::TenMinutes;
+        s.settings.close_behavior = CloseBehavior::Hide;
+        s.settings.position = (100.0, 200.0);
         s.pool_energy.insert("typing".to_string(), 20);
         s.pool_energy.insert("click".to_string(), 10);
         s.pool_energy.insert("audio".to_string(), 5);
 
-        sanitize(&mut s);
+        sanitize(&mut s, &registry());
 
-        assert_eq!(s.music_volume, 0.5);
-        assert_eq!(s.size_index, 2);
-        assert_eq!(s.day_night_cycle, "10min");
-        assert_eq!(s.close_behavior, "hide");
-        assert_eq!(s.position, (100.0, 200.0));
+        assert_eq!(s.settings.music_volume, 0.5);
+        assert_eq!(s.settings.size_index, 2);
+        assert_eq!(s.settings.day_night_cycle, DayNightCycle::TenMinutes);
+        assert_eq!(s.settings.close_behavior, CloseBehavior::Hide);
+        assert_eq!(s.settings.position, (100.0, 200.0));
         assert_eq!(*s.pool_energy.get("typing").unwrap(), 20);
     }
 
     #[test]
     fn sanitize_all_valid_day_night_cycles() {
-        for cycle in &["computer", "5min", "10min", "60min", "3hours"] {
+        for (cycle, _) in DayNightCycle::OPTIONS {
             let mut s = make_state();
-            s.day_night_cycle = cycle.to_string();
-            sanitize(&mut s);
+            s.settings.set("dayNightCycle", (*cycle).into()).unwrap();
+            let before = s.settings.day_night_cycle;
+            sanitize(&mut s, &registry());
             assert_eq!(
-                s.day_night_cycle, *cycle,
+                s.settings.day_night_cycle, before,
                 "valid cycle '{cycle}' should not be changed"
             );
         }
@@ -342,13 +769,17 @@ mod tests {
 
     #[test]
     fn sanitize_all_valid_close_behaviors() {
-        for behavior in &["ask", "hide", "close"] {
+        for behavior in [
+            CloseBehavior::Ask,
+            CloseBehavior::Hide,
+            CloseBehavior::Close,
+        ] {
             let mut s = make_state();
-            s.close_behavior = behavior.to_string();
-            sanitize(&mut s);
+            s.settings.close_behavior = behavior;
+            sanitize(&mut s, &registry());
             assert_eq!(
-                s.close_behavior, *behavior,
-                "valid behavior '{behavior}' should not be changed"
+                s.settings.close_behavior, behavior,
+                "valid behavior {behavior:?} should not be changed"
             );
         }
     }
@@ -358,37 +789,37 @@ mod tests {
     #[test]
     fn sanitize_music_volume_nan_resets() {
         let mut s = make_state();
-        s.music_volume = f32::NAN;
-        sanitize(&mut s);
-        assert_eq!(s.music_volume, 0.08);
+        s.settings.music_volume = f32::NAN;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.music_volume, 0.08);
     }
 
     #[test]
     fn sanitize_music_volume_negative_resets() {
         let mut s = make_state();
-        s.music_volume = -0.5;
-        sanitize(&mut s);
-        assert_eq!(s.music_volume, 0.08);
+        s.settings.music_volume = -0.5;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.music_volume, 0.08);
     }
 
     #[test]
     fn sanitize_music_volume_above_one_resets() {
         let mut s = make_state();
-        s.music_volume = 1.5;
-        sanitize(&mut s);
-        assert_eq!(s.music_volume, 0.08);
+        s.settings.music_volume = 1.5;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.music_volume, 0.08);
     }
 
     #[test]
     fn sanitize_music_volume_boundary_values_kept() {
         let mut s = make_state();
-        s.music_volume = 0.0;
-        sanitize(&mut s);
-        assert_eq!(s.music_volume, 0.0);
+        s.settings.music_volume = 0.0;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.music_volume, 0.0);
 
-        s.music_volume = 1.0;
-        sanitize(&mut s);
-        assert_eq!(s.music_volume, 1.0);
+        s.settings.music_volume = 1.0;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.music_volume, 1.0);
     }
 
     // --- sanitize: size_index ---
@@ -396,9 +827,9 @@ mod tests {
     #[test]
     fn sanitize_size_index_out_of_range_resets() {
         let mut s = make_state();
-        s.size_index = 9999;
-        sanitize(&mut s);
-        assert_eq!(s.size_index, 1);
+        s.settings.size_index = 9999;
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.size_index, 1);
     }
 
     // --- sanitize: pool_energy ---
@@ -407,7 +838,7 @@ mod tests {
     fn sanitize_unknown_pool_keys_removed() {
         let mut s = make_state();
         s.pool_energy.insert("hacking".to_string(), 50);
-        sanitize(&mut s);
+        sanitize(&mut s, &registry());
         assert!(
             !s.pool_energy.contains_key("hacking"),
             "unknown key 'hacking' should be removed"
@@ -421,7 +852,7 @@ mod tests {
     fn sanitize_missing_pool_keys_inserted_as_zero() {
         let mut s = make_state();
         s.pool_energy.clear();
-        sanitize(&mut s);
+        sanitize(&mut s, &registry());
         assert_eq!(*s.pool_energy.get("typing").unwrap(), 0);
         assert_eq!(*s.pool_energy.get("click").unwrap(), 0);
         assert_eq!(*s.pool_energy.get("audio").unwrap(), 0);
@@ -431,7 +862,7 @@ mod tests {
     fn sanitize_pool_energy_excessive_value_capped() {
         let mut s = make_state();
         s.pool_energy.insert("typing".to_string(), u32::MAX);
-        sanitize(&mut s);
+        sanitize(&mut s, &registry());
         assert_eq!(*s.pool_energy.get("typing").unwrap(), 1_000);
     }
 
@@ -439,28 +870,24 @@ mod tests {
     fn sanitize_pool_energy_at_cap_unchanged() {
         let mut s = make_state();
         s.pool_energy.insert("click".to_string(), 1_000);
-        sanitize(&mut s);
+        sanitize(&mut s, &registry());
         assert_eq!(*s.pool_energy.get("click").unwrap(), 1_000);
     }
 
-    // --- sanitize: day_night_cycle ---
+    // --- load: unrecognised enum values ---
 
     #[test]
-    fn sanitize_unknown_day_night_cycle_resets() {
-        let mut s = make_state();
-        s.day_night_cycle = "24hours".to_string();
-        sanitize(&mut s);
-        assert_eq!(s.day_night_cycle, "computer");
+    fn load_unknown_day_night_cycle_resets() {
+        let display: Settings =
+            serde_json::from_value(serde_json::json!({ "day_night_cycle": "24hours" })).unwrap();
+        assert_eq!(display.day_night_cycle, DayNightCycle::Computer);
     }
 
-    // --- sanitize: close_behavior ---
-
     #[test]
-    fn sanitize_unknown_close_behavior_resets() {
-        let mut s = make_state();
-        s.close_behavior = "explode".to_string();
-        sanitize(&mut s);
-        assert_eq!(s.close_behavior, "ask");
+    fn load_unknown_close_behavior_resets() {
+        let display: Settings =
+            serde_json::from_value(serde_json::json!({ "close_behavior": "explode" })).unwrap();
+        assert_eq!(display.close_behavior, CloseBehavior::Ask);
     }
 
     // --- sanitize: position ---
@@ -468,25 +895,310 @@ mod tests {
     #[test]
     fn sanitize_nan_position_resets() {
         let mut s = make_state();
-        s.position = (f64::NAN, 100.0);
-        sanitize(&mut s);
-        assert_eq!(s.position, (0.0, 0.0));
+        s.settings.position = (f64::NAN, 100.0);
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.position, (0.0, 0.0));
     }
 
     #[test]
     fn sanitize_infinite_position_resets() {
         let mut s = make_state();
-        s.position = (f64::INFINITY, 0.0);
-        sanitize(&mut s);
-        assert_eq!(s.position, (0.0, 0.0));
+        s.settings.position = (f64::INFINITY, 0.0);
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.position, (0.0, 0.0));
     }
 
     #[test]
     fn sanitize_negative_position_kept() {
         // Negative screen coordinates are valid (multi-monitor setups)
         let mut s = make_state();
-        s.position = (-500.0, -200.0);
-        sanitize(&mut s);
-        assert_eq!(s.position, (-500.0, -200.0));
+        s.settings.position = (-500.0, -200.0);
+        sanitize(&mut s, &registry());
+        assert_eq!(s.settings.position, (-500.0, -200.0));
+    }
+
+    // --- sanitize: unknown creatures and renames ---
+
+    #[test]
+    fn sanitize_quarantines_unknown_creatures() {
+        let mut s = make_state();
+        s.collection
+            .insert("t_common_01".to_string(), owned(2, "2025-01-01"));
+        s.collection
+            .insert("gone:fish".to_string(), owned(5, "2025-02-01"));
+        s.settings.hidden_creatures = vec!["t_common_02".to_string(), "gone:fish".to_string()];
+        sanitize(&mut s, &registry());
+        assert!(s.collection.contains_key("t_common_01"));
+        assert!(!s.collection.contains_key("gone:fish"));
+        assert_eq!(s.orphaned.collection["gone:fish"].count, 5);
+        assert_eq!(s.settings.hidden_creatures, vec!["t_common_02".to_string()]);
+        assert_eq!(s.orphaned.hidden_creatures, vec!["gone:fish".to_string()]);
+    }
+
+    #[test]
+    fn reconcile_restores_quarantined_creature_when_known_again() {
+        let mut s = make_state();
+        s.collection
+            .insert("t_common_01".to_string(), owned(1, "2025-03-01"));
+        s.orphaned
+            .collection
+            .insert("t_common_01".to_string(), owned(4, "2025-01-01"));
+        s.orphaned.hidden_creatures = vec!["t_common_02".to_string()];
+        let orphaned = reconcile_creatures(&mut s, &registry());
+        assert!(orphaned.is_empty());
+        assert!(s.orphaned.is_empty());
+        assert_eq!(s.collection["t_common_01"].count, 5);
+        assert_eq!(s.collection["t_common_01"].first_seen, "2025-01-01");
+        assert_eq!(s.settings.hidden_creatures, vec!["t_common_02".to_string()]);
+    }
+
+    #[test]
+    fn renames_move_and_merge_catches() {
+        let renames: HashMap<String, String> = [
+            ("old_a", "mid_a"),
+            ("mid_a", "t_common_01"),
+            ("old_b", "t_common_02"),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+        let mut s = make_state();
+        s.collection
+            .insert("old_a".to_string(), owned(3, "2024-06-01"));
+        s.collection
+            .insert("t_common_01".to_string(), owned(1, "2025-01-01"));
+        s.orphaned
+            .collection
+            .insert("old_b".to_string(), owned(2, "2024-01-01"));
+        s.settings.hidden_creatures = vec!["old_a".to_string(), "t_common_01".to_string()];
+
+        apply_renames(&mut s, &renames);
+        reconcile_creatures(&mut s, &registry());
+
+        assert_eq!(s.collection["t_common_01"].count, 4);
+        assert_eq!(s.collection["t_common_01"].first_seen, "2024-06-01");
+        assert_eq!(s.collection["t_common_02"].count, 2);
+        assert!(s.orphaned.is_empty());
+        assert_eq!(s.settings.hidden_creatures, vec!["t_common_01".to_string()]);
+    }
+
+    // --- settings split and import ---
+
+    #[test]
+    fn v2_save_with_display_still_loads() {
+        let v2 = serde_json::json!({
+            "version": 2,
+            "meta": { "created": "2025-01-01", "lastSaved": "2025-01-02", "appVersion": "0.1.0" },
+            "collection": { "t_common_01": { "count": 2, "firstSeen": "2025-01-01" } },
+            "progression": { "pool_energy": { "typing": 5 }, "totalDiscoveries": 2 },
+            "display": { "position": [10.0, 20.0], "size_index": 3, "close_behavior": "hide" },
+        });
+        let save: SaveFile = serde_json::from_value(v2).unwrap();
+        let state = save.into_state();
+        assert_eq!(state.collection["t_common_01"].count, 2);
+        assert_eq!(state.settings.size_index, 3);
+        assert_eq!(state.settings.position, (10.0, 20.0));
+        assert_eq!(state.settings.close_behavior, CloseBehavior::Hide);
+    }
+
+    #[test]
+    fn progress_save_omits_settings() {
+        let mut s = make_state();
+        s.settings.size_index = 4;
+        let progress = serde_json::to_value(SaveFile::from_state(&s, "c".into(), false)).unwrap();
+        assert!(progress.get("display").is_none());
+        assert_eq!(progress["version"], SAVE_VERSION);
+        let both = serde_json::to_value(SaveFile::from_state(&s, "c".into(), true)).unwrap();
+        assert_eq!(both["display"]["size_index"], 4);
+    }
+
+    #[test]
+    fn import_respects_scope() {
+        let dir = TempDir::new("import");
+        let mut s = make_state();
+        s.settings.size_index = 5;
+        s.collection
+            .insert("t_common_01".to_string(), owned(1, "2025-01-01"));
+
+        let both = dir.join("both.reef");
+        export(&s, &both, SaveScope::Both).unwrap();
+        let imported = read_import(&both, SaveScope::Progress).unwrap();
+        assert!(imported.progress.is_some() && imported.settings.is_none());
+        let imported = read_import(&both, SaveScope::Both).unwrap();
+        assert_eq!(imported.settings.unwrap()["size_index"], 5);
+
+        let settings = dir.join("settings.json");
+        export(&s, &settings, SaveScope::Settings).unwrap();
+        assert!(read_import(&settings, SaveScope::Progress).is_err());
+        let imported = read_import(&settings, SaveScope::Both).unwrap();
+        assert!(imported.progress.is_none() && imported.settings.is_some());
+
+        let progress = dir.join("progress.reef");
+        export(&s, &progress, SaveScope::Progress).unwrap();
+        assert!(read_import(&progress, SaveScope::Settings).is_err());
+    }
+
+    #[test]
+    fn first_sightings_are_newest_first() {
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("crab".into(), owned(4, "2026-01-02"));
+        state
+            .collection
+            .insert("eel".into(), owned(1, "2026-03-01"));
+        state
+            .collection
+            .insert("fish".into(), owned(9, "2026-01-02"));
+        let ids: Vec<String> = first_sightings(&state, 2)
+            .into_iter()
+            .map(|s| s.creature_id)
+            .collect();
+        assert_eq!(ids, ["eel", "crab"]);
+    }
+
+    #[test]
+    fn rename_cycles_terminate() {
+        let map = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect()
+        };
+        // A cycle comes back round to where it started
+        let cycle = map(&[("a", "b"), ("b", "a")]);
+        assert_eq!(resolve_rename(&cycle, "a"), "a");
+        assert_eq!(resolve_rename(&cycle, "b"), "b");
+        // A chain is followed to its end, from any point on it
+        let chain = map(&[("a", "b"), ("b", "c")]);
+        assert_eq!(resolve_rename(&chain, "a"), "c");
+        assert_eq!(resolve_rename(&chain, "b"), "c");
+        assert_eq!(resolve_rename(&chain, "c"), "c");
+    }
+
+    fn save_json(total_discoveries: u32) -> String {
+        let state = GameState {
+            total_discoveries,
+            ..make_state()
+        };
+        serde_json::to_string_pretty(&SaveFile::from_state(&state, "created".to_string(), false))
+            .unwrap()
+    }
+
+    /// `total_discoveries` of the save at `path`, if it loads.
+    fn loadable(path: &Path) -> Option<u32> {
+        let data = fs::read_to_string(path).ok()?;
+        let save: SaveFile = serde_json::from_str(&data).ok()?;
+        Some(save.into_state().total_discoveries)
+    }
+
+    #[test]
+    fn crash_at_any_step_leaves_a_loadable_save() {
+        let steps = [
+            Step::TmpWritten,
+            Step::BackupStaged,
+            Step::BackupReplaced,
+            Step::SaveReplaced,
+        ];
+        for step in steps {
+            let dir = TempDir::new(&format!("crash-{:?}", step));
+            let (main, bak) = (dir.join(SAVE_FILE), dir.join(BACKUP_FILE));
+            write_save(&dir, &save_json(1), &mut |_| Ok(())).unwrap();
+            write_save(&dir, &save_json(2), &mut |_| Ok(())).unwrap();
+
+            let crashed = write_save(&dir, &save_json(3), &mut |at| {
+                if at == step {
+                    Err("killed".to_string())
+                } else {
+                    Ok(())
+                }
+            });
+            assert!(crashed.is_err(), "{:?}", step);
+            let after = loadable(&main).unwrap_or_else(|| panic!("no save after {:?}", step));
+            assert!(after == 2 || after == 3, "{:?}: save has {}", step, after);
+            let backup = loadable(&bak).unwrap_or_else(|| panic!("no backup after {:?}", step));
+            assert!(
+                backup == 1 || backup == 2,
+                "{:?}: backup has {}",
+                step,
+                backup
+            );
+
+            // A torn staging file from the crash does not get in the way
+            fs::write(durable::tmp_path(&main), "{\"version\":").unwrap();
+            write_save(&dir, &save_json(4), &mut |_| Ok(())).unwrap();
+            assert_eq!(loadable(&main), Some(4));
+            assert_eq!(loadable(&bak), Some(after));
+        }
+    }
+
+    #[test]
+    fn damaged_save_does_not_replace_the_backup() {
+        let dir = TempDir::new("damaged");
+        write_save(&dir, &save_json(1), &mut |_| Ok(())).unwrap();
+        write_save(&dir, &save_json(2), &mut |_| Ok(())).unwrap();
+        fs::write(dir.join(SAVE_FILE), "").unwrap();
+        write_save(&dir, &save_json(3), &mut |_| Ok(())).unwrap();
+        assert_eq!(loadable(&dir.join(SAVE_FILE)), Some(3));
+        assert_eq!(loadable(&dir.join(BACKUP_FILE)), Some(1));
+    }
+
+    #[test]
+    fn load_falls_back_and_quarantines() {
+        let dir = TempDir::new("recover");
+        let total = |save: Option<SaveFile>| save.map(|s| s.into_state().total_discoveries);
+
+        let (save, notice) = load_from(&dir, "t0");
+        assert!(save.is_none() && notice.is_none());
+
+        write_save(&dir, &save_json(1), &mut |_| Ok(())).unwrap();
+        snapshot_daily(&dir, "2025-01-01").unwrap();
+        write_save(&dir, &save_json(2), &mut |_| Ok(())).unwrap();
+        write_save(&dir, &save_json(3), &mut |_| Ok(())).unwrap();
+        let (save, notice) = load_from(&dir, "t1");
+        assert_eq!(total(save), Some(3));
+        assert!(notice.is_none());
+
+        // Main save truncated: the backup is used and the main kept aside
+        fs::write(dir.join(SAVE_FILE), "").unwrap();
+        let (save, notice) = load_from(&dir, "t2");
+        assert_eq!(total(save), Some(2));
+        let notice = notice.unwrap();
+        assert!(notice.recovered_from.unwrap().ends_with(BACKUP_FILE));
+        assert!(notice.quarantined[0].ends_with("save.reef.corrupt-t2"));
+        assert!(dir.join("save.reef.corrupt-t2").exists());
+        assert!(!dir.join(SAVE_FILE).exists());
+
+        // Backup damaged too: the snapshot is next
+        fs::write(dir.join(BACKUP_FILE), "{").unwrap();
+        let (save, notice) = load_from(&dir, "t3");
+        assert_eq!(total(save), Some(1));
+        assert!(notice
+            .unwrap()
+            .recovered_from
+            .unwrap()
+            .ends_with("save-2025-01-01.reef"));
+
+        // Nothing readable: a fresh start, with the damage still on disk
+        fs::write(dir.join(SNAPSHOT_DIR).join("save-2025-01-01.reef"), "x").unwrap();
+        let (save, notice) = load_from(&dir, "t4");
+        assert!(save.is_none());
+        let notice = notice.unwrap();
+        assert_eq!(notice.recovered_from, None);
+        assert_eq!(notice.quarantined.len(), 1);
+    }
+
+    #[test]
+    fn snapshots_are_daily_and_pruned() {
+        let dir = TempDir::new("snapshots");
+        write_save(&dir, &save_json(1), &mut |_| Ok(())).unwrap();
+        for day in 1..=9 {
+            snapshot_daily(&dir, &format!("2025-01-{:02}", day)).unwrap();
+            snapshot_daily(&dir, &format!("2025-01-{:02}", day)).unwrap();
+        }
+        let snapshots = snapshot_paths(&dir, "reef");
+        assert_eq!(snapshots.len(), SNAPSHOTS_KEPT);
+        assert!(snapshots[0].ends_with("snapshots/save-2025-01-09.reef"));
+        assert!(snapshots[6].ends_with("snapshots/save-2025-01-03.reef"));
     }
 }
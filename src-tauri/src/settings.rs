@@ -0,0 +1,709 @@
+//! User settings. `Settings` lives in its own versioned `settings.json`, apart
+//! from game progress, so moving a save between machines keeps each machine's
+//! layout. `SCHEMA` describes every user-editable setting (kind, range, label)
+//! and is the only thing `set_setting`, `sanitize`, the tray and the settings
+//! window validate against. Adding a setting means adding a field here and a
+//! schema entry, nothing else.
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri_plugin_global_shortcut::Shortcut;
+
+/// Bumped when the settings file layout changes incompatibly
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Parse a hotkey setting with the global shortcut plugin's own grammar.
+/// A key that types text needs a modifier, or it would be swallowed in
+/// every other app.
+pub fn parse_shortcut(text: &str) -> Result<Shortcut, String> {
+    let shortcut: Shortcut = text
+        .parse()
+        .map_err(|e| format!("Invalid shortcut {:?}: {}", text, e))?;
+    let key = shortcut.key.to_string();
+    if shortcut.mods.is_empty()
+        && (key.starts_with("Key") || key.starts_with("Digit") || key == "Space")
+    {
+        return Err(format!(
+            "Invalid shortcut {:?}: needs a modifier such as Control or Alt",
+            text
+        ));
+    }
+    Ok(shortcut)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DayNightCycle {
+    /// Follow the computer clock
+    #[default]
+    #[serde(rename = "computer")]
+    Computer,
+    #[serde(rename = "5min")]
+    FiveMinutes,
+    #[serde(rename = "10min")]
+    TenMinutes,
+    #[serde(rename = "60min")]
+    SixtyMinutes,
+    #[serde(rename = "3hours")]
+    ThreeHours,
+}
+
+impl DayNightCycle {
+    /// Stored value and display label of every mode, in menu order
+    pub const OPTIONS: &'static [(&'static str, &'static str)] = &[
+        ("computer", "Computer Time (Default)"),
+        ("5min", "5 min day / 5 min night"),
+        ("10min", "10 min day / 10 min night"),
+        ("60min", "60 min day / 60 min night"),
+        ("3hours", "3 hours day / 3 hours night"),
+    ];
+}
+
+/// What the window X button does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    #[default]
+    Ask,
+    Hide,
+    Close,
+}
+
+impl CloseBehavior {
+    pub const OPTIONS: &'static [(&'static str, &'static str)] = &[
+        ("ask", "Ask Me Each Time"),
+        ("hide", "Hide to Tray"),
+        ("close", "Close App"),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Window position, restored on start (not user-editable)
+    #[serde(default)]
+    pub position: (f64, f64),
+    /// Selected size preset index
+    #[serde(default = "default_size_index")]
+    pub size_index: usize,
+    /// Whether leaderboard score submission is enabled
+    #[serde(default = "default_send_scores")]
+    pub send_scores: bool,
+    /// Whether ambient music is enabled
+    #[serde(default)]
+    pub sound_enabled: bool,
+    /// Music volume (0.0 - 1.0)
+    #[serde(default = "default_music_volume")]
+    pub music_volume: f32,
+    #[serde(default, deserialize_with = "or_default")]
+    pub day_night_cycle: DayNightCycle,
+    /// Whether message-in-a-bottle interactions are enabled
+    #[serde(default)]
+    pub message_bottles_enabled: bool,
+    /// Whether user has already seen the first-time opt-in prompt
+    #[serde(default)]
+    pub message_bottles_prompted: bool,
+    #[serde(default, deserialize_with = "or_default")]
+    pub close_behavior: CloseBehavior,
+    /// IDs of creatures hidden from the aquarium display
+    #[serde(default)]
+    pub hidden_creatures: Vec<String>,
+    /// Ids of installed creature packs the user has switched off
+    #[serde(default)]
+    pub disabled_packs: Vec<String>,
+    /// Shared folder other devices sync through; empty when sync is off
+    #[serde(default)]
+    pub sync_dir: String,
+    /// Whether the local status API (`api`) is served
+    #[serde(default)]
+    pub api_enabled: bool,
+    /// Port of the status API on 127.0.0.1
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
+    /// Global shortcuts (`hotkeys`), empty when unassigned
+    #[serde(default)]
+    pub hotkey_toggle_window: String,
+    #[serde(default)]
+    pub hotkey_toggle_drag_mode: String,
+    #[serde(default)]
+    pub hotkey_open_collection: String,
+    #[serde(default)]
+    pub hotkey_mute_music: String,
+}
+
+fn default_size_index() -> usize {
+    1 // "Medium" (60x16) = default
+}
+
+fn default_send_scores() -> bool {
+    true
+}
+
+fn default_music_volume() -> f32 {
+    0.08
+}
+
+fn default_api_port() -> u16 {
+    7333
+}
+
+/// Fall back to the default instead of failing the whole save on a value
+/// this version does not recognise (hand edits, newer versions).
+fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value.clone()).unwrap_or_else(|_| {
+        eprintln!("Settings: unrecognised value {}, using default", value);
+        T::default()
+    }))
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            position: (0.0, 0.0),
+            size_index: default_size_index(),
+            send_scores: default_send_scores(),
+            sound_enabled: false,
+            music_volume: default_music_volume(),
+            day_night_cycle: DayNightCycle::default(),
+            message_bottles_enabled: false,
+            message_bottles_prompted: false,
+            close_behavior: CloseBehavior::default(),
+            hidden_creatures: Vec::new(),
+            disabled_packs: Vec::new(),
+            sync_dir: String::new(),
+            api_enabled: false,
+            api_port: default_api_port(),
+            hotkey_toggle_window: String::new(),
+            hotkey_toggle_drag_mode: String::new(),
+            hotkey_open_collection: String::new(),
+            hotkey_mute_music: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingKind {
+    Bool,
+    /// Inclusive range
+    Number {
+        min: f64,
+        max: f64,
+    },
+    /// Index into a list of labelled presets
+    Index(Vec<&'static str>),
+    /// One of a fixed set of `(value, label)` strings
+    Choice(&'static [(&'static str, &'static str)]),
+    /// List of creature or pack ids
+    IdList,
+    /// Absolute directory path, or empty for none
+    Dir,
+    /// Keyboard shortcut (`accelerator`), or empty for none
+    Accelerator,
+}
+
+impl SettingKind {
+    fn check(&self, value: &Value) -> Result<(), String> {
+        let ok = match self {
+            SettingKind::Bool => value.is_boolean(),
+            SettingKind::Number { min, max } => value
+                .as_f64()
+                .is_some_and(|v| v.is_finite() && *min <= v && v <= *max),
+            SettingKind::Index(labels) => {
+                value.as_u64().is_some_and(|i| (i as usize) < labels.len())
+            }
+            SettingKind::Choice(options) => value
+                .as_str()
+                .is_some_and(|s| options.iter().any(|(v, _)| *v == s)),
+            SettingKind::IdList => value
+                .as_array()
+                .is_some_and(|ids| ids.iter().all(Value::is_string)),
+            SettingKind::Dir => value
+                .as_str()
+                .is_some_and(|s| s.is_empty() || std::path::Path::new(s).is_absolute()),
+            SettingKind::Accelerator => value
+                .as_str()
+                .is_some_and(|s| s.is_empty() || parse_shortcut(s).is_ok()),
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("expected {}, got {}", self.describe(), value))
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            SettingKind::Bool => "a boolean".to_string(),
+            SettingKind::Number { min, max } => format!("a number from {} to {}", min, max),
+            SettingKind::Index(labels) => format!("an index below {}", labels.len()),
+            SettingKind::Choice(options) => format!(
+                "one of {:?}",
+                options.iter().map(|(v, _)| *v).collect::<Vec<_>>()
+            ),
+            SettingKind::IdList => "a list of ids".to_string(),
+            SettingKind::Dir => "an absolute path or an empty string".to_string(),
+            SettingKind::Accelerator => {
+                "a shortcut like Control+Alt+R or an empty string".to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SettingSpec {
+    /// camelCase name used by the frontend; the field is its snake_case form
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: SettingKind,
+}
+
+impl SettingSpec {
+    fn field(&self) -> String {
+        let mut field = String::new();
+        for c in self.key.chars() {
+            if c.is_ascii_uppercase() {
+                field.push('_');
+            }
+            field.push(c.to_ascii_lowercase());
+        }
+        field
+    }
+
+    /// Description sent to the settings window, including the default value.
+    pub fn to_json(&self) -> Value {
+        let mut spec = json!({
+            "key": self.key,
+            "label": self.label,
+            "default": Settings::default().get(self.key),
+        });
+        let extra = match &self.kind {
+            SettingKind::Bool => json!({ "type": "bool" }),
+            SettingKind::Number { min, max } => json!({ "type": "number", "min": min, "max": max }),
+            SettingKind::Index(labels) => json!({
+                "type": "choice",
+                "options": labels.iter().enumerate()
+                    .map(|(i, label)| json!({ "value": i, "label": label }))
+                    .collect::<Vec<_>>(),
+            }),
+            SettingKind::Choice(options) => json!({
+                "type": "choice",
+                "options": options.iter()
+                    .map(|(value, label)| json!({ "value": value, "label": label }))
+                    .collect::<Vec<_>>(),
+            }),
+            SettingKind::IdList => json!({ "type": "idList" }),
+            SettingKind::Dir => json!({ "type": "dir" }),
+            SettingKind::Accelerator => json!({ "type": "accelerator" }),
+        };
+        if let (Some(spec), Value::Object(extra)) = (spec.as_object_mut(), extra) {
+            spec.extend(extra);
+        }
+        spec
+    }
+}
+
+pub static SCHEMA: Lazy<Vec<SettingSpec>> = Lazy::new(|| {
+    let spec = |key, label, kind| SettingSpec { key, label, kind };
+    vec![
+        spec(
+            "sizeIndex",
+            "Aquarium Size",
+            SettingKind::Index(crate::tray::SIZE_PRESETS.iter().map(|p| p.0).collect()),
+        ),
+        spec(
+            "dayNightCycle",
+            "Day/Night Cycle",
+            SettingKind::Choice(DayNightCycle::OPTIONS),
+        ),
+        spec("soundEnabled", "Sound", SettingKind::Bool),
+        spec(
+            "musicVolume",
+            "Volume",
+            SettingKind::Number { min: 0.0, max: 1.0 },
+        ),
+        spec(
+            "closeBehavior",
+            "When Clicking X",
+            SettingKind::Choice(CloseBehavior::OPTIONS),
+        ),
+        spec("sendScores", "Send Scores", SettingKind::Bool),
+        spec(
+            "messageBottlesEnabled",
+            "Messages in a Bottle",
+            SettingKind::Bool,
+        ),
+        spec(
+            "messageBottlesPrompted",
+            "Messages in a Bottle prompt shown",
+            SettingKind::Bool,
+        ),
+        spec("hiddenCreatures", "Hidden Creatures", SettingKind::IdList),
+        spec(
+            "disabledPacks",
+            "Disabled Creature Packs",
+            SettingKind::IdList,
+        ),
+        spec("syncDir", "Sync Folder", SettingKind::Dir),
+        spec("apiEnabled", "Status API", SettingKind::Bool),
+        spec(
+            "apiPort",
+            "Status API Port",
+            SettingKind::Number {
+                min: 1024.0,
+                max: 65535.0,
+            },
+        ),
+        spec(
+            "hotkeyToggleWindow",
+            "Show/Hide Window",
+            SettingKind::Accelerator,
+        ),
+        spec(
+            "hotkeyToggleDragMode",
+            "Drag Mode",
+            SettingKind::Accelerator,
+        ),
+        spec(
+            "hotkeyOpenCollection",
+            "Open Collection",
+            SettingKind::Accelerator,
+        ),
+        spec("hotkeyMuteMusic", "Mute Music", SettingKind::Accelerator),
+    ]
+});
+
+pub fn spec(key: &str) -> Option<&'static SettingSpec> {
+    SCHEMA.iter().find(|s| s.key == key)
+}
+
+impl Settings {
+    fn fields(&self) -> serde_json::Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        }
+    }
+
+    /// Current value of a schema setting, `None` for unknown keys.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let spec = spec(key)?;
+        self.fields().remove(&spec.field())
+    }
+
+    /// Every schema setting keyed by its frontend name.
+    pub fn to_json(&self) -> serde_json::Map<String, Value> {
+        let mut fields = self.fields();
+        SCHEMA
+            .iter()
+            .filter_map(|spec| Some((spec.key.to_string(), fields.remove(&spec.field())?)))
+            .collect()
+    }
+
+    /// Validate `value` against the schema and store it.
+    pub fn set(&mut self, key: &str, value: Value) -> Result<(), String> {
+        let spec = spec(key).ok_or_else(|| format!("Unknown setting: {}", key))?;
+        spec.kind
+            .check(&value)
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+        if let (SettingKind::Accelerator, Some(shortcut)) = (&spec.kind, value.as_str()) {
+            self.check_shortcut_free(key, shortcut)?;
+        }
+        let mut fields = self.fields();
+        fields.insert(spec.field(), value);
+        *self = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+        Ok(())
+    }
+
+    /// Refuse a shortcut that another setting already uses, however spelled.
+    fn check_shortcut_free(&self, key: &str, shortcut: &str) -> Result<(), String> {
+        if shortcut.is_empty() {
+            return Ok(());
+        }
+        let wanted = parse_shortcut(shortcut)?;
+        let taken = SCHEMA.iter().find(|spec| {
+            spec.key != key
+                && matches!(spec.kind, SettingKind::Accelerator)
+                && self
+                    .get(spec.key)
+                    .and_then(|v| parse_shortcut(v.as_str()?).ok())
+                    .is_some_and(|other| other == wanted)
+        });
+        match taken {
+            Some(spec) => Err(format!("{} is already used for {}", shortcut, spec.label)),
+            None => Ok(()),
+        }
+    }
+
+    /// Reset any setting that fails its schema check (e.g. from a hand-edited
+    /// save) to its default, logging what was replaced.
+    pub fn sanitize(&mut self) {
+        // position must be finite (no NaN/Inf from corrupted saves). Fixed
+        // first: a non-finite field would break the round-trip in `set`.
+        if !self.position.0.is_finite() || !self.position.1.is_finite() {
+            self.position = (0.0, 0.0);
+        }
+
+        let defaults = Settings::default();
+        for spec in SCHEMA.iter() {
+            // Non-finite floats serialize as null, so they fail the check too
+            let value = self.get(spec.key).unwrap_or(Value::Null);
+            if let Err(e) = spec.kind.check(&value) {
+                eprintln!("Save: {} {}, resetting to default", spec.key, e);
+                if let Some(default) = defaults.get(spec.key) {
+                    let _ = self.set(spec.key, default);
+                }
+            }
+        }
+
+        // Walking backwards, a clash can only be with an earlier shortcut, so
+        // the first one keeps it and later duplicates are cleared
+        for spec in SCHEMA.iter().rev() {
+            if !matches!(spec.kind, SettingKind::Accelerator) {
+                continue;
+            }
+            let Some(Value::String(shortcut)) = self.get(spec.key) else {
+                continue;
+            };
+            if let Err(e) = self.check_shortcut_free(spec.key, &shortcut) {
+                eprintln!("Save: {} {}, clearing it", spec.key, e);
+                let _ = self.set(spec.key, json!(""));
+            }
+        }
+    }
+
+    /// Schema settings present in an imported settings object whose value
+    /// differs from ours. Window position is per-machine and never imported.
+    pub fn changes_from(&self, imported: &Value) -> Vec<(&'static str, Value)> {
+        let Some(imported) = imported.as_object() else {
+            return Vec::new();
+        };
+        let current = self.fields();
+        SCHEMA
+            .iter()
+            .filter_map(|spec| {
+                let field = spec.field();
+                let value = imported.get(&field)?;
+                (current.get(&field) != Some(value)).then(|| (spec.key, value.clone()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsFile {
+    pub version: u32,
+    pub settings: Settings,
+}
+
+impl SettingsFile {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            settings: settings.clone(),
+        }
+    }
+}
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Settings file of the active profile.
+pub fn settings_path() -> PathBuf {
+    crate::profiles::active_dir().join(SETTINGS_FILE)
+}
+
+/// Read a settings file; `Ok(None)` if it does not exist yet.
+pub fn read_settings_file(path: &Path) -> Result<Option<Settings>, String> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read settings: {}", e)),
+    };
+    let file: SettingsFile =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    if file.version > SETTINGS_VERSION {
+        eprintln!(
+            "Settings: file version {} is newer than {}, unknown fields are ignored",
+            file.version, SETTINGS_VERSION
+        );
+    }
+    let mut settings = file.settings;
+    settings.sanitize();
+    Ok(Some(settings))
+}
+
+pub fn write_settings_file(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create save dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&SettingsFile::new(settings))
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    crate::durable::write_atomic(path, json.as_bytes())
+}
+
+/// The saved settings, or `None` if there are none yet or they are unreadable.
+pub fn load() -> Option<Settings> {
+    read_settings_file(&settings_path()).unwrap_or_else(|e| {
+        eprintln!("Settings: {}", e);
+        None
+    })
+}
+
+pub fn save(settings: &Settings) -> Result<(), String> {
+    write_settings_file(&settings_path(), settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn every_schema_key_maps_to_a_field() {
+        let settings = Settings::default();
+        for spec in SCHEMA.iter() {
+            let value = settings.get(spec.key);
+            assert!(value.is_some(), "{} has no matching field", spec.key);
+            assert!(
+                spec.kind.check(&value.unwrap()).is_ok(),
+                "default for {} fails its own schema",
+                spec.key
+            );
+        }
+        assert_eq!(settings.to_json().len(), SCHEMA.len());
+    }
+
+    #[test]
+    fn set_validates_against_schema() {
+        let mut s = Settings::default();
+        assert!(s.set("musicVolume", json!(0.5)).is_ok());
+        assert_eq!(s.music_volume, 0.5);
+        assert!(s.set("musicVolume", json!(1.5)).is_err());
+        assert!(s.set("musicVolume", json!("loud")).is_err());
+        assert!(s.set("sizeIndex", json!(9999)).is_err());
+        assert!(s.set("noSuchSetting", json!(true)).is_err());
+        assert!(s.set("closeBehavior", json!("explode")).is_err());
+        assert_eq!(s.music_volume, 0.5);
+
+        s.set("dayNightCycle", json!("60min")).unwrap();
+        assert_eq!(s.day_night_cycle, DayNightCycle::SixtyMinutes);
+        s.set("hiddenCreatures", json!(["t_common_01"])).unwrap();
+        assert_eq!(s.hidden_creatures, vec!["t_common_01".to_string()]);
+    }
+
+    #[test]
+    fn shortcuts_cannot_be_shared() {
+        let mut s = Settings::default();
+        s.set("hotkeyToggleWindow", json!("Ctrl+Alt+R")).unwrap();
+        let err = s
+            .set("hotkeyMuteMusic", json!("alt+control+r"))
+            .unwrap_err();
+        assert!(err.contains("Show/Hide Window"), "{}", err);
+        assert!(s.set("hotkeyMuteMusic", json!("R")).is_err());
+        assert!(s.set("hotkeyMuteMusic", json!("Ctrl+Wheel")).is_err());
+        // Anything the plugin can register is accepted
+        s.set("hotkeyOpenCollection", json!("Alt+Numpad5")).unwrap();
+        s.set("hotkeyToggleDragMode", json!("MediaPlayPause"))
+            .unwrap();
+        assert!(s.set("hotkeyMuteMusic", json!("mediaplaypause")).is_err());
+        // Re-assigning the same action, or clearing, is always fine
+        s.set("hotkeyToggleWindow", json!("Control+Alt+R")).unwrap();
+        s.set("hotkeyToggleWindow", json!("")).unwrap();
+        s.set("hotkeyMuteMusic", json!("Ctrl+Alt+R")).unwrap();
+    }
+
+    #[test]
+    fn sanitize_clears_duplicate_shortcuts() {
+        let mut s: Settings = serde_json::from_value(json!({
+            "hotkey_toggle_window": "Ctrl+Alt+R",
+            "hotkey_toggle_drag_mode": "Ctrl+Alt+D",
+            "hotkey_open_collection": "control+alt+r",
+            "hotkey_mute_music": "Alt+Ctrl+R",
+        }))
+        .unwrap();
+        s.sanitize();
+        assert_eq!(s.hotkey_toggle_window, "Ctrl+Alt+R");
+        assert_eq!(s.hotkey_toggle_drag_mode, "Ctrl+Alt+D");
+        assert_eq!(s.hotkey_open_collection, "");
+        assert_eq!(s.hotkey_mute_music, "");
+    }
+
+    #[test]
+    fn option_tables_match_serde_names() {
+        for (value, _) in DayNightCycle::OPTIONS {
+            assert!(serde_json::from_value::<DayNightCycle>(json!(value)).is_ok());
+        }
+        for (value, _) in CloseBehavior::OPTIONS {
+            assert!(serde_json::from_value::<CloseBehavior>(json!(value)).is_ok());
+        }
+        assert_eq!(json!(CloseBehavior::Hide), json!("hide"));
+        assert_eq!(json!(DayNightCycle::ThreeHours), json!("3hours"));
+    }
+
+    #[test]
+    fn unknown_enum_values_fall_back_to_default() {
+        let s: Settings = serde_json::from_value(json!({
+            "day_night_cycle": "24hours",
+            "close_behavior": "explode",
+            "size_index": 2,
+        }))
+        .unwrap();
+        assert_eq!(s.day_night_cycle, DayNightCycle::Computer);
+        assert_eq!(s.close_behavior, CloseBehavior::Ask);
+        assert_eq!(s.size_index, 2);
+    }
+
+    #[test]
+    fn changes_from_lists_only_present_differing_keys() {
+        let s = Settings::default();
+        let imported = json!({
+            "position": [300.0, 400.0],
+            "size_index": 4,
+            "send_scores": true,
+            "close_behavior": "hide",
+        });
+        let changes = s.changes_from(&imported);
+        assert_eq!(
+            changes,
+            vec![("sizeIndex", json!(4)), ("closeBehavior", json!("hide"))]
+        );
+        assert!(s.changes_from(&json!("not an object")).is_empty());
+    }
+
+    #[test]
+    fn settings_file_round_trips_and_tolerates_newer_versions() {
+        let dir = TempDir::new("settings");
+        let path = dir.join("missing/settings.json");
+        assert_eq!(read_settings_file(&path), Ok(None));
+
+        let mut s = Settings::default();
+        s.set("dayNightCycle", json!("10min")).unwrap();
+        s.position = (12.0, 34.0);
+        write_settings_file(&path, &s).unwrap();
+        assert_eq!(read_settings_file(&path), Ok(Some(s)));
+
+        let newer = json!({
+            "version": SETTINGS_VERSION + 1,
+            "settings": { "music_volume": 7.0, "some_future_setting": 1 },
+        });
+        fs::write(&path, newer.to_string()).unwrap();
+        let loaded = read_settings_file(&path).unwrap().unwrap();
+        assert_eq!(loaded.music_volume, default_music_volume());
+    }
+
+    #[test]
+    fn schema_json_lists_options_and_defaults() {
+        let cycle = spec("dayNightCycle").unwrap().to_json();
+        assert_eq!(cycle["type"], "choice");
+        assert_eq!(cycle["default"], "computer");
+        assert_eq!(cycle["options"].as_array().unwrap().len(), 5);
+        let volume = spec("musicVolume").unwrap().to_json();
+        assert_eq!(volume["max"], 1.0);
+    }
+}
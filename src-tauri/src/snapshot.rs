@@ -0,0 +1,576 @@
+//! Still frame of the aquarium as text, for pasting into chat or commit
+//! messages. Follows the tank's layout (sky above the surface row, the rock
+//! line and sand at the bottom, each creature in its category's depth band)
+//! without animation, decorations or UI.
+use crate::energy::CreatureDef;
+use crate::registry::CreatureRegistry;
+use crate::report::parse_color;
+use crate::state::GameState;
+use crate::tray::SIZE_PRESETS;
+use crate::validate::RARITIES;
+use rand::Rng;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// Plain text
+    Plain,
+    /// Text with 24-bit ANSI colour escapes
+    Ansi,
+}
+
+// Layout and colours from the frontend (environment.js, colors.js)
+const SURFACE_ROW: usize = 2;
+const SURFACE_GLYPHS: [char; 3] = ['~', '-', '='];
+const SAND_GLYPHS: [char; 7] = ['~', '.', ',', '~', '-', '.', ','];
+const SURFACE_COLOR: Rgb = (120, 200, 255);
+const ROCK_COLOR: Rgb = (0x61, 0x61, 0x61);
+const SAND_COLOR: Rgb = (0xa8, 0x89, 0x6c);
+const CREATURE_COLOR: Rgb = (0xb8, 0xc4, 0xcc);
+/// Placement tries per creature before it is left out
+const PLACE_ATTEMPTS: usize = 30;
+
+pub(crate) type Rgb = (u8, u8, u8);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Cell {
+    pub ch: char,
+    pub color: Option<Rgb>,
+}
+
+const EMPTY: Cell = Cell {
+    ch: ' ',
+    color: None,
+};
+
+/// A creature's frames, padded to its width, with its colour.
+#[derive(Clone)]
+pub(crate) struct Sprite {
+    pub id: String,
+    pub rarity: String,
+    pub category: String,
+    pub frames: Vec<Vec<String>>,
+    pub width: usize,
+    pub height: usize,
+    pub color: Rgb,
+}
+
+/// Horizontal flip of one sprite character (`MIRROR_MAP` in sprites.js).
+fn mirror_char(ch: char) -> char {
+    match ch {
+        '<' => '>',
+        '>' => '<',
+        '(' => ')',
+        ')' => '(',
+        '/' => '\\',
+        '\\' => '/',
+        '{' => '}',
+        '}' => '{',
+        '[' => ']',
+        ']' => '[',
+        other => other,
+    }
+}
+
+impl Sprite {
+    pub fn new(def: &CreatureDef) -> Option<Self> {
+        let frames: Vec<Vec<String>> = def
+            .extra
+            .get("frames")?
+            .as_array()?
+            .iter()
+            .filter_map(|frame| {
+                let lines = frame.as_array()?;
+                Some(
+                    lines
+                        .iter()
+                        .filter_map(|l| l.as_str().map(str::to_string))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|lines| !lines.is_empty())
+            .collect();
+        let width = frames.iter().flatten().map(|l| l.chars().count()).max()?;
+        let height = frames.iter().map(Vec::len).max()?;
+        let frames = frames
+            .into_iter()
+            .map(|lines| {
+                lines
+                    .into_iter()
+                    .map(|l| format!("{:<width$}", l, width = width))
+                    .collect()
+            })
+            .collect();
+        let category = def.extra.get("category").and_then(|c| c.as_str());
+        Some(Self {
+            id: def.id.clone(),
+            rarity: def.rarity.clone(),
+            category: category.unwrap_or("swimmer").to_string(),
+            frames,
+            width,
+            height,
+            color: def
+                .extra
+                .get("naturalColor")
+                .and_then(|c| c.as_str())
+                .and_then(parse_color)
+                .unwrap_or(CREATURE_COLOR),
+        })
+    }
+
+    /// Frame `index` facing right, or flipped to face left.
+    pub fn frame(&self, index: usize, mirrored: bool) -> Vec<String> {
+        let lines = &self.frames[index % self.frames.len()];
+        if !mirrored {
+            return lines.clone();
+        }
+        lines
+            .iter()
+            .map(|l| l.chars().rev().map(mirror_char).collect())
+            .collect()
+    }
+}
+
+/// Owned creatures that are not hidden, with how many of each are owned,
+/// in id order. Entries with a count of zero are left out.
+pub(crate) fn visible(state: &GameState, registry: &CreatureRegistry) -> Vec<(Sprite, u32)> {
+    let mut owned: Vec<(Sprite, u32)> = state
+        .collection
+        .iter()
+        .filter(|(id, c)| c.count > 0 && !state.settings.hidden_creatures.contains(*id))
+        .filter_map(|(id, c)| Some((Sprite::new(registry.get(id)?)?, c.count)))
+        .collect();
+    owned.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+    owned
+}
+
+/// How many creatures a tank of this size shows at once.
+pub(crate) fn size_cap(cols: usize, rows: usize) -> usize {
+    (cols * rows / 150).clamp(5, 28)
+}
+
+/// Weight of a rarity when picking what to show (`DISPLAY_WEIGHTS`).
+fn display_weight(rarity: &str) -> f64 {
+    match rarity {
+        "common" => 6.0,
+        "uncommon" => 3.0,
+        "rare" => 1.5,
+        "epic" => 0.5,
+        "legendary" => 0.1,
+        _ => 1.0,
+    }
+}
+
+/// Pick a creature to show by display weight among those with any left.
+pub(crate) fn pick_weighted(owned: &[(Sprite, u32)], rng: &mut impl Rng) -> Option<usize> {
+    let weight = |(sprite, left): &(Sprite, u32)| {
+        if *left > 0 {
+            display_weight(&sprite.rarity)
+        } else {
+            0.0
+        }
+    };
+    let total: f64 = owned.iter().map(weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut roll = rng.gen_range(0.0..total);
+    owned
+        .iter()
+        .position(|o| {
+            roll -= weight(o);
+            roll < 0.0
+        })
+        // Rounding can leave a sliver past the last weight
+        .or_else(|| owned.iter().rposition(|o| weight(o) > 0.0))
+}
+
+/// The rarest of `owned`.
+pub(crate) fn rarest(owned: &[(Sprite, u32)]) -> Option<usize> {
+    let rank = |s: &Sprite| RARITIES.iter().position(|r| *r == s.rarity).unwrap_or(0);
+    (0..owned.len()).max_by_key(|&i| rank(&owned[i].0))
+}
+
+/// Rows the top of a sprite may start on, as in `getRowConstraints`.
+pub(crate) fn row_range(category: &str, height: usize, rows: usize) -> Option<(usize, usize)> {
+    let rock = rows.checked_sub(2)?;
+    let water_top = SURFACE_ROW + 1;
+    let (min, max) = match category {
+        "bottom" => {
+            let row = rock.checked_sub(height)?;
+            (row, row)
+        }
+        "floater" => (water_top, (water_top + 1).max(rock / 2)),
+        "heavy" => (water_top, rock.checked_sub(height + 2)?),
+        _ => (water_top, rock.checked_sub(height + 3)?),
+    };
+    (min <= max && max + height <= rock).then_some((min, max))
+}
+
+/// A grid of coloured characters with the water surface, rock line and sand
+/// already drawn.
+pub(crate) struct Tank {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+}
+
+impl Tank {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let mut tank = Self {
+            cols,
+            rows,
+            cells: vec![EMPTY; cols * rows],
+        };
+        for col in 0..cols {
+            tank.put(col, SURFACE_ROW, SURFACE_GLYPHS[col % 3], SURFACE_COLOR);
+            tank.put(col, rows.saturating_sub(2), '_', ROCK_COLOR);
+            tank.put(
+                col,
+                rows.saturating_sub(1),
+                SAND_GLYPHS[col % 7],
+                SAND_COLOR,
+            );
+        }
+        tank
+    }
+
+    fn put(&mut self, col: usize, row: usize, ch: char, color: Rgb) {
+        if col < self.cols && row < self.rows {
+            self.cells[row * self.cols + col] = Cell {
+                ch,
+                color: Some(color),
+            };
+        }
+    }
+
+    /// Draw sprite lines with their top-left corner at `col`, `row`, clipped
+    /// to the tank. Spaces are transparent.
+    pub fn draw(&mut self, lines: &[String], col: isize, row: isize, color: Rgb) {
+        for (dy, line) in lines.iter().enumerate() {
+            for (dx, ch) in line.chars().enumerate() {
+                let (x, y) = (col + dx as isize, row + dy as isize);
+                if x < 0 || y < 0 || x as usize >= self.cols || y as usize >= self.rows {
+                    continue;
+                }
+                match ch {
+                    ' ' => {}
+                    // Occlusion tile: hides what is behind without a glyph
+                    '!' => self.cells[y as usize * self.cols + x as usize] = EMPTY,
+                    _ => self.put(x as usize, y as usize, ch, color),
+                }
+            }
+        }
+    }
+
+    /// Rows of cells, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.cols.max(1))
+    }
+
+    fn render(&self, format: SnapshotFormat) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            let end = row.iter().rposition(|c| c.ch != ' ').map_or(0, |i| i + 1);
+            let mut current = None;
+            for cell in &row[..end] {
+                if format == SnapshotFormat::Ansi && cell.ch != ' ' && cell.color != current {
+                    if let Some((r, g, b)) = cell.color {
+                        out.push_str(&format!("\x1b[38;2;{};{};{}m", r, g, b));
+                    }
+                    current = cell.color;
+                }
+                out.push(cell.ch);
+            }
+            if current.is_some() {
+                out.push_str("\x1b[0m");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// A creature crossing the tank.
+pub(crate) struct Swimmer {
+    pub sprite: Sprite,
+    pub col: f64,
+    pub row: usize,
+    pub direction: isize,
+    /// Columns per second
+    pub speed: f64,
+    pub frame: usize,
+    frame_secs: f64,
+    frame_progress: f64,
+}
+
+impl Swimmer {
+    /// Speeds and frame durations per category, as in creature.js.
+    pub fn new(sprite: Sprite, cols: usize, row: usize, rng: &mut impl Rng) -> Self {
+        let direction = if rng.gen_bool(0.5) { 1 } else { -1 };
+        let (speed, frame_ms) = match sprite.category.as_str() {
+            "bottom" => (rng.gen_range(0.3..0.7), rng.gen_range(300..520)),
+            "floater" => (rng.gen_range(0.25..0.6), rng.gen_range(260..420)),
+            "heavy" => (rng.gen_range(0.2..0.5), rng.gen_range(360..580)),
+            _ => (rng.gen_range(0.5..1.3), rng.gen_range(180..300)),
+        };
+        let col = if direction == 1 {
+            -(sprite.width as f64)
+        } else {
+            cols as f64
+        };
+        Self {
+            frame: rng.gen_range(0..sprite.frames.len()),
+            sprite,
+            col,
+            row,
+            direction,
+            speed,
+            frame_secs: frame_ms as f64 / 1000.0,
+            frame_progress: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.col += self.speed * self.direction as f64 * dt;
+        self.frame_progress += dt;
+        if self.frame_progress >= self.frame_secs {
+            self.frame_progress = 0.0;
+            self.frame = (self.frame + 1) % self.sprite.frames.len();
+        }
+    }
+
+    pub fn offscreen(&self, cols: usize) -> bool {
+        let width = self.sprite.width as f64;
+        (self.direction == 1 && self.col > cols as f64 + 2.0)
+            || (self.direction == -1 && self.col < -(width + 2.0))
+    }
+
+    pub fn draw(&self, tank: &mut Tank) {
+        let lines = self.sprite.frame(self.frame, self.direction == -1);
+        let (col, row) = (self.col.floor() as isize, self.row as isize);
+        tank.draw(&lines, col, row, self.sprite.color);
+    }
+}
+
+/// A spot for `sprite` in its depth band that does not overlap the
+/// rectangles in `taken`, as (col, row).
+fn place(
+    taken: &mut Vec<(usize, usize, usize, usize)>,
+    sprite: &Sprite,
+    cols: usize,
+    rows: usize,
+    rng: &mut impl Rng,
+) -> Option<(usize, usize)> {
+    let (min_row, max_row) = row_range(&sprite.category, sprite.height, rows)?;
+    if sprite.width > cols {
+        return None;
+    }
+    for _ in 0..PLACE_ATTEMPTS {
+        let col = rng.gen_range(0..=cols - sprite.width);
+        let row = rng.gen_range(min_row..=max_row);
+        let overlaps = taken.iter().any(|&(c, r, w, h)| {
+            col < c + w && c < col + sprite.width && row < r + h && r < row + sprite.height
+        });
+        if !overlaps {
+            taken.push((col, row, sprite.width, sprite.height));
+            return Some((col, row));
+        }
+    }
+    None
+}
+
+/// Pick and place the visible owned creatures for a tank of `cols` x `rows`,
+/// as (sprite, col, row). The rarest is always shown; the rest are picked by
+/// display weight, never more of one kind than are owned.
+pub(crate) fn layout(
+    state: &GameState,
+    registry: &CreatureRegistry,
+    cols: usize,
+    rows: usize,
+    rng: &mut impl Rng,
+) -> Vec<(Sprite, usize, usize)> {
+    let mut owned = visible(state, registry);
+    let mut taken = Vec::new();
+    let mut placed = Vec::new();
+    if let Some(i) = rarest(&owned) {
+        if let Some((col, row)) = place(&mut taken, &owned[i].0, cols, rows, rng) {
+            owned[i].1 -= 1;
+            placed.push((owned[i].0.clone(), col, row));
+        }
+    }
+    for _ in 0..size_cap(cols, rows) * 2 {
+        if placed.len() >= size_cap(cols, rows) {
+            break;
+        }
+        let Some(pick) = pick_weighted(&owned, rng) else {
+            break;
+        };
+        if let Some((col, row)) = place(&mut taken, &owned[pick].0, cols, rows, rng) {
+            placed.push((owned[pick].0.clone(), col, row));
+        }
+        // Spent either way, so a sprite that does not fit is not retried forever
+        owned[pick].1 -= 1;
+    }
+    placed
+}
+
+/// Render the visible owned creatures into a tank of `cols` x `rows`.
+pub fn render(
+    state: &GameState,
+    registry: &CreatureRegistry,
+    cols: usize,
+    rows: usize,
+    format: SnapshotFormat,
+    rng: &mut impl Rng,
+) -> String {
+    let mut tank = Tank::new(cols, rows);
+    for (sprite, col, row) in layout(state, registry, cols, rows, rng) {
+        tank.draw(
+            &sprite.frame(0, false),
+            col as isize,
+            row as isize,
+            sprite.color,
+        );
+    }
+    tank.render(format)
+}
+
+/// The reef at the player's tank size.
+pub fn snapshot(state: &GameState, registry: &CreatureRegistry, format: SnapshotFormat) -> String {
+    let (_, cols, rows, _, _) = SIZE_PRESETS
+        .get(state.settings.size_index)
+        .unwrap_or(&SIZE_PRESETS[0]);
+    render(
+        state,
+        registry,
+        *cols as usize,
+        *rows as usize,
+        format,
+        &mut rand::thread_rng(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventCalendar;
+    use crate::test_util::owned;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    fn def(id: &str, category: &str, frame: &[&str]) -> CreatureDef {
+        let mut extra = serde_json::Map::new();
+        extra.insert("category".to_string(), category.into());
+        extra.insert("frames".to_string(), serde_json::json!([frame]));
+        extra.insert("naturalColor".to_string(), "#FF8000".into());
+        CreatureDef {
+            id: id.to_string(),
+            pool: "typing".to_string(),
+            rarity: "common".to_string(),
+            extra,
+        }
+    }
+
+    fn setup(counts: &[(&str, u32)]) -> (GameState, CreatureRegistry) {
+        let registry = CreatureRegistry {
+            builtin: vec![
+                def("fish", "swimmer", &["><>"]),
+                def("crab", "bottom", &["(\\/)", "!oo!"]),
+                def("eel", "swimmer", &["~~~~~~~~"]),
+            ],
+            packs: Vec::new(),
+            calendar: Arc::new(EventCalendar::default()),
+        };
+        let mut state = GameState::default();
+        for (id, count) in counts {
+            state
+                .collection
+                .insert(id.to_string(), owned(*count, "2025-01-01"));
+        }
+        (state, registry)
+    }
+
+    fn draw(state: &GameState, registry: &CreatureRegistry, format: SnapshotFormat) -> String {
+        render(
+            state,
+            registry,
+            40,
+            16,
+            format,
+            &mut SmallRng::seed_from_u64(7),
+        )
+    }
+
+    #[test]
+    fn plain_frame_has_surface_floor_and_creatures() {
+        let (mut state, registry) = setup(&[("fish", 2), ("crab", 1), ("eel", 1)]);
+        state.settings.hidden_creatures = vec!["eel".to_string()];
+        let out = draw(&state, &registry, SnapshotFormat::Plain);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 16);
+        assert!(lines[SURFACE_ROW].starts_with("~-=~-="));
+        assert_eq!(lines[14], "_".repeat(40));
+        assert_eq!(out.matches("><>").count(), 2);
+        // Bottom dwellers sit on the rock line
+        assert!(lines[12].contains("(\\/)"));
+        assert!(lines[13].contains("oo"));
+        assert!(!out.contains("~~~~~~~~"));
+        assert!(lines
+            .iter()
+            .all(|l| l.chars().count() <= 40 && !l.ends_with(' ')));
+    }
+
+    #[test]
+    fn zero_count_creatures_are_not_drawn() {
+        let (state, mut registry) = setup(&[("eel", 0), ("fish", 1)]);
+        // The rarest creature is always shown, unless none are left
+        registry.builtin[2].rarity = "legendary".to_string();
+        let out = draw(&state, &registry, SnapshotFormat::Plain);
+        assert!(!out.contains("~~~~~~~~"));
+        assert_eq!(out.matches("><>").count(), 1);
+    }
+
+    #[test]
+    fn sprites_pad_and_mirror_like_the_frontend() {
+        let sprite = Sprite::new(&def("crab", "bottom", &["(\\/)", "oo"])).unwrap();
+        assert_eq!((sprite.width, sprite.height), (4, 2));
+        assert_eq!(sprite.frame(0, false), ["(\\/)", "oo  "]);
+        assert_eq!(sprite.frame(0, true), ["(\\/)", "  oo"]);
+        let fish = Sprite::new(&def("fish", "swimmer", &["><(((o>"])).unwrap();
+        assert_eq!(fish.frame(1, true), ["<o)))><"]);
+    }
+
+    #[test]
+    fn weighted_pick_skips_spent_creatures() {
+        let eel = Sprite::new(&def("eel", "swimmer", &["~~~"])).unwrap();
+        let rarities = ["legendary", "legendary", "uncommon", "rare", "common"];
+        let mut owned: Vec<(Sprite, u32)> = rarities
+            .iter()
+            .map(|rarity| {
+                let mut sprite = eel.clone();
+                sprite.rarity = rarity.to_string();
+                (sprite, 1)
+            })
+            .collect();
+        owned[4].1 = 0;
+        // The highest roll; rounding runs it past every weight
+        let mut rng = rand::rngs::mock::StepRng::new(u64::MAX, 0);
+        let pick = pick_weighted(&owned, &mut rng).unwrap();
+        assert!(owned[pick].1 > 0);
+    }
+
+    #[test]
+    fn ansi_frame_colours_creatures() {
+        let (state, registry) = setup(&[("fish", 1)]);
+        let out = draw(&state, &registry, SnapshotFormat::Ansi);
+        assert!(out.contains("\x1b[38;2;255;128;0m><>"));
+        assert!(out.lines().all(|l| l.is_empty() || l.ends_with("\x1b[0m")));
+        assert_eq!(
+            draw(&state, &registry, SnapshotFormat::Plain),
+            draw(&state, &registry, SnapshotFormat::Plain)
+        );
+    }
+}
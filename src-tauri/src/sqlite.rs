@@ -0,0 +1,639 @@
+//! SQLite storage for reefs with long histories. Saves only touch the rows
+//! that changed since the last one, and every increase in a creature's
+//! count is appended to `journal`, so the file grows with activity rather
+//! than being rewritten whole.
+//!
+//! A profile switches to SQLite once (see `save::StorageKind`): its JSON save
+//! is imported and renamed to `save.reef.migrated`, and from then on the
+//! database is the live copy. Like JSON saves it is copied into `snapshots/`
+//! once a day, for recovery if the file is ever damaged.
+use crate::durable;
+use crate::save::{
+    HistoryEntry, JsonStorage, LoadNotice, SaveFile, Storage, MIGRATED_FILE, SAVE_FILE,
+};
+use crate::state::{GameState, OwnedCreature, PityCounters};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DB_FILE: &str = "save.db";
+const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS collection (
+    id TEXT PRIMARY KEY,
+    count INTEGER NOT NULL,
+    first_seen TEXT NOT NULL,
+    event TEXT,
+    orphaned INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS journal (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    at TEXT NOT NULL,
+    creature_id TEXT NOT NULL,
+    delta INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stats (
+    key TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+";
+
+pub fn exists(dir: &Path) -> bool {
+    dir.join(DB_FILE).exists()
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+/// Files SQLite keeps beside a database while writing to it
+const SIDECARS: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+/// Move a damaged database aside together with its sidecar files, so none
+/// of them is replayed into the replacement.
+fn quarantine_db(path: &Path, now: &str) -> Result<Vec<String>, String> {
+    let mut moved = vec![crate::save::quarantine(path, now)?.display().to_string()];
+    for suffix in SIDECARS {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        let sidecar = PathBuf::from(name);
+        if sidecar.exists() {
+            match crate::save::quarantine(&sidecar, now) {
+                Ok(target) => moved.push(target.display().to_string()),
+                Err(e) => eprintln!("Save: {}", e),
+            }
+        }
+    }
+    Ok(moved)
+}
+
+/// Create the database at `path` holding `save`, or empty.
+fn build(dir: &Path, path: &Path, save: Option<SaveFile>) -> Result<(), String> {
+    let staging = durable::tmp_path(path);
+    let _ = fs::remove_file(&staging);
+    {
+        let mut staged = SqliteStorage::open_at(dir, &staging)?;
+        if let Some(save) = save {
+            // v2 saves carry settings inline; keep them if nothing else has
+            if let Some(settings) = &save.display {
+                let path = dir.join(crate::settings::SETTINGS_FILE);
+                if crate::settings::read_settings_file(&path)?.is_none() {
+                    crate::settings::write_settings_file(&path, settings)?;
+                }
+            }
+            let created = save.meta.created.clone();
+            staged.write(&save.into_state(), false, Some(&created))?;
+        }
+    }
+    durable::replace(&staging, path)
+}
+
+/// Put a copy of the newest intact snapshot at `path`, else rebuild it from
+/// the migrated JSON save. Unreadable snapshots are quarantined. Returns the
+/// file recovered from, or `None` to start empty.
+fn restore(dir: &Path, path: &Path, now: &str, quarantined: &mut Vec<String>) -> Option<String> {
+    let staging = durable::tmp_path(path);
+    for snapshot in crate::save::snapshot_paths(dir, "db") {
+        let _ = fs::remove_file(&staging);
+        let intact = fs::copy(&snapshot, &staging).is_ok()
+            && SqliteStorage::open_at(dir, &staging).is_ok_and(|s| s.is_intact());
+        if intact && durable::replace(&staging, path).is_ok() {
+            return Some(snapshot.display().to_string());
+        }
+        eprintln!("Save: {} is unreadable", snapshot.display());
+        match crate::save::quarantine(&snapshot, now) {
+            Ok(moved) => quarantined.push(moved.display().to_string()),
+            Err(e) => eprintln!("Save: {}", e),
+        }
+    }
+    let _ = fs::remove_file(&staging);
+
+    let migrated = dir.join(MIGRATED_FILE);
+    let save = fs::read_to_string(&migrated)
+        .ok()
+        .and_then(|data| serde_json::from_str::<SaveFile>(&data).ok())?;
+    match build(dir, path, Some(save)) {
+        Ok(()) => Some(migrated.display().to_string()),
+        Err(e) => {
+            eprintln!("Save: failed to restore {}: {}", migrated.display(), e);
+            None
+        }
+    }
+}
+
+pub struct SqliteStorage {
+    dir: PathBuf,
+    conn: Connection,
+    /// Progress as of the last load or save, to find what changed
+    last: Option<GameState>,
+    /// Recovery notice from opening, handed out by the first `load`
+    notice: Option<LoadNotice>,
+}
+
+/// Each creature row by id, with whether it is quarantined.
+fn rows(state: &GameState) -> HashMap<&str, (&OwnedCreature, bool)> {
+    let known = state.collection.iter().map(|(id, c)| (id, (c, false)));
+    let orphaned = state
+        .orphaned
+        .collection
+        .iter()
+        .map(|(id, c)| (id, (c, true)));
+    known
+        .chain(orphaned)
+        .map(|(id, row)| (id.as_str(), row))
+        .collect()
+}
+
+fn stats(state: &GameState) -> Vec<(String, u32)> {
+    let PityCounters {
+        legendary,
+        epic,
+        rare,
+        uncommon,
+    } = state.pity;
+    let mut stats = vec![
+        ("total_discoveries".to_string(), state.total_discoveries),
+        ("pity:legendary".to_string(), legendary),
+        ("pity:epic".to_string(), epic),
+        ("pity:rare".to_string(), rare),
+        ("pity:uncommon".to_string(), uncommon),
+    ];
+    stats.extend(
+        state
+            .pool_energy
+            .iter()
+            .map(|(pool, energy)| (format!("pool:{}", pool), *energy)),
+    );
+    stats
+}
+
+impl SqliteStorage {
+    fn open_at(dir: &Path, path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(db_err)?;
+        conn.execute_batch("PRAGMA synchronous = FULL;")
+            .and_then(|()| conn.execute_batch(SCHEMA))
+            .map_err(db_err)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            conn,
+            last: None,
+            notice: None,
+        })
+    }
+
+    /// Whether SQLite's own consistency check passes.
+    fn is_intact(&self) -> bool {
+        self.conn
+            .query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))
+            .is_ok_and(|result| result == "ok")
+    }
+
+    /// Open the database in `dir`, creating it from the profile's JSON save
+    /// the first time. A damaged database is quarantined and rebuilt from
+    /// the newest intact snapshot, else the migrated JSON save.
+    pub fn open_or_migrate(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(DB_FILE);
+        if path.exists() {
+            match Self::open_at(dir, &path) {
+                Ok(storage) if storage.is_intact() => return Ok(storage),
+                Ok(storage) => drop(storage),
+                Err(e) => eprintln!("Save: {}", e),
+            }
+            let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+            let mut quarantined = quarantine_db(&path, &now)?;
+            let recovered_from = restore(dir, &path, &now, &mut quarantined);
+            let mut storage = Self::open_at(dir, &path)?;
+            storage.notice = Some(LoadNotice {
+                recovered_from,
+                quarantined,
+            });
+            return Ok(storage);
+        }
+
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create save dir: {}", e))?;
+        let (save, notice) = JsonStorage::new(dir).load();
+        build(dir, &path, save)?;
+        let json = dir.join(SAVE_FILE);
+        if json.exists() {
+            if let Err(e) = fs::rename(&json, dir.join(MIGRATED_FILE)) {
+                eprintln!("Save: failed to retire {}: {}", json.display(), e);
+            }
+        }
+        let mut storage = Self::open_at(dir, &path)?;
+        storage.notice = notice;
+        Ok(storage)
+    }
+
+    /// Copy the database into `snapshots/` once a day, keeping the newest few.
+    fn snapshot_daily(&self, today: &str) -> Result<(), String> {
+        let path = crate::save::snapshot_path(&self.dir, today, "db");
+        if path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create snapshot dir: {}", e))?;
+        }
+        let staging = durable::tmp_path(&path);
+        let _ = fs::remove_file(&staging);
+        self.conn
+            .execute("VACUUM INTO ?1", [staging.to_string_lossy()])
+            .map_err(db_err)?;
+        durable::replace(&staging, &path)?;
+        crate::save::prune_snapshots(&self.dir, "db");
+        Ok(())
+    }
+
+    fn read_state(&self) -> Result<GameState, String> {
+        let mut state = GameState::default();
+        let mut query = self
+            .conn
+            .prepare("SELECT id, count, first_seen, event, orphaned FROM collection")
+            .map_err(db_err)?;
+        let creatures = query
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    OwnedCreature {
+                        count: row.get(1)?,
+                        first_seen: row.get(2)?,
+                        event: row.get(3)?,
+                    },
+                    row.get::<_, bool>(4)?,
+                ))
+            })
+            .map_err(db_err)?;
+        for creature in creatures {
+            let (id, owned, orphaned) = creature.map_err(db_err)?;
+            if orphaned {
+                state.orphaned.collection.insert(id, owned);
+            } else {
+                state.collection.insert(id, owned);
+            }
+        }
+
+        let mut query = self
+            .conn
+            .prepare("SELECT key, value FROM stats")
+            .map_err(db_err)?;
+        let stats = query
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+            })
+            .map_err(db_err)?;
+        for stat in stats {
+            let (key, value) = stat.map_err(db_err)?;
+            match key.as_str() {
+                "total_discoveries" => state.total_discoveries = value,
+                "pity:legendary" => state.pity.legendary = value,
+                "pity:epic" => state.pity.epic = value,
+                "pity:rare" => state.pity.rare = value,
+                "pity:uncommon" => state.pity.uncommon = value,
+                _ => {
+                    if let Some(pool) = key.strip_prefix("pool:") {
+                        state.pool_energy.insert(pool.to_string(), value);
+                    }
+                }
+            }
+        }
+
+        if let Some(hidden) = self.meta("hidden_orphans")? {
+            state.orphaned.hidden_creatures = serde_json::from_str(&hidden)
+                .map_err(|e| format!("Failed to parse hidden creatures: {}", e))?;
+        }
+        Ok(state)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(db_err)
+    }
+
+    /// Write the rows that differ from the last save in one transaction,
+    /// journaling count increases if `journal` is set.
+    fn write(
+        &mut self,
+        state: &GameState,
+        journal: bool,
+        created: Option<&str>,
+    ) -> Result<(), String> {
+        let last = match self.last.take() {
+            Some(last) => last,
+            None => self.read_state()?,
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        let hidden = serde_json::to_string(&state.orphaned.hidden_creatures)
+            .map_err(|e| format!("Failed to serialize hidden creatures: {}", e))?;
+
+        let tx = self.conn.transaction().map_err(db_err)?;
+        {
+            let (before, after) = (rows(&last), rows(state));
+            let mut upsert = tx
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO collection (id, count, first_seen, event, orphaned)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(db_err)?;
+            let mut log = tx
+                .prepare_cached("INSERT INTO journal (at, creature_id, delta) VALUES (?1, ?2, ?3)")
+                .map_err(db_err)?;
+            for (id, &(owned, orphaned)) in &after {
+                let prev = before.get(id);
+                if prev.is_some_and(|&(prev, was_orphaned)| {
+                    prev.count == owned.count
+                        && prev.first_seen == owned.first_seen
+                        && prev.event == owned.event
+                        && was_orphaned == orphaned
+                }) {
+                    continue;
+                }
+                upsert
+                    .execute(params![
+                        id,
+                        owned.count,
+                        owned.first_seen,
+                        owned.event,
+                        orphaned
+                    ])
+                    .map_err(db_err)?;
+                let was = prev.map_or(0, |(prev, _)| prev.count);
+                if journal && owned.count > was {
+                    log.execute(params![now, id, owned.count - was])
+                        .map_err(db_err)?;
+                }
+            }
+
+            let mut delete = tx
+                .prepare_cached("DELETE FROM collection WHERE id = ?1")
+                .map_err(db_err)?;
+            for id in before.keys().filter(|id| !after.contains_key(*id)) {
+                delete.execute([id]).map_err(db_err)?;
+            }
+
+            let mut stat = tx
+                .prepare_cached("INSERT OR REPLACE INTO stats (key, value) VALUES (?1, ?2)")
+                .map_err(db_err)?;
+            for (key, value) in stats(state) {
+                stat.execute(params![key, value]).map_err(db_err)?;
+            }
+
+            let mut meta = tx
+                .prepare_cached("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)")
+                .map_err(db_err)?;
+            meta.execute(params!["version", SCHEMA_VERSION.to_string()])
+                .map_err(db_err)?;
+            meta.execute(params!["last_saved", now]).map_err(db_err)?;
+            meta.execute(params!["app_version", env!("CARGO_PKG_VERSION")])
+                .map_err(db_err)?;
+            meta.execute(params!["hidden_orphans", hidden])
+                .map_err(db_err)?;
+            tx.execute(
+                "INSERT OR IGNORE INTO meta (key, value) VALUES ('created', ?1)",
+                [created.unwrap_or(&now)],
+            )
+            .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)?;
+
+        self.last = Some(state.clone());
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn load(&mut self) -> (Option<SaveFile>, Option<LoadNotice>) {
+        let notice = self.notice.take();
+        match self.read_state() {
+            Ok(state) => {
+                let save = self
+                    .created()
+                    .map(|created| SaveFile::from_state(&state, created, false));
+                self.last = Some(state);
+                (save, notice)
+            }
+            Err(e) => {
+                eprintln!("Save: failed to read {}: {}", DB_FILE, e);
+                (None, notice)
+            }
+        }
+    }
+
+    fn save(&mut self, state: &GameState) -> Result<(), String> {
+        self.write(state, true, None)?;
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        if let Err(e) = self.snapshot_daily(&today) {
+            eprintln!("Save: {}", e);
+        }
+        Ok(())
+    }
+
+    fn created(&self) -> Option<String> {
+        self.meta("created").ok().flatten()
+    }
+
+    fn history(&mut self, limit: usize) -> Result<Option<Vec<HistoryEntry>>, String> {
+        let mut query = self
+            .conn
+            .prepare_cached("SELECT at, creature_id, delta FROM journal ORDER BY seq DESC LIMIT ?1")
+            .map_err(db_err)?;
+        let rows = query
+            .query_map([limit as i64], |row| {
+                Ok(HistoryEntry {
+                    at: row.get(0)?,
+                    creature_id: row.get(1)?,
+                    delta: row.get(2)?,
+                })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map(Some)
+            .map_err(db_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{owned, TempDir};
+
+    fn journal(storage: &SqliteStorage) -> Vec<(String, u32)> {
+        let mut query = storage
+            .conn
+            .prepare("SELECT creature_id, delta FROM journal ORDER BY seq")
+            .unwrap();
+        query
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn sorted(mut rows: Vec<(String, u32)>) -> Vec<(String, u32)> {
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn saves_incrementally_and_round_trips() {
+        let dir = TempDir::new("sqlite-roundtrip");
+        let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+        assert!(storage.load().0.is_none());
+
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("t_common_01".to_string(), owned(2, "2025-01-01"));
+        state
+            .orphaned
+            .collection
+            .insert("gone_fish".to_string(), owned(1, "2025-01-01"));
+        state.orphaned.hidden_creatures = vec!["gone_fish".to_string()];
+        state.total_discoveries = 3;
+        state.pity.rare = 4;
+        state.pool_energy.insert("click".to_string(), 9);
+        storage.save(&state).unwrap();
+
+        state.collection.get_mut("t_common_01").unwrap().count = 5;
+        state
+            .collection
+            .insert("t_rare_01".to_string(), owned(1, "2025-01-01"));
+        storage.save(&state).unwrap();
+        storage.save(&state).unwrap();
+        assert_eq!(
+            sorted(journal(&storage)),
+            vec![
+                ("gone_fish".to_string(), 1),
+                ("t_common_01".to_string(), 2),
+                ("t_common_01".to_string(), 3),
+                ("t_rare_01".to_string(), 1),
+            ]
+        );
+
+        // A reset removes the rows but keeps the history
+        state.collection.clear();
+        storage.save(&state).unwrap();
+        drop(storage);
+        let mut reopened = SqliteStorage::open_or_migrate(&dir).unwrap();
+        let loaded = reopened.load().0.unwrap().into_state();
+        assert!(loaded.collection.is_empty());
+        assert_eq!(loaded.orphaned.collection["gone_fish"].count, 1);
+        assert_eq!(loaded.orphaned.hidden_creatures, vec!["gone_fish"]);
+        assert_eq!(loaded.total_discoveries, 3);
+        assert_eq!(loaded.pity.rare, 4);
+        assert_eq!(loaded.pool_energy["click"], 9);
+        assert_eq!(journal(&reopened).len(), 4);
+        let history = reopened.history(2).unwrap().unwrap();
+        let newest = history
+            .into_iter()
+            .map(|e| (e.creature_id, e.delta))
+            .collect();
+        assert_eq!(
+            sorted(newest),
+            vec![("t_common_01".to_string(), 3), ("t_rare_01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn migrates_json_save_once() {
+        let dir = TempDir::new("sqlite-migrate");
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("t_common_01".to_string(), owned(7, "2025-01-01"));
+        state.total_discoveries = 7;
+        JsonStorage::new(&dir).save(&state).unwrap();
+        let created = JsonStorage::new(&dir).created().unwrap();
+
+        let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+        let save = storage.load().0.unwrap();
+        assert_eq!(save.meta.created, created);
+        assert_eq!(save.into_state().collection["t_common_01"].count, 7);
+        // Imported progress is not new activity
+        assert!(journal(&storage).is_empty());
+        assert!(!dir.join(SAVE_FILE).exists());
+        assert!(dir.join("save.reef.migrated").exists());
+        assert!(!durable::tmp_path(&dir.join(DB_FILE)).exists());
+    }
+
+    #[test]
+    fn damaged_database_is_quarantined() {
+        let dir = TempDir::new("sqlite-corrupt");
+        fs::write(dir.join(DB_FILE), "not a database, just some text").unwrap();
+        let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+        let (save, notice) = storage.load();
+        assert!(save.is_none());
+        let notice = notice.unwrap();
+        assert_eq!(notice.recovered_from, None);
+        assert_eq!(notice.quarantined.len(), 1);
+        assert!(Path::new(&notice.quarantined[0]).exists());
+        storage.save(&GameState::default()).unwrap();
+    }
+
+    #[test]
+    fn damaged_database_is_restored_from_a_snapshot() {
+        let dir = TempDir::new("sqlite-restore");
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("crab".into(), owned(3, "2025-01-01"));
+        {
+            let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+            storage.load();
+            storage.save(&state).unwrap();
+        }
+        assert_eq!(crate::save::snapshot_paths(&dir, "db").len(), 1);
+        fs::write(dir.join(DB_FILE), "not a database, just some text").unwrap();
+
+        let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+        let (save, notice) = storage.load();
+        assert_eq!(save.unwrap().into_state().collection["crab"].count, 3);
+        let notice = notice.unwrap();
+        assert!(notice.recovered_from.unwrap().ends_with(".db"));
+        assert_eq!(notice.quarantined.len(), 1);
+    }
+
+    #[test]
+    fn quarantine_takes_sidecar_files_along() {
+        let dir = TempDir::new("sqlite-sidecars");
+        let path = dir.join(DB_FILE);
+        for name in [DB_FILE, "save.db-journal", "save.db-wal"] {
+            fs::write(dir.join(name), "x").unwrap();
+        }
+        let moved = quarantine_db(&path, "now").unwrap();
+        assert_eq!(moved.len(), 3);
+        assert!(moved.iter().all(|m| m.ends_with(".corrupt-now")));
+        assert!(!path.exists() && !dir.join("save.db-journal").exists());
+    }
+
+    #[test]
+    fn damaged_database_falls_back_to_the_migrated_save() {
+        let dir = TempDir::new("sqlite-fallback");
+        let mut state = GameState::default();
+        state
+            .collection
+            .insert("crab".into(), owned(2, "2025-01-01"));
+        JsonStorage::new(&dir).save(&state).unwrap();
+        drop(SqliteStorage::open_or_migrate(&dir).unwrap());
+        fs::write(dir.join(DB_FILE), "not a database, just some text").unwrap();
+
+        let mut storage = SqliteStorage::open_or_migrate(&dir).unwrap();
+        let (save, notice) = storage.load();
+        assert_eq!(save.unwrap().into_state().collection["crab"].count, 2);
+        let recovered = notice.unwrap().recovered_from.unwrap();
+        assert!(recovered.ends_with(MIGRATED_FILE));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::settings::Settings;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -7,6 +8,53 @@ pub struct OwnedCreature {
     pub count: u32,
     #[serde(rename = "firstSeen")]
     pub first_seen: String,
+    /// Id of the limited-time event this creature was discovered during,
+    /// set only for event-only creatures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+}
+
+impl OwnedCreature {
+    /// Fold another record of the same creature into this one: counts add up
+    /// and the earliest sighting wins.
+    pub fn absorb(&mut self, other: OwnedCreature) {
+        self.count = self.count.saturating_add(other.count);
+        if other.first_seen < self.first_seen {
+            self.first_seen = other.first_seen;
+        }
+        if self.event.is_none() {
+            self.event = other.event;
+        }
+    }
+
+    /// Combine two copies of the same history (one save on two machines):
+    /// the higher count and the earliest sighting win.
+    pub fn merge(&mut self, other: OwnedCreature) {
+        self.count = self.count.max(other.count);
+        if other.first_seen < self.first_seen {
+            self.first_seen = other.first_seen;
+        }
+        if self.event.is_none() {
+            self.event = other.event;
+        }
+    }
+}
+
+/// Save data referring to creatures with no known definition (removed pack,
+/// renamed id without a mapping). Kept out of `collection`, and so away from
+/// the frontend, until the definition comes back.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrphanedCreatures {
+    #[serde(default)]
+    pub collection: HashMap<String, OwnedCreature>,
+    #[serde(default)]
+    pub hidden_creatures: Vec<String>,
+}
+
+impl OrphanedCreatures {
+    pub fn is_empty(&self) -> bool {
+        self.collection.is_empty() && self.hidden_creatures.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -23,66 +71,12 @@ pub struct GameState {
     pub pool_energy: HashMap<String, u32>,
     pub total_discoveries: u32,
     pub pity: PityCounters,
-    pub position: (f64, f64),
-    /// Selected size preset index
-    #[serde(default = "default_size_index")]
-    pub size_index: usize,
-    /// Whether leaderboard score submission is enabled
-    #[serde(default = "default_send_scores")]
-    pub send_scores: bool,
-    /// Whether ambient music is enabled
-    #[serde(default = "default_sound_enabled")]
-    pub sound_enabled: bool,
-    /// Music volume (0.0 - 1.0)
-    #[serde(default = "default_music_volume")]
-    pub music_volume: f32,
-    /// Day/night cycle mode: "computer", "5min", "10min", "60min", "3hours"
-    #[serde(default = "default_day_night_cycle")]
-    pub day_night_cycle: String,
-    /// Whether message-in-a-bottle interactions are enabled
-    #[serde(default = "default_message_bottles_enabled")]
-    pub message_bottles_enabled: bool,
-    /// Whether user has already seen the first-time opt-in prompt
-    #[serde(default = "default_message_bottles_prompted")]
-    pub message_bottles_prompted: bool,
-    /// Behavior when clicking the window X button: "ask", "hide", or "close"
-    #[serde(default = "default_close_behavior")]
-    pub close_behavior: String,
-    /// IDs of creatures hidden from the aquarium display
+    /// User preferences, stored as the save file's `display` section
     #[serde(default)]
-    pub hidden_creatures: Vec<String>,
-}
-
-fn default_size_index() -> usize {
-    1 // "Medium" (60x16) = default
-}
-
-fn default_send_scores() -> bool {
-    true
-}
-
-fn default_sound_enabled() -> bool {
-    false
-}
-
-fn default_music_volume() -> f32 {
-    0.08
-}
-
-fn default_day_night_cycle() -> String {
-    "computer".to_string()
-}
-
-fn default_message_bottles_enabled() -> bool {
-    false
-}
-
-fn default_message_bottles_prompted() -> bool {
-    false
-}
-
-fn default_close_behavior() -> String {
-    "ask".to_string()
+    pub settings: Settings,
+    /// Quarantined entries for creatures that no longer have a definition
+    #[serde(default)]
+    pub orphaned: OrphanedCreatures,
 }
 
 fn default_pool_energy() -> HashMap<String, u32> {
@@ -100,16 +94,8 @@ impl Default for GameState {
             pool_energy: default_pool_energy(),
             total_discoveries: 0,
             pity: PityCounters::default(),
-            position: (0.0, 0.0),
-            size_index: default_size_index(),
-            send_scores: default_send_scores(),
-            sound_enabled: default_sound_enabled(),
-            music_volume: default_music_volume(),
-            day_night_cycle: default_day_night_cycle(),
-            message_bottles_enabled: default_message_bottles_enabled(),
-            message_bottles_prompted: default_message_bottles_prompted(),
-            close_behavior: default_close_behavior(),
-            hidden_creatures: Vec::new(),
+            settings: Settings::default(),
+            orphaned: OrphanedCreatures::default(),
         }
     }
 }
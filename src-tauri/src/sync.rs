@@ -0,0 +1,503 @@
+//! Syncing a reef between devices through a shared folder (Syncthing, a
+//! network drive, ...). Each device writes only its own journal,
+//! `<sync dir>/<profile>/<device id>.json`, recording how many of each
+//! creature it has discovered. Those counts only ever grow, so journals
+//! merge without conflicts: copies of the same device's journal take the
+//! higher count, and different devices' counts add up.
+//!
+//! Local discoveries reach the journal as the growth of the collection since
+//! the last sync (the baseline, kept in `sync.json` next to the profile's
+//! save). On a device's first sync there is no baseline yet, so whatever the
+//! other devices' journals already hold is taken as the starting point: a
+//! copied save is not counted twice. Nothing is lost while the folder is
+//! unreachable; the journal is published again on the next sync. Resetting the aquarium is not synced:
+//! with sync on, the next sync brings every device's creatures back.
+use crate::notify::Notify;
+use crate::persist::Dirty;
+use crate::registry::SharedRegistry;
+use crate::state::{GameState, OwnedCreature, SharedState};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const SYNC_INTERVAL_SECS: u64 = 30;
+
+static DEVICE_ID: Lazy<String> = Lazy::new(|| device_id_in(&crate::save::save_dir()));
+
+/// One device's discoveries for one profile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Journal {
+    pub device: String,
+    #[serde(default)]
+    pub updated: String,
+    #[serde(default)]
+    pub creatures: HashMap<String, OwnedCreature>,
+    #[serde(default)]
+    pub total_discoveries: u32,
+}
+
+impl Journal {
+    /// Fold in another copy of the same device's journal (e.g. a sync tool's
+    /// conflict copy): the higher counts win.
+    fn merge(&mut self, other: Journal) {
+        for (id, theirs) in other.creatures {
+            match self.creatures.get_mut(&id) {
+                Some(ours) => ours.merge(theirs),
+                None => {
+                    self.creatures.insert(id, theirs);
+                }
+            }
+        }
+        self.total_discoveries = self.total_discoveries.max(other.total_discoveries);
+        self.updated = self.updated.clone().max(other.updated);
+    }
+}
+
+/// Per-profile sync bookkeeping, stored locally.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    /// This device's journal; the copy in the sync folder is only published
+    #[serde(default)]
+    pub journal: Journal,
+    /// Creature counts right after the last sync
+    #[serde(default)]
+    pub baseline: HashMap<String, u32>,
+    #[serde(default)]
+    pub baseline_total: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Devices with a journal for this profile, including this one
+    pub devices: usize,
+    /// Whether other devices' discoveries changed the local collection
+    pub changed: bool,
+}
+
+/// Random id for this machine, created on first use.
+fn device_id_in(root: &Path) -> String {
+    let path = root.join("device-id");
+    if let Some(id) = fs::read_to_string(&path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| crate::profiles::valid_name(s))
+    {
+        return id;
+    }
+    let id = format!("{:016x}", rand::random::<u64>());
+    let _ = fs::create_dir_all(root);
+    if let Err(e) = fs::write(&path, &id) {
+        eprintln!("Sync: failed to store device id: {}", e);
+    }
+    id
+}
+
+pub fn device_id() -> &'static str {
+    &DEVICE_ID
+}
+
+fn local_state_path() -> PathBuf {
+    crate::profiles::active_dir().join("sync.json")
+}
+
+fn read_local(path: &Path) -> SyncState {
+    let Ok(json) = fs::read_to_string(path) else {
+        return SyncState::default();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        eprintln!("Sync: ignoring unreadable {}: {}", path.display(), e);
+        SyncState::default()
+    })
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    crate::durable::write_atomic(path, json.as_bytes())
+}
+
+/// Every journal in `dir` except `device`'s own, with copies of the same
+/// device already merged.
+pub fn read_journals(dir: &Path, device: &str) -> Vec<Journal> {
+    let mut journals: HashMap<String, Journal> = HashMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let journal = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Journal>(&json).map_err(|e| e.to_string()))
+        {
+            Ok(journal) => journal,
+            Err(e) => {
+                eprintln!("Sync: skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if journal.device.is_empty() || journal.device == device {
+            continue;
+        }
+        match journals.get_mut(&journal.device) {
+            Some(ours) => ours.merge(journal),
+            None => {
+                journals.insert(journal.device.clone(), journal);
+            }
+        }
+    }
+    journals.into_values().collect()
+}
+
+/// Creature counts across the collection and quarantine.
+fn counts(state: &GameState) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for (id, owned) in state
+        .collection
+        .iter()
+        .chain(state.orphaned.collection.iter())
+    {
+        *counts.entry(id.clone()).or_insert(0) += owned.count;
+    }
+    counts
+}
+
+/// Record local discoveries since the last sync in this device's journal,
+/// then set the collection to the sum of every device's journal. Creatures
+/// `is_known` rejects go to the quarantine. Returns whether the collection
+/// changed.
+pub fn reconcile(
+    state: &mut GameState,
+    local: &mut SyncState,
+    others: &[Journal],
+    device: &str,
+    now: &str,
+    is_known: impl Fn(&str) -> bool,
+) -> bool {
+    if local.journal.device.is_empty() {
+        // First sync from this device: what the others already published is
+        // assumed to be in this collection too (e.g. a copied save)
+        local.baseline.clear();
+        local.baseline_total = 0;
+        for journal in others {
+            for (id, theirs) in &journal.creatures {
+                let count = local.baseline.entry(id.clone()).or_insert(0);
+                *count = count.saturating_add(theirs.count);
+            }
+            local.baseline_total = local
+                .baseline_total
+                .saturating_add(journal.total_discoveries);
+        }
+    }
+    let journal = &mut local.journal;
+    journal.device = device.to_string();
+    journal.updated = now.to_string();
+    for (id, count) in counts(state) {
+        let delta = count.saturating_sub(local.baseline.get(&id).copied().unwrap_or(0));
+        if delta == 0 {
+            continue;
+        }
+        let owned = state
+            .collection
+            .get(&id)
+            .or_else(|| state.orphaned.collection.get(&id))
+            .cloned();
+        let Some(owned) = owned else { continue };
+        let entry = journal.creatures.entry(id).or_insert(OwnedCreature {
+            count: 0,
+            first_seen: owned.first_seen.clone(),
+            event: owned.event.clone(),
+        });
+        entry.absorb(OwnedCreature {
+            count: delta,
+            ..owned
+        });
+    }
+    journal.total_discoveries = journal
+        .total_discoveries
+        .saturating_add(state.total_discoveries.saturating_sub(local.baseline_total));
+
+    let mut merged: HashMap<String, OwnedCreature> = HashMap::new();
+    let mut total = 0u32;
+    for journal in std::iter::once(&*journal).chain(others) {
+        for (id, theirs) in &journal.creatures {
+            match merged.get_mut(id) {
+                Some(ours) => ours.absorb(theirs.clone()),
+                None => {
+                    merged.insert(id.clone(), theirs.clone());
+                }
+            }
+        }
+        total = total.saturating_add(journal.total_discoveries);
+    }
+
+    let mut changed = false;
+    for (id, theirs) in merged {
+        let target = if is_known(&id) {
+            &mut state.collection
+        } else {
+            &mut state.orphaned.collection
+        };
+        let ours = target.entry(id).or_insert_with(|| OwnedCreature {
+            count: 0,
+            first_seen: theirs.first_seen.clone(),
+            event: theirs.event.clone(),
+        });
+        if ours.count != theirs.count || ours.first_seen != theirs.first_seen {
+            ours.count = theirs.count;
+            ours.first_seen = theirs.first_seen;
+            changed = true;
+        }
+    }
+    if state.total_discoveries != total {
+        state.total_discoveries = total;
+        changed = true;
+    }
+
+    local.baseline = counts(state);
+    local.baseline_total = state.total_discoveries;
+    changed
+}
+
+/// Sync the active profile with the folder in its settings.
+pub fn sync_now(state: &SharedState, registry: &SharedRegistry) -> Result<SyncReport, String> {
+    let (sync_dir, profile) = {
+        let guard = state.lock().unwrap_or_else(|p| p.into_inner());
+        (guard.settings.sync_dir.clone(), crate::profiles::active())
+    };
+    if sync_dir.is_empty() {
+        return Err("Sync is off: choose a sync folder first".to_string());
+    }
+    let folder = Path::new(&sync_dir).join(&profile);
+    fs::create_dir_all(&folder)
+        .map_err(|e| format!("Failed to open sync folder {}: {}", folder.display(), e))?;
+    let device = device_id();
+    // Read other devices' journals before taking the lock: the folder may be slow
+    let others = read_journals(&folder, device);
+
+    let (journal, changed) = {
+        let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
+        if crate::profiles::active() != profile || guard.settings.sync_dir != sync_dir {
+            // Switched profile or folder meanwhile; the next sync picks it up
+            return Ok(SyncReport::default());
+        }
+        let reg = registry.read().unwrap_or_else(|p| p.into_inner());
+        let path = local_state_path();
+        let mut local = read_local(&path);
+        let now = chrono::Utc::now().to_rfc3339();
+        let changed = reconcile(&mut guard, &mut local, &others, device, &now, |id| {
+            reg.is_known(id)
+        });
+        // The baseline must be on disk before the save it describes, or a
+        // failed write would count other devices' creatures as ours next time
+        write_json(&path, &local)?;
+        if changed {
+            crate::persist::mark(Dirty::Progress);
+        }
+        (local.journal, changed)
+    };
+    write_json(&folder.join(format!("{}.json", device)), &journal)?;
+
+    Ok(SyncReport {
+        devices: others.len() + 1,
+        changed,
+    })
+}
+
+/// Sync every `SYNC_INTERVAL_SECS` while a sync folder is set.
+pub fn start_sync_loop(app: impl Notify, state: Arc<SharedState>, registry: Arc<SharedRegistry>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(SYNC_INTERVAL_SECS));
+        let enabled = !state
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .settings
+            .sync_dir
+            .is_empty();
+        if !enabled {
+            continue;
+        }
+        match sync_now(&state, &registry) {
+            Ok(report) if report.changed => {
+                app.notify("sync-merged", &report);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Sync: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{owned, TempDir};
+
+    /// A device's reef plus its sync bookkeeping.
+    struct Device {
+        id: &'static str,
+        state: GameState,
+        local: SyncState,
+        /// Whether this device has the pack defining `pack_*` creatures
+        has_pack: bool,
+    }
+
+    impl Device {
+        fn new(id: &'static str) -> Self {
+            Self {
+                id,
+                state: GameState::default(),
+                local: SyncState::default(),
+                has_pack: false,
+            }
+        }
+
+        fn discover(&mut self, id: &str, first_seen: &str) {
+            self.state
+                .collection
+                .entry(id.to_string())
+                .or_insert(owned(0, first_seen))
+                .count += 1;
+            self.state.total_discoveries += 1;
+        }
+
+        fn sync(&mut self, folder: &Path) -> bool {
+            let others = read_journals(folder, self.id);
+            let has_pack = self.has_pack;
+            let changed = reconcile(
+                &mut self.state,
+                &mut self.local,
+                &others,
+                self.id,
+                "now",
+                |id| has_pack || id.starts_with("t_"),
+            );
+            write_json(
+                &folder.join(format!("{}.json", self.id)),
+                &self.local.journal,
+            )
+            .unwrap();
+            changed
+        }
+
+        fn count(&self, id: &str) -> u32 {
+            self.state.collection.get(id).map_or(0, |c| c.count)
+        }
+    }
+
+    #[test]
+    fn two_devices_converge() {
+        let folder = TempDir::new("sync-converge");
+        let (mut a, mut b) = (Device::new("laptop"), Device::new("desktop"));
+        a.sync(&folder);
+        b.sync(&folder);
+        a.discover("t_common_01", "2025-02-01");
+        a.discover("t_common_01", "2025-02-01");
+        b.discover("t_common_01", "2025-01-01");
+        b.discover("t_rare_01", "2025-01-05");
+
+        assert!(!a.sync(&folder));
+        assert!(b.sync(&folder));
+        assert!(a.sync(&folder));
+        for device in [&a, &b] {
+            assert_eq!(device.count("t_common_01"), 3);
+            assert_eq!(device.count("t_rare_01"), 1);
+            assert_eq!(device.state.total_discoveries, 4);
+            assert_eq!(
+                device.state.collection["t_common_01"].first_seen,
+                "2025-01-01"
+            );
+        }
+
+        // Syncing again without new discoveries counts nothing twice
+        assert!(!a.sync(&folder));
+        assert!(!b.sync(&folder));
+        a.discover("t_rare_01", "2025-03-01");
+        a.sync(&folder);
+        b.sync(&folder);
+        assert_eq!(b.count("t_rare_01"), 2);
+        assert_eq!(a.count("t_common_01"), 3);
+    }
+
+    #[test]
+    fn first_sync_of_a_copied_save_adds_nothing() {
+        let folder = TempDir::new("sync-copied");
+        let mut a = Device::new("laptop");
+        a.discover("t_common_01", "2025-01-01");
+        a.discover("t_common_01", "2025-01-01");
+        a.discover("t_rare_01", "2025-01-05");
+        let mut b = Device::new("desktop");
+        b.state = a.state.clone();
+        b.discover("t_rare_01", "2025-01-05");
+
+        a.sync(&folder);
+        b.sync(&folder);
+        a.sync(&folder);
+        for device in [&a, &b] {
+            assert_eq!(device.count("t_common_01"), 2);
+            assert_eq!(device.count("t_rare_01"), 2);
+            assert_eq!(device.state.total_discoveries, 4);
+        }
+    }
+
+    #[test]
+    fn offline_discoveries_are_published_later() {
+        let (folder, offline) = (TempDir::new("sync-online"), TempDir::new("sync-offline"));
+        let (mut a, mut b) = (Device::new("laptop"), Device::new("desktop"));
+        a.discover("t_common_01", "2025-01-01");
+        // The first sync only reaches a folder the other device never sees
+        a.sync(&offline);
+        a.discover("t_common_01", "2025-01-01");
+        a.sync(&folder);
+        b.sync(&folder);
+        assert_eq!(b.count("t_common_01"), 2);
+    }
+
+    #[test]
+    fn conflict_copies_of_a_journal_are_not_double_counted() {
+        let folder = TempDir::new("sync-conflict");
+        let mut a = Device::new("laptop");
+        a.discover("t_common_01", "2025-01-01");
+        a.sync(&folder);
+        let stale = a.local.journal.clone();
+        a.discover("t_common_01", "2025-01-01");
+        a.sync(&folder);
+        write_json(&folder.join("laptop.sync-conflict-1.json"), &stale).unwrap();
+
+        let mut b = Device::new("desktop");
+        b.sync(&folder);
+        assert_eq!(b.count("t_common_01"), 2);
+        assert_eq!(b.state.total_discoveries, 2);
+    }
+
+    #[test]
+    fn unknown_creatures_are_quarantined() {
+        let folder = TempDir::new("sync-unknown");
+        let mut a = Device::new("laptop");
+        a.has_pack = true;
+        a.discover("pack_fish", "2025-01-01");
+        a.sync(&folder);
+        let mut b = Device::new("desktop");
+        b.sync(&folder);
+        assert_eq!(b.count("pack_fish"), 0);
+        assert_eq!(b.state.orphaned.collection["pack_fish"].count, 1);
+        // Staying quarantined is not a change and is not re-counted
+        assert!(!b.sync(&folder));
+        assert_eq!(b.state.orphaned.collection["pack_fish"].count, 1);
+    }
+
+    #[test]
+    fn device_id_is_stable() {
+        let root = TempDir::new("sync-device");
+        let id = device_id_in(&root);
+        assert_eq!(id.len(), 16);
+        assert_eq!(device_id_in(&root), id);
+    }
+}
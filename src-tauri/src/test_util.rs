@@ -0,0 +1,47 @@
+//! Fixtures shared by the unit tests.
+use crate::state::OwnedCreature;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// An empty `ascii-reef-<name>-<pid>` directory in the system temp dir,
+/// removed again when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("ascii-reef-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// `count` of a creature first seen on `first_seen`.
+pub fn owned(count: u32, first_seen: &str) -> OwnedCreature {
+    OwnedCreature {
+        count,
+        first_seen: first_seen.to_string(),
+        event: None,
+    }
+}
@@ -1,7 +1,11 @@
-//! System tray setup, size/day-night submenus, window visibility toggle,
-//! and helpers to open the collection and settings windows.
+//! System tray setup, size/day-night/profile submenus, window visibility
+//! toggle, and helpers to open the collection and settings windows.
+use crate::persist::Dirty;
+use crate::registry::SharedRegistry;
+use crate::settings::{DayNightCycle, Settings};
 use crate::state::SharedState;
 use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
 use std::sync::Arc;
 use tauri::{
     menu::{CheckMenuItem, Menu, MenuItem, Submenu},
@@ -9,9 +13,13 @@ use tauri::{
     AppHandle, Emitter, Manager,
 };
 use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
 static WINDOW_TOGGLE_ITEM: OnceCell<MenuItem<tauri::Wry>> = OnceCell::new();
-static DAY_NIGHT_CYCLE_ITEMS: OnceCell<Vec<CheckMenuItem<tauri::Wry>>> = OnceCell::new();
+static PROFILE_MENU: OnceCell<Submenu<tauri::Wry>> = OnceCell::new();
+/// Check items bound to a setting: each is checked while the setting equals its value
+static SETTING_ITEMS: OnceCell<Vec<(&'static str, Value, CheckMenuItem<tauri::Wry>)>> =
+    OnceCell::new();
 
 // Size presets: (label, cols, rows, pixel_width, pixel_height)
 // charWidth ≈ 9, charHeight = 16
@@ -26,14 +34,6 @@ pub const SIZE_PRESETS: &[(&str, u32, u32, f64, f64)] = &[
     ("Extra Wide", 120, 24, 1080.0, 384.0),
 ];
 
-pub const DAY_NIGHT_CYCLES: &[(&str, &str)] = &[
-    ("computer", "Computer Time (Default)"),
-    ("5min", "5 min day / 5 min night"),
-    ("10min", "10 min day / 10 min night"),
-    ("60min", "60 min day / 60 min night"),
-    ("3hours", "3 hours day / 3 hours night"),
-];
-
 pub fn setup_tray(
     app: &AppHandle,
     state: Arc<SharedState>,
@@ -54,26 +54,28 @@ pub fn setup_tray(
     let _ = WINDOW_TOGGLE_ITEM.set(window_toggle_item.clone());
     let collection_item = MenuItem::with_id(app, "collection", "Collection", true, None::<&str>)?;
     let settings_item = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
-
-    // Get current size index from state
-    let (current_size, send_scores_enabled, sound_enabled, current_cycle, message_bottles_enabled) = {
-        let guard = state.lock().unwrap_or_else(|p| p.into_inner());
-        (
-            guard.size_index,
-            guard.send_scores,
-            guard.sound_enabled,
-            guard.day_night_cycle.clone(),
-            guard.message_bottles_enabled,
-        )
+    let profile_submenu = Submenu::new(app, "Profile", true)?;
+    let _ = PROFILE_MENU.set(profile_submenu.clone());
+    refresh_profile_menu(app);
+
+    let settings = state
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .settings
+        .clone();
+    let mut setting_items = Vec::new();
+    let mut setting_item = |id: &str, label: &str, key: &'static str, value: Value| {
+        let checked = settings.get(key).as_ref() == Some(&value);
+        let item = CheckMenuItem::with_id(app, id, label, true, checked, None::<&str>)?;
+        setting_items.push((key, value, item.clone()));
+        Ok::<_, tauri::Error>(item)
     };
 
     // Size submenu with check marks
     let mut size_items: Vec<CheckMenuItem<tauri::Wry>> = Vec::new();
     for (i, (label, _, _, _, _)) in SIZE_PRESETS.iter().enumerate() {
         let id = format!("size_{}", i);
-        let checked = i == current_size;
-        let item = CheckMenuItem::with_id(app, &id, *label, true, checked, None::<&str>)?;
-        size_items.push(item);
+        size_items.push(setting_item(&id, *label, "sizeIndex", json!(i))?);
     }
     let size_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = size_items
         .iter()
@@ -82,13 +84,10 @@ pub fn setup_tray(
     let size_submenu = Submenu::with_items(app, "Size", true, &size_refs)?;
 
     let mut cycle_items: Vec<CheckMenuItem<tauri::Wry>> = Vec::new();
-    for (mode, label) in DAY_NIGHT_CYCLES.iter() {
+    for (mode, label) in DayNightCycle::OPTIONS.iter() {
         let id = format!("cycle_{}", mode);
-        let checked = *mode == current_cycle;
-        let item = CheckMenuItem::with_id(app, &id, *label, true, checked, None::<&str>)?;
-        cycle_items.push(item);
+        cycle_items.push(setting_item(&id, *label, "dayNightCycle", json!(mode))?);
     }
-    let _ = DAY_NIGHT_CYCLE_ITEMS.set(cycle_items.clone());
     let cycle_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = cycle_items
         .iter()
         .map(|i| i as &dyn tauri::menu::IsMenuItem<tauri::Wry>)
@@ -106,33 +105,25 @@ pub fn setup_tray(
         None::<&str>,
     )?;
 
-    // Send Scores toggle
-    let send_scores_item = CheckMenuItem::with_id(
-        app,
-        "send_scores",
-        "Send Scores",
-        true,
-        send_scores_enabled,
-        None::<&str>,
-    )?;
-    // Sound toggle
-    let sound_item = CheckMenuItem::with_id(
-        app,
-        "sound_enabled",
-        "Sound",
-        true,
-        sound_enabled,
-        None::<&str>,
-    )?;
-    let message_bottles_item = CheckMenuItem::with_id(
-        app,
+    // Setting toggles
+    let send_scores_item = setting_item("send_scores", "Send Scores", "sendScores", json!(true))?;
+    let sound_item = setting_item("sound_enabled", "Sound", "soundEnabled", json!(true))?;
+    let message_bottles_item = setting_item(
         "message_bottles_enabled",
         "Messages in a Bottle",
+        "messageBottlesEnabled",
+        json!(true),
+    )?;
+    let api_item = setting_item("api_enabled", "Status API", "apiEnabled", json!(true))?;
+    let _ = SETTING_ITEMS.set(setting_items);
+
+    let copy_reef_item = MenuItem::with_id(
+        app,
+        "copy_reef",
+        "Copy Reef to Clipboard",
         true,
-        message_bottles_enabled,
         None::<&str>,
     )?;
-
     let reset_item =
         MenuItem::with_id(app, "reset_aquarium", "Reset Aquarium", true, None::<&str>)?;
     let reset_pos_item =
@@ -145,12 +136,15 @@ pub fn setup_tray(
             &window_toggle_item,
             &collection_item,
             &settings_item,
+            &profile_submenu,
             &size_submenu,
             &cycle_submenu,
             &send_scores_item,
             &sound_item,
             &message_bottles_item,
+            &api_item,
             &autostart_item,
+            &copy_reef_item,
             &reset_item,
             &reset_pos_item,
             &quit_item,
@@ -181,38 +175,17 @@ pub fn setup_tray(
                     std::thread::spawn(move || open_settings_window(&app));
                 }
                 "send_scores" => {
-                    let enabled = {
-                        let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
-                        guard.send_scores = !guard.send_scores;
-                        let _ = crate::save::atomic_save(&guard);
-                        guard.send_scores
-                    };
-                    let _ = send_scores_item.set_checked(enabled);
-                    let _ = app.emit("send-scores", serde_json::json!({ "enabled": enabled }));
+                    let _ = toggle_setting(app, &state, "sendScores");
                 }
                 "sound_enabled" => {
-                    let enabled = {
-                        let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
-                        guard.sound_enabled = !guard.sound_enabled;
-                        let _ = crate::save::atomic_save(&guard);
-                        guard.sound_enabled
-                    };
-                    let _ = sound_item.set_checked(enabled);
-                    let _ = app.emit("sound-settings", serde_json::json!({ "enabled": enabled }));
+                    let _ = toggle_setting(app, &state, "soundEnabled");
                 }
                 "message_bottles_enabled" => {
-                    let enabled = {
-                        let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
-                        guard.message_bottles_enabled = !guard.message_bottles_enabled;
-                        guard.message_bottles_prompted = true;
-                        let _ = crate::save::atomic_save(&guard);
-                        guard.message_bottles_enabled
-                    };
-                    let _ = message_bottles_item.set_checked(enabled);
-                    let _ = app.emit(
-                        "message-bottles-settings",
-                        serde_json::json!({ "enabled": enabled, "prompted": true }),
-                    );
+                    let _ = apply_setting(app, &state, "messageBottlesPrompted", json!(true));
+                    let _ = toggle_setting(app, &state, "messageBottlesEnabled");
+                }
+                "api_enabled" => {
+                    let _ = toggle_setting(app, &state, "apiEnabled");
                 }
                 "autostart" => {
                     let autolaunch = app.autolaunch();
@@ -223,6 +196,9 @@ pub fn setup_tray(
                         let _ = autolaunch.enable();
                     }
                 }
+                "copy_reef" => {
+                    copy_reef(app, &state);
+                }
                 "reset_aquarium" => {
                     reset_aquarium(app, &state);
                 }
@@ -231,32 +207,22 @@ pub fn setup_tray(
                 }
                 "quit" => {
                     let guard = state.lock().unwrap_or_else(|p| p.into_inner());
-                    let _ = crate::save::atomic_save(&guard);
+                    let _ = crate::persist::flush(&guard);
                     drop(guard);
                     app.exit(0);
                 }
                 _ => {
-                    // Check for size_ prefix
-                    if let Some(idx_str) = id.strip_prefix("size_") {
-                        if let Ok(idx) = idx_str.parse::<usize>() {
-                            if idx < SIZE_PRESETS.len() {
-                                // Update checkmarks: uncheck all, check selected
-                                for (i, item) in size_items.iter().enumerate() {
-                                    let _ = item.set_checked(i == idx);
-                                }
-
-                                let _ = apply_size_index(app, &state, idx);
-                            }
-                        }
-                        return;
-                    }
-
-                    if let Some(mode) = id.strip_prefix("cycle_") {
-                        if DAY_NIGHT_CYCLES.iter().any(|(m, _)| *m == mode) {
-                            for (i, (item_mode, _)) in DAY_NIGHT_CYCLES.iter().enumerate() {
-                                let _ = cycle_items[i].set_checked(*item_mode == mode);
-                            }
-                            let _ = apply_day_night_cycle(app, &state, mode.to_string());
+                    if let Some(idx) = id
+                        .strip_prefix("size_")
+                        .and_then(|i| i.parse::<usize>().ok())
+                    {
+                        let _ = apply_setting(app, &state, "sizeIndex", json!(idx));
+                    } else if let Some(mode) = id.strip_prefix("cycle_") {
+                        let _ = apply_setting(app, &state, "dayNightCycle", json!(mode));
+                    } else if let Some(name) = id.strip_prefix("profile_") {
+                        let registry = app.state::<Arc<SharedRegistry>>();
+                        if let Err(e) = switch_profile(app, &state, &registry, name) {
+                            eprintln!("Profiles: {}", e);
                         }
                     }
                 }
@@ -308,7 +274,7 @@ pub fn set_window_visibility(app: &AppHandle, show: bool) {
     update_window_toggle_label(show);
 }
 
-fn toggle_window_visibility(app: &AppHandle) {
+pub fn toggle_window_visibility(app: &AppHandle) {
     let visible = is_window_visible(app);
     set_window_visibility(app, !visible);
 }
@@ -323,22 +289,114 @@ fn resize_tank(app: &AppHandle, cols: u32, rows: u32, width: f64, height: f64) {
     }
 }
 
-pub fn apply_size_index(
+/// Validate and store one setting, then apply its side effects (window
+/// size, tray checkmarks, global shortcuts) and broadcast `settings-changed`.
+pub fn apply_setting(
     app: &AppHandle,
     state: &Arc<SharedState>,
-    idx: usize,
+    key: &str,
+    value: Value,
 ) -> Result<(), String> {
-    if idx >= SIZE_PRESETS.len() {
-        return Err("Invalid size preset".to_string());
+    let (result, settings) = {
+        let mut guard = state.lock().map_err(|e| e.to_string())?;
+        let result = guard.settings.set(key, value);
+        if result.is_ok() {
+            crate::persist::mark(Dirty::Settings);
+        }
+        (result, guard.settings.clone())
+    };
+    // Outside the lock: menu updates hop to the main thread. Always resync,
+    // since clicking a check item toggles it even if the change is rejected.
+    sync_setting_items(&settings);
+    result?;
+
+    if key == "sizeIndex" {
+        let (_, cols, rows, w, h) = SIZE_PRESETS[settings.size_index];
+        resize_tank(app, cols, rows, w, h);
     }
-    let (_, cols, rows, w, h) = SIZE_PRESETS[idx];
+    if crate::hotkeys::Action::ALL
+        .iter()
+        .any(|action| action.setting() == key)
     {
+        crate::hotkeys::register(app, &settings);
+    }
+    let _ = app.emit(
+        "settings-changed",
+        json!({ "key": key, "value": settings.get(key) }),
+    );
+    Ok(())
+}
+
+/// Flip a boolean setting.
+pub fn toggle_setting(app: &AppHandle, state: &Arc<SharedState>, key: &str) -> Result<(), String> {
+    let current = {
+        let guard = state.lock().map_err(|e| e.to_string())?;
+        guard.settings.get(key).and_then(|v| v.as_bool())
+    };
+    let current = current.ok_or_else(|| format!("{} is not a toggle", key))?;
+    apply_setting(app, state, key, json!(!current))
+}
+
+fn sync_setting_items(settings: &Settings) {
+    for (key, value, item) in SETTING_ITEMS.get().into_iter().flatten() {
+        let _ = item.set_checked(settings.get(key).as_ref() == Some(value));
+    }
+}
+
+/// Rebuild the Profile submenu from the profiles on disk.
+pub fn refresh_profile_menu(app: &AppHandle) {
+    let Some(menu) = PROFILE_MENU.get() else {
+        return;
+    };
+    for item in menu.items().unwrap_or_default() {
+        let _ = menu.remove(&item);
+    }
+    let active = crate::profiles::active();
+    for name in crate::profiles::list() {
+        let id = format!("profile_{}", name);
+        let checked = name == active;
+        if let Ok(item) = CheckMenuItem::with_id(app, &id, &name, true, checked, None::<&str>) {
+            let _ = menu.append(&item);
+        }
+    }
+}
+
+/// Save the current profile, load `name` in its place and apply its window
+/// layout. The state lock is held across the swap, so the energy loop never
+/// ticks against a half-switched profile.
+pub fn switch_profile(
+    app: &AppHandle,
+    state: &Arc<SharedState>,
+    registry: &SharedRegistry,
+    name: &str,
+) -> Result<(), String> {
+    let settings = {
         let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.size_index = idx;
-        crate::save::atomic_save(&guard)?;
+        if crate::profiles::active() == name {
+            drop(guard);
+            refresh_profile_menu(app);
+            return Ok(());
+        }
+        crate::persist::flush(&guard)?;
+        crate::profiles::set_active(name)?;
+        let registry = registry.read().map_err(|e| e.to_string())?;
+        *guard = crate::save::load_or_default(&registry);
+        guard.settings.clone()
+    };
+
+    sync_setting_items(&settings);
+    refresh_profile_menu(app);
+    crate::hotkeys::register(app, &settings);
+    if let Some(&(_, cols, rows, w, h)) = SIZE_PRESETS.get(settings.size_index) {
+        resize_tank(app, cols, rows, w, h);
+    }
+    let (x, y) = settings.position;
+    if x != 0.0 || y != 0.0 {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_position(tauri::PhysicalPosition::new(x as i32, y as i32));
+        }
     }
-    resize_tank(app, cols, rows, w, h);
-    let _ = app.emit("size-index", serde_json::json!({ "index": idx }));
+    let _ = app.emit("profile-changed", json!({ "name": name }));
     Ok(())
 }
 
@@ -346,46 +404,33 @@ fn reset_aquarium(app: &AppHandle, state: &Arc<SharedState>) {
     {
         let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
         guard.collection.clear();
+        guard.orphaned = crate::state::OrphanedCreatures::default();
         for val in guard.pool_energy.values_mut() {
             *val = 0;
         }
         guard.total_discoveries = 0;
         guard.pity = crate::state::PityCounters::default();
-        let _ = crate::save::atomic_save(&guard);
+        crate::persist::mark(Dirty::Progress);
     }
     let _ = app.emit("reset-aquarium", ());
 }
 
-pub fn open_collection_from_command(app: &AppHandle) {
-    let app = app.clone();
-    std::thread::spawn(move || open_collection_window(&app));
-}
-
-pub fn apply_day_night_cycle(
-    app: &AppHandle,
-    state: &Arc<SharedState>,
-    cycle: String,
-) -> Result<(), String> {
-    let cycle = match cycle.as_str() {
-        "computer" | "5min" | "10min" | "60min" | "3hours" => cycle,
-        _ => "computer".to_string(),
+/// Put a plain-text still of the reef on the clipboard.
+fn copy_reef(app: &AppHandle, state: &Arc<SharedState>) {
+    let text = {
+        let guard = state.lock().unwrap_or_else(|p| p.into_inner());
+        let registry = app.state::<Arc<SharedRegistry>>();
+        let registry = registry.read().unwrap_or_else(|p| p.into_inner());
+        crate::snapshot::snapshot(&guard, &registry, crate::snapshot::SnapshotFormat::Plain)
     };
-    {
-        let mut guard = state.lock().map_err(|e| e.to_string())?;
-        guard.day_night_cycle = cycle.clone();
-        crate::save::atomic_save(&guard)?;
+    if let Err(e) = app.clipboard().write_text(text) {
+        eprintln!("Tray: failed to copy reef: {}", e);
     }
-    update_day_night_cycle_checks(&cycle);
-    let _ = app.emit("day-night-cycle", serde_json::json!({ "cycle": cycle }));
-    Ok(())
 }
 
-fn update_day_night_cycle_checks(selected_mode: &str) {
-    if let Some(items) = DAY_NIGHT_CYCLE_ITEMS.get() {
-        for (i, (mode, _)) in DAY_NIGHT_CYCLES.iter().enumerate() {
-            let _ = items[i].set_checked(*mode == selected_mode);
-        }
-    }
+pub fn open_collection_from_command(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || open_collection_window(&app));
 }
 
 pub fn reset_aquarium_from_command(app: &AppHandle, state: &Arc<SharedState>) {
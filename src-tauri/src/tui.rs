@@ -0,0 +1,532 @@
+//! Terminal front end (`--tui`). Runs the same reef as the desktop app (the
+//! energy loop, input listener, saves, sync and pack watcher) but draws the
+//! tank with ratatui instead of the webview, for keeping in a tmux pane.
+//!
+//! Tab switches between the tank and the collection browser; q quits.
+use crate::events::EventCalendar;
+use crate::input::InputCounters;
+use crate::notify::Notify;
+use crate::registry::SharedRegistry;
+use crate::report::{self, ReportRow};
+use crate::snapshot::{self, Rgb, Sprite, Swimmer, Tank};
+use crate::state::SharedState;
+use rand::Rng;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const TUI_FLAG: &str = "--tui";
+const FRAME_MS: u64 = 100;
+const TOAST_SECS: u64 = 5;
+/// Spawn delays from tank.js: fast below half the cap, slow above
+const SPAWN_FILL_MS: u64 = 1200;
+const SPAWN_MIN_MS: u64 = 6000;
+const SPAWN_MAX_MS: u64 = 10000;
+
+pub fn requested() -> bool {
+    std::env::args().any(|a| a == TUI_FLAG)
+}
+
+/// Forwards background events to the UI thread.
+struct Channel(Sender<(String, Value)>);
+
+impl Notify for Channel {
+    fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        crate::notify::broadcast(event, &payload);
+        let payload = serde_json::to_value(payload).unwrap_or(Value::Null);
+        let _ = self.0.send((event.to_string(), payload));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Tank,
+    Collection,
+}
+
+struct App {
+    state: Arc<SharedState>,
+    registry: Arc<SharedRegistry>,
+    view: View,
+    swimmers: Vec<Swimmer>,
+    next_spawn: Instant,
+    /// Tank size at the last draw
+    size: (usize, usize),
+    energy: HashMap<String, u32>,
+    threshold: u32,
+    active_events: Vec<String>,
+    toasts: VecDeque<(String, Instant)>,
+    collection: Vec<ReportRow>,
+    selected: ListState,
+    quit: bool,
+}
+
+impl App {
+    fn new(state: Arc<SharedState>, registry: Arc<SharedRegistry>) -> Self {
+        let energy = state
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .pool_energy
+            .clone();
+        let mut app = Self {
+            state,
+            registry,
+            view: View::Tank,
+            swimmers: Vec::new(),
+            next_spawn: Instant::now(),
+            size: (0, 0),
+            energy,
+            threshold: 0,
+            active_events: Vec::new(),
+            toasts: VecDeque::new(),
+            collection: Vec::new(),
+            selected: ListState::default(),
+            quit: false,
+        };
+        app.refresh_collection();
+        app
+    }
+
+    fn refresh_collection(&mut self) {
+        let guard = self.state.lock().unwrap_or_else(|p| p.into_inner());
+        let registry = self.registry.read().unwrap_or_else(|p| p.into_inner());
+        self.collection = report::rows(&guard, &registry);
+        if self.selected.selected().is_none() && !self.collection.is_empty() {
+            self.selected.select(Some(0));
+        }
+    }
+
+    fn toast(&mut self, text: String) {
+        self.toasts.push_back((text, Instant::now()));
+    }
+
+    fn handle_event(&mut self, event: &str, payload: &Value) {
+        match event {
+            "energy-update" => {
+                for pool in ["typing", "click", "audio"] {
+                    if let Some(value) = payload[pool].as_u64() {
+                        self.energy.insert(pool.to_string(), value as u32);
+                    }
+                }
+                if let Some(threshold) = payload["threshold"].as_u64() {
+                    self.threshold = threshold as u32;
+                }
+            }
+            "discovery" => {
+                let id = payload["creatureId"].as_str().unwrap_or_default();
+                let rarity = payload["rarity"].as_str().unwrap_or_default();
+                self.refresh_collection();
+                let name = self
+                    .collection
+                    .iter()
+                    .find(|r| r.id == id)
+                    .map_or(id.to_string(), |r| r.name.clone());
+                if payload["isNew"].as_bool() == Some(true) {
+                    self.toast(format!("New discovery! {} ({})", name, rarity));
+                } else {
+                    self.toast(format!("Another {} ({})", name, rarity));
+                }
+                self.spawn_discovery(id);
+            }
+            "events-changed" => {
+                self.active_events = payload["active"]
+                    .as_array()
+                    .map(|ids| {
+                        ids.iter()
+                            .filter_map(|id| id.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+            }
+            "creatures-reloaded" | "sync-merged" => {
+                self.refresh_collection();
+                // Definitions or counts may have changed under the swimmers
+                self.swimmers.clear();
+                self.next_spawn = Instant::now();
+            }
+            _ => {}
+        }
+    }
+
+    /// Show a fresh discovery in the middle of the tank, like the webview does.
+    fn spawn_discovery(&mut self, id: &str) {
+        let (cols, rows) = self.size;
+        let sprite = {
+            let registry = self.registry.read().unwrap_or_else(|p| p.into_inner());
+            registry.get(id).and_then(Sprite::new)
+        };
+        let Some(sprite) = sprite else {
+            return;
+        };
+        let row = rows.saturating_sub(sprite.height) / 2;
+        let mut swimmer = Swimmer::new(sprite, cols, row, &mut rand::thread_rng());
+        swimmer.col = cols.saturating_sub(swimmer.sprite.width) as f64 / 2.0;
+        swimmer.direction = 1;
+        swimmer.speed = 0.5;
+        self.swimmers.push(swimmer);
+    }
+
+    /// Add a creature if it is time, following tank.js: the rarest owned
+    /// creature first, then species not yet on screen, weighted by rarity.
+    fn maybe_spawn(&mut self, rng: &mut impl Rng) {
+        let (cols, rows) = self.size;
+        let cap = snapshot::size_cap(cols, rows);
+        let now = Instant::now();
+        if now < self.next_spawn || self.swimmers.len() >= cap || cols == 0 {
+            return;
+        }
+        let filling = self.swimmers.len() < cap / 2;
+        self.next_spawn = now
+            + Duration::from_millis(if filling {
+                SPAWN_FILL_MS
+            } else {
+                rng.gen_range(SPAWN_MIN_MS..SPAWN_MAX_MS)
+            });
+
+        let mut owned = {
+            let guard = self.state.lock().unwrap_or_else(|p| p.into_inner());
+            let registry = self.registry.read().unwrap_or_else(|p| p.into_inner());
+            snapshot::visible(&guard, &registry)
+        };
+        let mut on_screen: HashMap<&str, u32> = HashMap::new();
+        for swimmer in &self.swimmers {
+            *on_screen.entry(&swimmer.sprite.id).or_default() += 1;
+        }
+        for (sprite, left) in owned.iter_mut() {
+            *left = left.saturating_sub(on_screen.get(sprite.id.as_str()).copied().unwrap_or(0));
+        }
+        let pick = match snapshot::rarest(&owned) {
+            Some(i) if owned[i].1 > 0 && !on_screen.contains_key(owned[i].0.id.as_str()) => i,
+            _ => {
+                let missing: Vec<(Sprite, u32)> = owned
+                    .iter()
+                    .filter(|(s, _)| !on_screen.contains_key(s.id.as_str()))
+                    .map(|(s, left)| (s.clone(), *left))
+                    .collect();
+                match snapshot::pick_weighted(&missing, rng) {
+                    Some(i) => {
+                        let id = &missing[i].0.id;
+                        match owned.iter().position(|(s, _)| &s.id == id) {
+                            Some(i) => i,
+                            None => return,
+                        }
+                    }
+                    None => match snapshot::pick_weighted(&owned, rng) {
+                        Some(i) => i,
+                        None => return,
+                    },
+                }
+            }
+        };
+        let sprite = owned.swap_remove(pick).0;
+        let Some((min, max)) = snapshot::row_range(&sprite.category, sprite.height, rows) else {
+            return;
+        };
+        let row = rng.gen_range(min..=max);
+        self.swimmers.push(Swimmer::new(sprite, cols, row, rng));
+    }
+
+    fn tick(&mut self, dt: f64, rng: &mut impl Rng) {
+        let (cols, _) = self.size;
+        for swimmer in &mut self.swimmers {
+            swimmer.update(dt);
+        }
+        self.swimmers.retain(|s| !s.offscreen(cols));
+        self.maybe_spawn(rng);
+        let expiry = Duration::from_secs(TOAST_SECS);
+        while self
+            .toasts
+            .front()
+            .is_some_and(|(_, at)| at.elapsed() > expiry)
+        {
+            self.toasts.pop_front();
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Tab | KeyCode::Char('c') => {
+                self.view = match self.view {
+                    View::Tank => {
+                        self.refresh_collection();
+                        View::Collection
+                    }
+                    View::Collection => View::Tank,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.view == View::Collection => {
+                self.selected.select_next();
+            }
+            KeyCode::Up | KeyCode::Char('k') if self.view == View::Collection => {
+                self.selected.select_previous();
+            }
+            KeyCode::Home if self.view == View::Collection => self.selected.select_first(),
+            KeyCode::End if self.view == View::Collection => self.selected.select_last(),
+            _ => {}
+        }
+    }
+}
+
+fn color(rgb: Rgb) -> Color {
+    Color::Rgb(rgb.0, rgb.1, rgb.2)
+}
+
+fn rarity_color(rarity: &str) -> Color {
+    match rarity {
+        "legendary" => Color::Rgb(0xff, 0xc8, 0x57),
+        "epic" => Color::Rgb(0xc7, 0x92, 0xea),
+        "rare" => Color::Rgb(0x6f, 0xb7, 0xff),
+        "uncommon" => Color::Rgb(0x7f, 0xd6, 0x7f),
+        _ => Color::Gray,
+    }
+}
+
+/// The tank grid as styled lines, one span per run of a colour.
+fn tank_lines(tank: &Tank) -> Vec<Line<'static>> {
+    tank.rows()
+        .map(|row| {
+            let mut spans: Vec<Span> = Vec::new();
+            let mut text = String::new();
+            let mut current = None;
+            for cell in row {
+                if cell.color != current && !text.is_empty() {
+                    let style = current.map_or(Style::default(), |c| Style::default().fg(color(c)));
+                    spans.push(Span::styled(std::mem::take(&mut text), style));
+                }
+                current = cell.color;
+                text.push(cell.ch);
+            }
+            let style = current.map_or(Style::default(), |c| Style::default().fg(color(c)));
+            spans.push(Span::styled(text, style));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn energy_line(app: &App, owned: usize, total: usize) -> Line<'static> {
+    let threshold = app.threshold.max(1);
+    let mut spans = Vec::new();
+    for pool in ["typing", "click", "audio"] {
+        let value = app.energy.get(pool).copied().unwrap_or(0);
+        let filled = (value.min(threshold) * 8 / threshold) as usize;
+        spans.push(Span::raw(format!("{} ", pool)));
+        spans.push(Span::styled(
+            "█".repeat(filled),
+            Style::default().fg(Color::Cyan),
+        ));
+        spans.push(Span::styled(
+            "░".repeat(8 - filled),
+            Style::default().fg(Color::DarkGray),
+        ));
+        spans.push(Span::raw("  "));
+    }
+    spans.push(Span::styled(
+        format!("{}/{} species", owned, total),
+        Style::default().fg(Color::Gray),
+    ));
+    if !app.active_events.is_empty() {
+        spans.push(Span::styled(
+            format!("  event: {}", app.active_events.join(", ")),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    spans.push(Span::styled(
+        "  [Tab] collection  [q] quit",
+        Style::default().fg(Color::DarkGray),
+    ));
+    Line::from(spans)
+}
+
+fn draw_tank(frame: &mut Frame, app: &mut App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" ASCII Reef ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    app.size = (inner.width as usize, inner.height as usize);
+
+    let mut tank = Tank::new(app.size.0, app.size.1);
+    for swimmer in &app.swimmers {
+        swimmer.draw(&mut tank);
+    }
+    frame.render_widget(Paragraph::new(tank_lines(&tank)), inner);
+
+    if let Some((text, _)) = app.toasts.back().filter(|_| inner.height >= 5) {
+        let width = (text.chars().count() as u16 + 4).min(inner.width);
+        let toast = Rect::new(inner.x + (inner.width - width) / 2, inner.y + 1, width, 3);
+        frame.render_widget(Clear, toast);
+        frame.render_widget(
+            Paragraph::new(text.as_str())
+                .alignment(Alignment::Center)
+                .style(Style::default().add_modifier(Modifier::BOLD))
+                .block(Block::default().borders(Borders::ALL)),
+            toast,
+        );
+    }
+}
+
+fn draw_collection(frame: &mut Frame, app: &mut App, area: Rect) {
+    let [list_area, detail_area] =
+        Layout::horizontal([Constraint::Length(36), Constraint::Min(0)]).areas(area);
+    let items: Vec<ListItem> = app
+        .collection
+        .iter()
+        .map(|row| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<24}", row.name),
+                    Style::default().fg(rarity_color(&row.rarity)),
+                ),
+                Span::raw(format!(" x{}", row.count)),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" Collection "))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, &mut app.selected);
+
+    let block = Block::default().borders(Borders::ALL);
+    let Some(row) = app.selected.selected().and_then(|i| app.collection.get(i)) else {
+        frame.render_widget(
+            Paragraph::new("Nothing discovered yet. Keep typing!").block(block),
+            detail_area,
+        );
+        return;
+    };
+    let sprite_style = row
+        .color
+        .as_deref()
+        .and_then(report::parse_color)
+        .map(|(r, g, b)| Style::default().fg(Color::Rgb(r, g, b)))
+        .unwrap_or_default();
+    let mut lines = vec![
+        Line::styled(
+            row.name.clone(),
+            Style::default()
+                .fg(rarity_color(&row.rarity))
+                .add_modifier(Modifier::BOLD),
+        ),
+        Line::raw(format!("{} · {} pool", row.rarity, row.pool)),
+        Line::raw(format!("Owned: {}", row.count)),
+        Line::raw(format!("First seen: {}", row.first_seen)),
+    ];
+    if let Some(event) = &row.event {
+        lines.push(Line::raw(format!("Event: {}", event)));
+    }
+    for sprite in &row.frames {
+        lines.push(Line::raw(""));
+        // `!` is an occlusion tile, drawn as nothing
+        lines.extend(
+            sprite
+                .iter()
+                .map(|l| Line::styled(l.replace('!', " "), sprite_style)),
+        );
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), detail_area);
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    match app.view {
+        View::Tank => draw_tank(frame, app, main),
+        View::Collection => draw_collection(frame, app, main),
+    }
+    let total = app
+        .registry
+        .read()
+        .unwrap_or_else(|p| p.into_inner())
+        .all()
+        .count();
+    let owned = app.collection.len();
+    frame.render_widget(Paragraph::new(energy_line(app, owned, total)), footer);
+}
+
+/// Send stderr to a log in the save dir so background warnings do not
+/// scribble over the screen.
+#[cfg(unix)]
+fn redirect_stderr() {
+    use std::os::fd::AsRawFd;
+    let path = crate::save::save_dir().join("tui.log");
+    if let Ok(log) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+    {
+        // SAFETY: duplicating a valid descriptor onto stderr
+        unsafe {
+            libc::dup2(log.as_raw_fd(), libc::STDERR_FILENO);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn redirect_stderr() {}
+
+/// Run the reef in the terminal until the user quits.
+pub fn run(
+    state: Arc<SharedState>,
+    registry: Arc<SharedRegistry>,
+    counters: Arc<InputCounters>,
+    audio_active: Arc<AtomicBool>,
+    calendar: Arc<EventCalendar>,
+) {
+    redirect_stderr();
+    let (tx, rx) = mpsc::channel();
+
+    crate::persist::start_persistence_thread(state.clone());
+    crate::input::start_input_listener(counters.clone());
+    crate::audio::start_audio_detection(audio_active.clone());
+    crate::energy::start_energy_loop(
+        Channel(tx.clone()),
+        state.clone(),
+        counters,
+        audio_active,
+        registry.clone(),
+        calendar,
+    );
+    crate::registry::start_registry_watcher(Channel(tx.clone()), registry.clone(), state.clone());
+    crate::sync::start_sync_loop(Channel(tx), state.clone(), registry.clone());
+    crate::ctl::start_terminal_server(state.clone(), registry.clone());
+
+    let mut terminal = ratatui::init();
+    let mut app = App::new(state.clone(), registry);
+    let mut rng = rand::thread_rng();
+    let mut last = Instant::now();
+    while !app.quit {
+        if let Err(e) = terminal.draw(|frame| draw(frame, &mut app)) {
+            eprintln!("TUI: draw failed: {}", e);
+            break;
+        }
+        if event::poll(Duration::from_millis(FRAME_MS)).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code, key.modifiers);
+                }
+            }
+        }
+        while let Ok((event, payload)) = rx.try_recv() {
+            app.handle_event(&event, &payload);
+        }
+        let now = Instant::now();
+        app.tick(now.duration_since(last).as_secs_f64(), &mut rng);
+        last = now;
+    }
+    ratatui::restore();
+
+    let guard = state.lock().unwrap_or_else(|p| p.into_inner());
+    if let Err(e) = crate::persist::flush(&guard) {
+        eprintln!("TUI: failed to save on exit: {}", e);
+    }
+}
@@ -0,0 +1,108 @@
+//! Consistency checks for creature definition data. Shared with `build.rs`
+//! (via `#[path]`), so it must only depend on `serde_json`.
+use serde_json::Value;
+use std::collections::HashSet;
+
+pub const POOLS: &[&str] = &["typing", "click", "audio"];
+pub const RARITIES: &[&str] = &["common", "uncommon", "rare", "epic", "legendary"];
+
+/// Validate one creature definition, appending any problems to `errors`.
+/// `seen` tracks ids across every file checked so duplicates are caught too.
+pub fn check_creature(
+    def: &Value,
+    source: &str,
+    seen: &mut HashSet<String>,
+    errors: &mut Vec<String>,
+) {
+    let id = match def["id"].as_str() {
+        Some(id) if !id.is_empty() => id.to_string(),
+        _ => {
+            errors.push(format!("{}: creature without an id: {}", source, def));
+            return;
+        }
+    };
+    let mut err = |msg: String| errors.push(format!("{}: {}: {}", source, id, msg));
+
+    if !seen.insert(id.clone()) {
+        err("duplicate id".to_string());
+    }
+    if def["name"].as_str().is_none_or(str::is_empty) {
+        err("missing name".to_string());
+    }
+    match def["pool"].as_str() {
+        Some(pool) if POOLS.contains(&pool) => {}
+        other => err(format!(
+            "unknown pool {:?} (expected one of {:?})",
+            other, POOLS
+        )),
+    }
+    match def["rarity"].as_str() {
+        Some(rarity) if RARITIES.contains(&rarity) => {}
+        other => err(format!(
+            "unknown rarity {:?} (expected one of {:?})",
+            other, RARITIES
+        )),
+    }
+
+    let (Some(width), Some(height)) = (def["width"].as_u64(), def["height"].as_u64()) else {
+        err("width and height must be positive integers".to_string());
+        return;
+    };
+    let Some(frames) = def["frames"].as_array().filter(|f| !f.is_empty()) else {
+        err("frames must be a non-empty array".to_string());
+        return;
+    };
+    let mut widest = 0u64;
+    for (i, frame) in frames.iter().enumerate() {
+        let Some(rows) = frame.as_array() else {
+            err(format!("frame {} is not an array of rows", i));
+            continue;
+        };
+        if rows.len() as u64 != height {
+            err(format!(
+                "frame {} has {} rows but height is {}",
+                i,
+                rows.len(),
+                height
+            ));
+        }
+        for (r, row) in rows.iter().enumerate() {
+            let Some(row) = row.as_str() else {
+                err(format!("frame {} row {} is not a string", i, r));
+                continue;
+            };
+            let len = row.chars().count() as u64;
+            if len > width {
+                err(format!(
+                    "frame {} row {} is {} chars wide but width is {}",
+                    i, r, len, width
+                ));
+            }
+            widest = widest.max(len);
+        }
+    }
+    if widest < width {
+        err(format!(
+            "width is {} but the widest sprite row is {}",
+            width, widest
+        ));
+    }
+}
+
+/// Every pool×rarity combination must have at least one creature, otherwise a
+/// discovery roll landing on that bucket has nothing to award.
+pub fn check_buckets(defs: &[Value], source: &str, errors: &mut Vec<String>) {
+    for pool in POOLS {
+        for rarity in RARITIES {
+            let any = defs
+                .iter()
+                .any(|d| d["pool"].as_str() == Some(pool) && d["rarity"].as_str() == Some(rarity));
+            if !any {
+                errors.push(format!(
+                    "{}: no {} creatures in the {} pool",
+                    source, rarity, pool
+                ));
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ import { computeUnlocked, getAchievements } from "./simulation/achievements.js";
 
 const { invoke } = window.__TAURI__.core;
 const { getCurrentWindow } = window.__TAURI__.window;
+const { listen } = window.__TAURI__.event;
 
 const RARITY_ORDER = ["legendary", "epic", "rare", "uncommon", "common"];
 
@@ -198,7 +199,7 @@ async function init() {
 
   // Save hidden fish to Rust state + emit to aquarium
   function persistHiddenFish() {
-    invoke("set_hidden_creatures", { ids: [...hiddenFish] })
+    invoke("set_setting", { key: "hiddenCreatures", value: [...hiddenFish] })
       .catch(e => console.error("Failed to save hidden creatures:", e));
   }
 
@@ -403,6 +404,9 @@ async function init() {
     updateHiddenToggle();
   });
 
+  // Another profile's collection: reload from scratch
+  listen("profile-changed", () => window.location.reload());
+
   // ── Initial render ──────────────────────────────────────────────────────────
   renderFish();
   renderAchievements();
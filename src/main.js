@@ -245,10 +245,8 @@ async function ensureMessageBottleOptIn() {
   syncMessageBottleSpawnState();
   await refreshMessageBottleReceiveState();
   try {
-    await invoke("set_message_bottles_preferences", {
-      enabled: accepted,
-      prompted: true,
-    });
+    await invoke("set_setting", { key: "messageBottlesPrompted", value: true });
+    await invoke("set_setting", { key: "messageBottlesEnabled", value: accepted });
   } catch (e) {
     console.error("Failed to persist message bottle preferences:", e);
   }
@@ -272,6 +270,19 @@ function showInfoMessageBottleModal(title, text) {
   );
 }
 
+// Shown once after a load had to fall back from a damaged save
+function showLoadNotice({ recoveredFrom, quarantined }) {
+  const escape = (s) => s.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
+  const kept = quarantined.length
+    ? `<br><br>Damaged files were kept as:<br>${quarantined.map(escape).join("<br>")}`
+    : "";
+  if (recoveredFrom) {
+    showInfoMessageBottleModal("Save Recovered", `Your reef was restored from ${escape(recoveredFrom)}.${kept}`);
+  } else {
+    showInfoMessageBottleModal("Save Unreadable", `Your save could not be read, so a new reef was started.${kept}`);
+  }
+}
+
 async function handleComposeBottle() {
   showMessageBottleModal(
     `
@@ -461,9 +472,26 @@ function updateHelpButtonState() {
   }
 }
 
+// Merge event-only and pack creatures (served by the backend) into allSprites
+async function loadExtraSprites() {
+  try {
+    const eventCreatures = await invoke("get_event_creatures");
+    allSprites = { ...parseAllCreatures(eventCreatures), ...allSprites };
+  } catch (e) {
+    console.error("Failed to load event creatures:", e);
+  }
+  try {
+    const packCreatures = await invoke("get_pack_creatures");
+    allSprites = { ...allSprites, ...parseAllCreatures(packCreatures) };
+  } catch (e) {
+    console.error("Failed to load creature packs:", e);
+  }
+}
+
 async function init() {
   // Parse creature sprites
   allSprites = parseAllCreatures(creaturesData);
+  await loadExtraSprites();
 
   // Initialize canvas
   const canvas = document.getElementById("tank");
@@ -619,63 +647,61 @@ async function init() {
     }
   });
 
-  // Listen for send-scores toggle from tray
-  listen("send-scores", (event) => {
-    sendScoresEnabled = !!event.payload.enabled;
-    setLeaderboardEnabled(sendScoresEnabled);
-    updateAchievements(lastCollection, false);
-    if (sendScoresEnabled) {
-      initLeaderboard();
-      const score = calculateScore();
-      const unique = getUniqueCount();
-      submitScore(score, unique);
-    }
-  });
-
-  listen("sound-settings", (event) => {
-    if (typeof event.payload.enabled === "boolean") {
-      soundEnabled = event.payload.enabled;
-      updateAchievements(lastCollection, false);
-    }
-    if (typeof event.payload.volume === "number") {
-      musicVolume = event.payload.volume;
-    }
-    applySoundSettings();
-  });
-
-  listen("size-index", (event) => {
-    if (typeof event.payload.index === "number") {
-      sizeIndex = event.payload.index;
-      updateAchievements(lastCollection, false);
-    }
-  });
-
-  listen("day-night-cycle", (event) => {
-    if (typeof event.payload.cycle === "string") {
-      dayNightCycle = event.payload.cycle;
-      setDayNightCycle(dayNightCycle);
-    }
-  });
-
-  listen("close-behavior", (event) => {
-    if (typeof event.payload.behavior === "string") {
-      closeBehavior = event.payload.behavior;
+  // One event for every setting change, from the tray or the settings window
+  listen("settings-changed", (event) => {
+    const { key, value } = event.payload;
+    switch (key) {
+      case "sendScores":
+        sendScoresEnabled = !!value;
+        setLeaderboardEnabled(sendScoresEnabled);
+        updateAchievements(lastCollection, false);
+        if (sendScoresEnabled) {
+          initLeaderboard();
+          const score = calculateScore();
+          const unique = getUniqueCount();
+          submitScore(score, unique);
+        }
+        break;
+      case "soundEnabled":
+        soundEnabled = !!value;
+        updateAchievements(lastCollection, false);
+        applySoundSettings();
+        break;
+      case "musicVolume":
+        musicVolume = value;
+        applySoundSettings();
+        break;
+      case "sizeIndex":
+        sizeIndex = value;
+        updateAchievements(lastCollection, false);
+        break;
+      case "dayNightCycle":
+        dayNightCycle = value;
+        setDayNightCycle(dayNightCycle);
+        break;
+      case "closeBehavior":
+        closeBehavior = value;
+        break;
+      case "hiddenCreatures":
+        setHiddenCreatures(value || []);
+        break;
+      case "messageBottlesEnabled":
+        messageBottlesEnabled = !!value;
+        syncMessageBottleSpawnState();
+        refreshMessageBottleReceiveState();
+        break;
+      case "messageBottlesPrompted":
+        messageBottlesPrompted = !!value;
+        break;
     }
   });
 
-  listen("hidden-fish-changed", (event) => {
-    setHiddenCreatures(event.payload.ids || []);
-  });
-
-  listen("message-bottles-settings", (event) => {
-    if (typeof event.payload.enabled === "boolean") {
-      messageBottlesEnabled = event.payload.enabled;
-    }
-    if (event.payload.prompted === true) {
-      messageBottlesPrompted = true;
-    }
-    syncMessageBottleSpawnState();
-    refreshMessageBottleReceiveState();
+  // Creature definitions changed on disk (pack installed/edited)
+  listen("creatures-reloaded", async () => {
+    allSprites = parseAllCreatures(creaturesData);
+    await loadExtraSprites();
+    initTank(allSprites, lastCollection || {});
+    clearCreatures();
   });
 
   // Listen for tank resize events from tray menu
@@ -686,21 +712,37 @@ async function init() {
     clearCreatures();
   });
 
-  // Listen for reset-aquarium event from tray menu
-  listen("reset-aquarium", async () => {
+  // Reload the collection after it was reset, merged from an imported save
+  // or synced with other devices
+  async function reloadCollection() {
     let col = {};
     try {
       const state = await invoke("get_state");
       col = state.collection || {};
       updateCollection(col);
     } catch (e) {
-      console.error("Failed to fetch state after reset:", e);
+      console.error("Failed to reload collection:", e);
     }
     lastCollection = col;
     updateAchievements(col, false);
     clearCreatures();
     setFirstRunState(col);
-  });
+  }
+
+  // A different profile is a different reef: start over from its state
+  listen("profile-changed", () => window.location.reload());
+
+  try {
+    const notice = await invoke("take_load_notice");
+    if (notice) showLoadNotice(notice);
+  } catch (e) {
+    console.error("Failed to check load notice:", e);
+  }
+
+  // Listen for reset-aquarium event from tray menu
+  listen("reset-aquarium", reloadCollection);
+  listen("save-imported", reloadCollection);
+  listen("sync-merged", reloadCollection);
 
   async function performCloseAction(behavior) {
     if (behavior === "close") {
@@ -749,7 +791,7 @@ async function init() {
       const choice = await promptCloseBehavior();
       closeBehavior = choice;
       try {
-        await invoke("set_close_behavior", { behavior: choice });
+        await invoke("set_setting", { key: "closeBehavior", value: choice });
       } catch (e) {
         console.error("Failed to persist close behavior:", e);
       }
@@ -2,31 +2,19 @@
 
 const { invoke } = window.__TAURI__.core;
 const { getCurrentWindow } = window.__TAURI__.window;
+const { listen } = window.__TAURI__.event;
 
-const SIZE_LABELS = [
-  "Small",
-  "Medium",
-  "Medium Tall",
-  "Large",
-  "Large Tall",
-  "Wide",
-  "Wide Tall",
-  "Extra Wide",
-];
-
-const DAY_NIGHT_OPTIONS = [
-  { value: "computer", label: "Computer Time (Default)" },
-  { value: "5min", label: "5 min day / 5 min night" },
-  { value: "10min", label: "10 min day / 10 min night" },
-  { value: "60min", label: "60 min day / 60 min night" },
-  { value: "3hours", label: "3 hours day / 3 hours night" },
-];
+// Persist one setting; the backend validates it against the settings schema
+function setSetting(key, value) {
+  return invoke("set_setting", { key, value });
+}
 
-const CLOSE_BEHAVIOR_OPTIONS = [
-  { value: "ask", label: "Ask Me Each Time" },
-  { value: "hide", label: "Hide to Tray" },
-  { value: "close", label: "Close App" },
-];
+function optionsHtml(schema, key) {
+  const spec = schema.find((s) => s.key === key);
+  return (spec?.options || []).map((option) =>
+    `<option value="${option.value}">${option.label}</option>`
+  ).join("");
+}
 
 function debounce(fn, ms) {
   let id;
@@ -49,6 +37,14 @@ function withConfirmation(btn, label, action) {
   });
 }
 
+// Global shortcut actions and the setting holding each accelerator
+const HOTKEYS = [
+  { action: "toggleWindow", key: "hotkeyToggleWindow" },
+  { action: "toggleDragMode", key: "hotkeyToggleDragMode" },
+  { action: "openCollection", key: "hotkeyOpenCollection" },
+  { action: "muteMusic", key: "hotkeyMuteMusic" },
+];
+
 const LS_COLOR_MODE = "ascii-reef-color-mode";
 const LS_SETTINGS_TAB = "ascii-reef-settings-tab";
 
@@ -62,9 +58,11 @@ async function init() {
   let sizeIndex = 1;
   let dayNightCycle = "computer";
   let closeBehavior = "ask";
+  let syncDir = "";
   let messageBottlesEnabled = false;
   let autostartEnabled = false;
   let windowVisible = true;
+  const hotkeys = {};
   let currentTab = localStorage.getItem(LS_SETTINGS_TAB) || "aquarium";
 
   root.innerHTML = `
@@ -114,6 +112,45 @@ async function init() {
       </div>
     </div>
     <div class="settings-tab-panel" id="settings-panel-system">
+      <div class="settings-section">
+        <div class="settings-select">
+          <label for="profile-select">Profile</label>
+          <select id="profile-select"></select>
+        </div>
+        <div class="settings-profile-edit">
+          <input id="profile-name-input" type="text" placeholder="profile-name" maxlength="32" />
+          <button id="create-profile-btn" type="button">Create</button>
+          <button id="delete-profile-btn" type="button" class="danger">Delete</button>
+        </div>
+        <div class="settings-hint">Each profile has its own reef, backups and settings. Type a name to create or delete one.</div>
+      </div>
+      <div class="settings-section">
+        <div class="settings-select">
+          <label for="save-location-input">Save Location</label>
+        </div>
+        <div class="settings-profile-edit">
+          <input id="save-location-input" type="text" placeholder="/path/to/empty/folder" />
+          <button id="move-save-location-btn" type="button">Move</button>
+        </div>
+        <div id="save-location-hint" class="settings-hint"></div>
+      </div>
+      <div class="settings-section">
+        <div class="settings-select">
+          <label for="sync-dir-input">Sync Folder</label>
+        </div>
+        <div class="settings-profile-edit">
+          <input id="sync-dir-input" type="text" placeholder="/path/to/shared/folder" />
+          <button id="sync-now-btn" type="button">Sync Now</button>
+        </div>
+        <div id="sync-status" class="settings-hint">Share discoveries with your other devices through a folder they all sync (e.g. Syncthing or a network drive). Leave empty to turn off.</div>
+      </div>
+      <div class="settings-section">
+        <div class="settings-select">
+          <label>Keyboard Shortcuts</label>
+        </div>
+        <div id="hotkeys-list"></div>
+        <div class="settings-hint">Work from any app, e.g. Ctrl+Alt+R. Leave empty to turn one off.</div>
+      </div>
       <div class="settings-section">
         <div class="settings-select">
           <label for="close-behavior-select">When Clicking X</label>
@@ -188,6 +225,52 @@ async function init() {
   const resetPositionBtn = document.getElementById("reset-position-btn");
   const resetAquariumBtn = document.getElementById("reset-aquarium-btn");
   const quitAppBtn = document.getElementById("quit-app-btn");
+  const profileSelect = document.getElementById("profile-select");
+  const profileNameInput = document.getElementById("profile-name-input");
+  const createProfileBtn = document.getElementById("create-profile-btn");
+  const deleteProfileBtn = document.getElementById("delete-profile-btn");
+  const saveLocationInput = document.getElementById("save-location-input");
+  const moveSaveLocationBtn = document.getElementById("move-save-location-btn");
+  const saveLocationHint = document.getElementById("save-location-hint");
+  const syncDirInput = document.getElementById("sync-dir-input");
+  const syncNowBtn = document.getElementById("sync-now-btn");
+  const syncStatus = document.getElementById("sync-status");
+  const hotkeysList = document.getElementById("hotkeys-list");
+
+  async function refreshProfiles() {
+    if (!profileSelect) return;
+    try {
+      const { active, profiles } = await invoke("list_profiles");
+      profileSelect.innerHTML = profiles.map((name) =>
+        `<option value="${name}">${name}</option>`
+      ).join("");
+      profileSelect.value = active;
+    } catch (e) {
+      console.error("Failed to list profiles:", e);
+    }
+  }
+
+  // Overrides from outside the app cannot be changed from here
+  const LOCATION_SOURCES = {
+    flag: "Set by --save-dir on the command line.",
+    env: "Set by the ASCII_REEF_SAVE_DIR environment variable.",
+    portable: "Portable mode: data stays next to the app.",
+  };
+
+  async function refreshSaveLocation() {
+    if (!saveLocationInput || !saveLocationHint) return;
+    try {
+      const { path, source } = await invoke("get_save_location");
+      saveLocationInput.value = path;
+      const fixed = LOCATION_SOURCES[source];
+      saveLocationInput.disabled = !!fixed;
+      if (moveSaveLocationBtn) moveSaveLocationBtn.disabled = !!fixed;
+      saveLocationHint.textContent = fixed
+        || "Moves your reef, profiles and packs to an empty folder.";
+    } catch (e) {
+      console.error("Failed to get save location:", e);
+    }
+  }
 
   function applyStateToUi() {
     if (sendScoresToggle) sendScoresToggle.checked = sendScores;
@@ -200,6 +283,11 @@ async function init() {
     if (sizeSelect) sizeSelect.value = String(sizeIndex);
     if (dayNightCycleSelect) dayNightCycleSelect.value = dayNightCycle;
     if (closeBehaviorSelect) closeBehaviorSelect.value = closeBehavior;
+    if (syncDirInput && document.activeElement !== syncDirInput) syncDirInput.value = syncDir;
+    for (const { key } of HOTKEYS) {
+      const input = document.getElementById(`${key}-input`);
+      if (input && document.activeElement !== input) input.value = hotkeys[key] || "";
+    }
     if (messageBottlesToggle) messageBottlesToggle.checked = messageBottlesEnabled;
     if (autostartToggle) autostartToggle.checked = autostartEnabled;
     syncToggleWindowLabel();
@@ -210,29 +298,43 @@ async function init() {
     toggleWindowBtn.textContent = windowVisible ? "Hide Aquarium" : "Show Aquarium";
   }
 
-  if (sizeSelect) {
-    sizeSelect.innerHTML = SIZE_LABELS.map((label, idx) =>
-      `<option value="${idx}">${label}</option>`
-    ).join("");
-  }
-
-  if (dayNightCycleSelect) {
-    dayNightCycleSelect.innerHTML = DAY_NIGHT_OPTIONS.map((option) =>
-      `<option value="${option.value}">${option.label}</option>`
-    ).join("");
+  let schema = [];
+  try {
+    schema = await invoke("get_settings_schema");
+  } catch (e) {
+    console.error("Failed to load settings schema:", e);
   }
-
-  if (closeBehaviorSelect) {
-    closeBehaviorSelect.innerHTML = CLOSE_BEHAVIOR_OPTIONS.map((option) =>
-      `<option value="${option.value}">${option.label}</option>`
-    ).join("");
+  if (sizeSelect) sizeSelect.innerHTML = optionsHtml(schema, "sizeIndex");
+  if (dayNightCycleSelect) dayNightCycleSelect.innerHTML = optionsHtml(schema, "dayNightCycle");
+  if (closeBehaviorSelect) closeBehaviorSelect.innerHTML = optionsHtml(schema, "closeBehavior");
+  if (hotkeysList) {
+    hotkeysList.innerHTML = HOTKEYS.map(({ key }) => {
+      const label = schema.find((s) => s.key === key)?.label || key;
+      return `<div class="settings-profile-edit">
+          <label for="${key}-input">${label}</label>
+          <input id="${key}-input" type="text" placeholder="None" />
+        </div>`;
+    }).join("");
+    for (const { action, key } of HOTKEYS) {
+      const input = document.getElementById(`${key}-input`);
+      input?.addEventListener("change", async (e) => {
+        const accelerator = e.target.value.trim();
+        try {
+          await invoke("set_hotkey", { action, accelerator });
+        } catch (e) {
+          console.error("Failed to set shortcut:", e);
+          window.alert(e);
+          input.value = hotkeys[key] || "";
+        }
+      });
+    }
   }
 
   if (sendScoresToggle) {
     sendScoresToggle.addEventListener("change", async (e) => {
       const enabled = !!e.target.checked;
       try {
-        await invoke("set_send_scores", { enabled });
+        await setSetting("sendScores", enabled);
       } catch (e) {
         console.error("Failed to set send scores:", e);
       }
@@ -243,7 +345,7 @@ async function init() {
     soundToggle.addEventListener("change", async (e) => {
       const enabled = !!e.target.checked;
       try {
-        await invoke("set_sound_enabled", { enabled });
+        await setSetting("soundEnabled", enabled);
       } catch (e) {
         console.error("Failed to set sound enabled:", e);
       }
@@ -253,7 +355,7 @@ async function init() {
   if (volumeSlider && volumeValue) {
     const debouncedSetVolume = debounce(async (value) => {
       try {
-        await invoke("set_music_volume", { volume: value });
+        await setSetting("musicVolume", value);
       } catch (err) {
         console.error("Failed to set music volume:", err);
       }
@@ -270,7 +372,7 @@ async function init() {
       const idx = parseInt(e.target.value, 10);
       if (Number.isNaN(idx)) return;
       try {
-        await invoke("set_size_index", { idx });
+        await setSetting("sizeIndex", idx);
       } catch (e) {
         console.error("Failed to set size index:", e);
       }
@@ -281,7 +383,7 @@ async function init() {
     dayNightCycleSelect.addEventListener("change", async (e) => {
       const cycle = e.target.value;
       try {
-        await invoke("set_day_night_cycle", { cycle });
+        await setSetting("dayNightCycle", cycle);
       } catch (e) {
         console.error("Failed to set day/night cycle:", e);
       }
@@ -298,13 +400,91 @@ async function init() {
     closeBehaviorSelect.addEventListener("change", async (e) => {
       const behavior = e.target.value;
       try {
-        await invoke("set_close_behavior", { behavior });
+        await setSetting("closeBehavior", behavior);
       } catch (e) {
         console.error("Failed to set close behavior:", e);
       }
     });
   }
 
+  if (profileSelect) {
+    profileSelect.addEventListener("change", async (e) => {
+      try {
+        await invoke("switch_profile", { name: e.target.value });
+      } catch (e) {
+        console.error("Failed to switch profile:", e);
+        refreshProfiles();
+      }
+    });
+  }
+
+  if (createProfileBtn && profileNameInput) {
+    createProfileBtn.addEventListener("click", async () => {
+      const name = profileNameInput.value.trim();
+      if (!name) return;
+      try {
+        await invoke("create_profile", { name });
+        profileNameInput.value = "";
+      } catch (e) {
+        console.error("Failed to create profile:", e);
+        window.alert(e);
+      }
+    });
+  }
+
+  if (deleteProfileBtn && profileNameInput) {
+    withConfirmation(deleteProfileBtn, "Delete", async () => {
+      const name = profileNameInput.value.trim();
+      if (!name) return;
+      try {
+        await invoke("delete_profile", { name });
+        profileNameInput.value = "";
+      } catch (e) {
+        console.error("Failed to delete profile:", e);
+        window.alert(e);
+      }
+    });
+  }
+
+  if (moveSaveLocationBtn && saveLocationInput) {
+    withConfirmation(moveSaveLocationBtn, "Move", async () => {
+      const path = saveLocationInput.value.trim();
+      if (!path) return;
+      try {
+        await invoke("move_save_location", { path });
+      } catch (e) {
+        console.error("Failed to move save location:", e);
+        window.alert(e);
+      }
+      refreshSaveLocation();
+    });
+  }
+
+  if (syncDirInput) {
+    syncDirInput.addEventListener("change", async (e) => {
+      const dir = e.target.value.trim();
+      try {
+        await setSetting("syncDir", dir);
+      } catch (e) {
+        console.error("Failed to set sync folder:", e);
+        window.alert(e);
+        syncDirInput.value = syncDir;
+      }
+    });
+  }
+
+  if (syncNowBtn && syncStatus) {
+    syncNowBtn.addEventListener("click", async () => {
+      try {
+        const { devices } = await invoke("sync_now");
+        syncStatus.textContent = `Synced with ${devices - 1} other device${devices === 2 ? "" : "s"}.`;
+      } catch (e) {
+        console.error("Failed to sync:", e);
+        syncStatus.textContent = String(e);
+      }
+    });
+  }
+
   if (autostartToggle) {
     autostartToggle.addEventListener("change", async (e) => {
       const enabled = !!e.target.checked;
@@ -321,7 +501,8 @@ async function init() {
     messageBottlesToggle.addEventListener("change", async (e) => {
       const enabled = !!e.target.checked;
       try {
-        await invoke("set_message_bottles_preferences", { enabled, prompted: true });
+        await setSetting("messageBottlesPrompted", true);
+        await setSetting("messageBottlesEnabled", enabled);
       } catch (e) {
         console.error("Failed to set message bottles preferences:", e);
         messageBottlesToggle.checked = !enabled;
@@ -382,6 +563,32 @@ async function init() {
     });
   }
 
+  // Everything on this page belongs to the old profile
+  listen("profile-changed", () => window.location.reload());
+  listen("profiles-changed", refreshProfiles);
+  refreshProfiles();
+  listen("save-location-changed", refreshSaveLocation);
+  refreshSaveLocation();
+
+  // Keep the form in sync with changes made from the tray or the aquarium
+  listen("settings-changed", (event) => {
+    const { key, value } = event.payload;
+    switch (key) {
+      case "sendScores": sendScores = !!value; break;
+      case "soundEnabled": soundEnabled = !!value; break;
+      case "musicVolume": musicVolume = value; break;
+      case "sizeIndex": sizeIndex = value; break;
+      case "dayNightCycle": dayNightCycle = value; break;
+      case "closeBehavior": closeBehavior = value; break;
+      case "syncDir": syncDir = value; break;
+      case "messageBottlesEnabled": messageBottlesEnabled = !!value; break;
+      default:
+        if (!HOTKEYS.some((h) => h.key === key)) return;
+        hotkeys[key] = value;
+    }
+    applyStateToUi();
+  });
+
   try {
     const state = await invoke("get_state");
     sendScores = !!state.sendScores;
@@ -390,7 +597,9 @@ async function init() {
     sizeIndex = typeof state.sizeIndex === "number" ? state.sizeIndex : sizeIndex;
     dayNightCycle = typeof state.dayNightCycle === "string" ? state.dayNightCycle : "computer";
     closeBehavior = typeof state.closeBehavior === "string" ? state.closeBehavior : "ask";
+    syncDir = typeof state.syncDir === "string" ? state.syncDir : "";
     messageBottlesEnabled = !!state.messageBottlesEnabled;
+    for (const { key } of HOTKEYS) hotkeys[key] = typeof state[key] === "string" ? state[key] : "";
     autostartEnabled = !!state.autostartEnabled;
     windowVisible = state.windowVisible !== false;
     applyStateToUi();